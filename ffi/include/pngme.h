@@ -0,0 +1,143 @@
+#ifndef PNGME_H
+#define PNGME_H
+
+/* Generated by cbindgen, do not edit it by hand. Regenerate it with
+   PNGME_UPDATE_HEADER=1 cargo test -p pngme-ffi --test header */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Result of a fallible function, every `PngMeError` has its own status
+typedef enum PngmeStatus {
+  PNGME_STATUS_OK = 0,
+  // A required pointer argument is null
+  PNGME_STATUS_NULL_POINTER = 1,
+  // A string argument is not valid UTF-8
+  PNGME_STATUS_INVALID_UTF8 = 2,
+  // The chunk index is not lower than `pngme_png_chunk_count`
+  PNGME_STATUS_INDEX_OUT_OF_RANGE = 3,
+  PNGME_STATUS_CRITICAL_CHUNK = 10,
+  PNGME_STATUS_INVALID_ANIMATION = 11,
+  PNGME_STATUS_INVALID_CHUNK_LENGTH = 12,
+  PNGME_STATUS_INVALID_BYTES = 13,
+  PNGME_STATUS_INVALID_CHUNK_TYPE = 14,
+  PNGME_STATUS_INVALID_CRC = 15,
+  PNGME_STATUS_INVALID_CHUNK_DATA = 16,
+  PNGME_STATUS_INVALID_CHUNK_ORDER = 17,
+  PNGME_STATUS_INVALID_EXIF = 18,
+  PNGME_STATUS_INVALID_ICC_PROFILE = 19,
+  PNGME_STATUS_INVALID_IMAGE_DATA = 20,
+  PNGME_STATUS_INVALID_PNG_FILE_HEADER = 21,
+  PNGME_STATUS_INVALID_XMP = 22,
+  PNGME_STATUS_PAYLOAD_NOT_FOUND = 23,
+  PNGME_STATUS_PAYLOAD_TOO_LARGE = 24,
+  PNGME_STATUS_TRUNCATED_CHUNK = 25,
+  PNGME_STATUS_UNEXISTENT_CHUNK_TYPE = 26,
+  PNGME_STATUS_UNABLE_TO_CREATE_FILE = 27,
+  PNGME_STATUS_UNABLE_TO_WRITE_OUTPUT_FILE = 28,
+  // Any other error, see `pngme_last_error`
+  PNGME_STATUS_OTHER = 99,
+} PngmeStatus;
+
+// A PNG file opened with `pngme_png_from_bytes`
+typedef struct PngmePng PngmePng;
+
+// Parses the `len` bytes of a PNG file, on success `*png` holds a handle
+// which must be released with `pngme_png_free`.
+//
+// # Safety
+//
+// `bytes` must point to `len` readable bytes and `png` must be writable.
+enum PngmeStatus pngme_png_from_bytes(const uint8_t *bytes, size_t len, struct PngmePng **png);
+
+// Releases a handle, null is ignored.
+//
+// # Safety
+//
+// `png` must be null or a handle which was not released yet.
+void pngme_png_free(struct PngmePng *png);
+
+// Retrieves the number of chunks, `0` for a null handle.
+//
+// # Safety
+//
+// `png` must be null or a valid handle.
+size_t pngme_png_chunk_count(const struct PngmePng *png);
+
+// Writes the type of the chunk at `index` as a NUL terminated string into
+// `chunk_type`, which must hold at least 5 bytes.
+//
+// # Safety
+//
+// `png` must be a valid handle and `chunk_type` must point to 5 writable
+// bytes.
+enum PngmeStatus pngme_png_chunk_type(const struct PngmePng *png, size_t index, char *chunk_type);
+
+// Points `*data` to the data of the chunk at `index` and writes its length
+// into `*len`, the data is owned by the handle.
+//
+// # Safety
+//
+// `png` must be a valid handle, `data` and `len` must be writable.
+enum PngmeStatus pngme_png_chunk_data(const struct PngmePng *png,
+                                      size_t index,
+                                      const uint8_t **data,
+                                      size_t *len);
+
+// Writes the CRC stored in the chunk at `index` into `*crc`.
+//
+// # Safety
+//
+// `png` must be a valid handle and `crc` must be writable.
+enum PngmeStatus pngme_png_chunk_crc(const struct PngmePng *png, size_t index, uint32_t *crc);
+
+// Adds a chunk holding `len` bytes of `data` in the position the PNG
+// specification requires for its type, chunks without ordering
+// constraints are added right before `IEND`. Critical and APNG chunk types
+// are rejected with `PNGME_STATUS_CRITICAL_CHUNK` and
+// `PNGME_STATUS_INVALID_ANIMATION` as they would break the image.
+//
+// # Safety
+//
+// `png` must be a valid handle, `chunk_type` a NUL terminated string and
+// `data` must point to `len` readable bytes, it may be null when `len`
+// is `0`.
+enum PngmeStatus pngme_png_add_chunk(struct PngmePng *png,
+                                     const char *chunk_type,
+                                     const uint8_t *data,
+                                     size_t len);
+
+// Removes the first chunk with the provided type.
+//
+// # Safety
+//
+// `png` must be a valid handle and `chunk_type` a NUL terminated string.
+enum PngmeStatus pngme_png_remove_chunk(struct PngmePng *png, const char *chunk_type);
+
+// Serializes the PNG file into a new buffer, `*bytes` and `*len` must be
+// released with `pngme_bytes_free`.
+//
+// # Safety
+//
+// `png` must be a valid handle, `bytes` and `len` must be writable.
+enum PngmeStatus pngme_png_to_bytes(const struct PngmePng *png, uint8_t **bytes, size_t *len);
+
+// Releases a buffer returned by `pngme_png_to_bytes`, null is ignored.
+//
+// # Safety
+//
+// `bytes` and `len` must be the values written by `pngme_png_to_bytes` and
+// the buffer must not be released yet.
+void pngme_bytes_free(uint8_t *bytes, size_t len);
+
+// Retrieves a static description of the status.
+const char *pngme_status_message(enum PngmeStatus status);
+
+// Retrieves the message of the last error on the calling thread, null if
+// no function failed yet. The message is valid until the next failure.
+const char *pngme_last_error(void);
+
+#endif  /* PNGME_H */
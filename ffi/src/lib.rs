@@ -0,0 +1,526 @@
+//! C API over the chunk logic of `pngme`, see `include/pngme.h`.
+//!
+//! A PNG file is opened from a buffer into an opaque `PngmePng` handle which
+//! must be released with `pngme_png_free`. Fallible functions return a
+//! `PngmeStatus`, `PNGME_STATUS_OK` on success, and keep a message
+//! describing the failure available through `pngme_last_error`.
+//!
+//! Pointers to data owned by a handle, such as the data of a chunk, are
+//! only valid until the handle is modified or released.
+use std::cell::RefCell;
+use std::convert::TryFrom;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::ptr;
+use std::slice;
+use std::str::FromStr;
+
+use pngme::chunk::Chunk;
+use pngme::chunk_type::ChunkType;
+use pngme::error::PngMeError;
+use pngme::png::Png;
+use pngme::Error;
+
+/// A PNG file opened with `pngme_png_from_bytes`
+pub struct PngmePng {
+    png: Png,
+}
+
+/// Result of a fallible function, every `PngMeError` has its own status
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PngmeStatus {
+    Ok = 0,
+    /// A required pointer argument is null
+    NullPointer = 1,
+    /// A string argument is not valid UTF-8
+    InvalidUtf8 = 2,
+    /// The chunk index is not lower than `pngme_png_chunk_count`
+    IndexOutOfRange = 3,
+    CriticalChunk = 10,
+    InvalidAnimation = 11,
+    InvalidChunkLength = 12,
+    InvalidBytes = 13,
+    InvalidChunkType = 14,
+    InvalidCrc = 15,
+    InvalidChunkData = 16,
+    InvalidChunkOrder = 17,
+    InvalidExif = 18,
+    InvalidIccProfile = 19,
+    InvalidImageData = 20,
+    InvalidPngFileHeader = 21,
+    InvalidXmp = 22,
+    PayloadNotFound = 23,
+    PayloadTooLarge = 24,
+    TruncatedChunk = 25,
+    UnexistentChunkType = 26,
+    UnableToCreateFile = 27,
+    UnableToWriteOutputFile = 28,
+    /// Any other error, see `pngme_last_error`
+    Other = 99,
+}
+
+impl From<&PngMeError> for PngmeStatus {
+    fn from(error: &PngMeError) -> Self {
+        match error {
+            PngMeError::CriticalChunk(_) => PngmeStatus::CriticalChunk,
+            PngMeError::InvalidAnimation(_) => PngmeStatus::InvalidAnimation,
+            PngMeError::InvalidChunkLength(_) => PngmeStatus::InvalidChunkLength,
+            PngMeError::InvalidBytes(_) => PngmeStatus::InvalidBytes,
+            PngMeError::InvalidChunkType => PngmeStatus::InvalidChunkType,
+            PngMeError::InvalidCRC => PngmeStatus::InvalidCrc,
+            PngMeError::InvalidChunkData(_) => PngmeStatus::InvalidChunkData,
+            PngMeError::InvalidChunkOrder(_) => PngmeStatus::InvalidChunkOrder,
+            PngMeError::InvalidExif(_) => PngmeStatus::InvalidExif,
+            PngMeError::InvalidIccProfile(_) => PngmeStatus::InvalidIccProfile,
+            PngMeError::InvalidImageData(_) => PngmeStatus::InvalidImageData,
+            PngMeError::InvalidPNGFileHeader => PngmeStatus::InvalidPngFileHeader,
+            PngMeError::InvalidXmp(_) => PngmeStatus::InvalidXmp,
+            PngMeError::PayloadNotFound(_) => PngmeStatus::PayloadNotFound,
+            PngMeError::PayloadTooLarge(_, _) => PngmeStatus::PayloadTooLarge,
+            PngMeError::TruncatedChunk => PngmeStatus::TruncatedChunk,
+            PngMeError::UnexistentChunkType => PngmeStatus::UnexistentChunkType,
+            PngMeError::UnableToCreateFile(_) => PngmeStatus::UnableToCreateFile,
+            PngMeError::UnableToWriteOutputFile(_) => PngmeStatus::UnableToWriteOutputFile,
+        }
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Keeps the message of the error for `pngme_last_error` and returns its
+/// status
+fn fail(error: Error) -> PngmeStatus {
+    let status = error
+        .downcast_ref::<PngMeError>()
+        .map(PngmeStatus::from)
+        .unwrap_or(PngmeStatus::Other);
+
+    set_last_error(&error.to_string());
+
+    status
+}
+
+fn fail_with(status: PngmeStatus) -> PngmeStatus {
+    set_last_error(status_message(status).trim_end_matches('\0'));
+
+    status
+}
+
+fn set_last_error(message: &str) {
+    let message = CString::new(message).unwrap_or_default();
+
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = Some(message));
+}
+
+/// NUL terminated description of the status
+fn status_message(status: PngmeStatus) -> &'static str {
+    match status {
+        PngmeStatus::Ok => "Ok\0",
+        PngmeStatus::NullPointer => "A required pointer argument is null\0",
+        PngmeStatus::InvalidUtf8 => "A string argument is not valid UTF-8\0",
+        PngmeStatus::IndexOutOfRange => "The chunk index is out of range\0",
+        PngmeStatus::CriticalChunk => "The chunk is critical\0",
+        PngmeStatus::InvalidAnimation => "Invalid animation\0",
+        PngmeStatus::InvalidChunkLength => "Invalid chunk type length\0",
+        PngmeStatus::InvalidBytes => "The chunk type has invalid bytes\0",
+        PngmeStatus::InvalidChunkType => "Invalid chunk type provided\0",
+        PngmeStatus::InvalidCrc => "CRC IEEE Checksum didn't matched\0",
+        PngmeStatus::InvalidChunkData => "Invalid chunk data\0",
+        PngmeStatus::InvalidChunkOrder => "Invalid chunk order\0",
+        PngmeStatus::InvalidExif => "Invalid Exif profile\0",
+        PngmeStatus::InvalidIccProfile => "Invalid ICC profile\0",
+        PngmeStatus::InvalidImageData => "Invalid image data\0",
+        PngmeStatus::InvalidPngFileHeader => "Invalid PNG file header\0",
+        PngmeStatus::InvalidXmp => "Invalid XMP packet\0",
+        PngmeStatus::PayloadNotFound => "No payload was found\0",
+        PngmeStatus::PayloadTooLarge => "The payload is too large\0",
+        PngmeStatus::TruncatedChunk => "The chunk is truncated\0",
+        PngmeStatus::UnexistentChunkType => "The provided chunk type doesn't exists\0",
+        PngmeStatus::UnableToCreateFile => "Unable to create file\0",
+        PngmeStatus::UnableToWriteOutputFile => "Unable to write output file\0",
+        PngmeStatus::Other => "Unexpected error\0",
+    }
+}
+
+unsafe fn chunk_type_arg(chunk_type: *const c_char) -> Result<ChunkType, PngmeStatus> {
+    if chunk_type.is_null() {
+        return Err(fail_with(PngmeStatus::NullPointer));
+    }
+
+    let chunk_type = CStr::from_ptr(chunk_type)
+        .to_str()
+        .map_err(|_| fail_with(PngmeStatus::InvalidUtf8))?;
+
+    ChunkType::from_str(chunk_type).map_err(|e| fail(Box::new(e)))
+}
+
+unsafe fn chunk_at<'a>(png: *const PngmePng, index: usize) -> Result<&'a Chunk, PngmeStatus> {
+    match png.as_ref() {
+        Some(png) => png
+            .png
+            .chunks()
+            .get(index)
+            .ok_or_else(|| fail_with(PngmeStatus::IndexOutOfRange)),
+        None => Err(fail_with(PngmeStatus::NullPointer)),
+    }
+}
+
+/// Parses the `len` bytes of a PNG file, on success `*png` holds a handle
+/// which must be released with `pngme_png_free`.
+///
+/// # Safety
+///
+/// `bytes` must point to `len` readable bytes and `png` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn pngme_png_from_bytes(
+    bytes: *const u8,
+    len: usize,
+    png: *mut *mut PngmePng,
+) -> PngmeStatus {
+    if bytes.is_null() || png.is_null() {
+        return fail_with(PngmeStatus::NullPointer);
+    }
+
+    match Png::try_from(slice::from_raw_parts(bytes, len)) {
+        Ok(parsed) => {
+            *png = Box::into_raw(Box::new(PngmePng { png: parsed }));
+
+            PngmeStatus::Ok
+        }
+        Err(e) => fail(e),
+    }
+}
+
+/// Releases a handle, null is ignored.
+///
+/// # Safety
+///
+/// `png` must be null or a handle which was not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn pngme_png_free(png: *mut PngmePng) {
+    if !png.is_null() {
+        drop(Box::from_raw(png));
+    }
+}
+
+/// Retrieves the number of chunks, `0` for a null handle.
+///
+/// # Safety
+///
+/// `png` must be null or a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn pngme_png_chunk_count(png: *const PngmePng) -> usize {
+    png.as_ref().map(|png| png.png.chunks().len()).unwrap_or(0)
+}
+
+/// Writes the type of the chunk at `index` as a NUL terminated string into
+/// `chunk_type`, which must hold at least 5 bytes.
+///
+/// # Safety
+///
+/// `png` must be a valid handle and `chunk_type` must point to 5 writable
+/// bytes.
+#[no_mangle]
+pub unsafe extern "C" fn pngme_png_chunk_type(
+    png: *const PngmePng,
+    index: usize,
+    chunk_type: *mut c_char,
+) -> PngmeStatus {
+    if chunk_type.is_null() {
+        return fail_with(PngmeStatus::NullPointer);
+    }
+
+    match chunk_at(png, index) {
+        Ok(chunk) => {
+            let bytes = chunk.chunk_type().bytes();
+
+            ptr::copy_nonoverlapping(bytes.as_ptr() as *const c_char, chunk_type, 4);
+            *chunk_type.add(4) = 0;
+
+            PngmeStatus::Ok
+        }
+        Err(status) => status,
+    }
+}
+
+/// Points `*data` to the data of the chunk at `index` and writes its length
+/// into `*len`, the data is owned by the handle.
+///
+/// # Safety
+///
+/// `png` must be a valid handle, `data` and `len` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn pngme_png_chunk_data(
+    png: *const PngmePng,
+    index: usize,
+    data: *mut *const u8,
+    len: *mut usize,
+) -> PngmeStatus {
+    if data.is_null() || len.is_null() {
+        return fail_with(PngmeStatus::NullPointer);
+    }
+
+    match chunk_at(png, index) {
+        Ok(chunk) => {
+            *data = chunk.data().as_ptr();
+            *len = chunk.data().len();
+
+            PngmeStatus::Ok
+        }
+        Err(status) => status,
+    }
+}
+
+/// Writes the CRC stored in the chunk at `index` into `*crc`.
+///
+/// # Safety
+///
+/// `png` must be a valid handle and `crc` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn pngme_png_chunk_crc(
+    png: *const PngmePng,
+    index: usize,
+    crc: *mut u32,
+) -> PngmeStatus {
+    if crc.is_null() {
+        return fail_with(PngmeStatus::NullPointer);
+    }
+
+    match chunk_at(png, index) {
+        Ok(chunk) => {
+            *crc = chunk.crc();
+
+            PngmeStatus::Ok
+        }
+        Err(status) => status,
+    }
+}
+
+/// Adds a chunk holding `len` bytes of `data` in the position the PNG
+/// specification requires for its type, chunks without ordering
+/// constraints are added right before `IEND`. Critical and APNG chunk types
+/// are rejected with `PNGME_STATUS_CRITICAL_CHUNK` and
+/// `PNGME_STATUS_INVALID_ANIMATION` as they would break the image.
+///
+/// # Safety
+///
+/// `png` must be a valid handle, `chunk_type` a NUL terminated string and
+/// `data` must point to `len` readable bytes, it may be null when `len`
+/// is `0`.
+#[no_mangle]
+pub unsafe extern "C" fn pngme_png_add_chunk(
+    png: *mut PngmePng,
+    chunk_type: *const c_char,
+    data: *const u8,
+    len: usize,
+) -> PngmeStatus {
+    let png = match png.as_mut() {
+        Some(png) => png,
+        None => return fail_with(PngmeStatus::NullPointer),
+    };
+    let chunk_type = match chunk_type_arg(chunk_type) {
+        Ok(chunk_type) => chunk_type,
+        Err(status) => return status,
+    };
+    let data = match (data.is_null(), len) {
+        (true, 0) => Vec::new(),
+        (true, _) => return fail_with(PngmeStatus::NullPointer),
+        (false, _) => slice::from_raw_parts(data, len).to_vec(),
+    };
+
+    match png.png.insert_message(Chunk::new(chunk_type, data)) {
+        Ok(()) => PngmeStatus::Ok,
+        Err(e) => fail(e),
+    }
+}
+
+/// Removes the first chunk with the provided type.
+///
+/// # Safety
+///
+/// `png` must be a valid handle and `chunk_type` a NUL terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn pngme_png_remove_chunk(
+    png: *mut PngmePng,
+    chunk_type: *const c_char,
+) -> PngmeStatus {
+    let png = match png.as_mut() {
+        Some(png) => png,
+        None => return fail_with(PngmeStatus::NullPointer),
+    };
+    let chunk_type = match chunk_type_arg(chunk_type) {
+        Ok(chunk_type) => chunk_type,
+        Err(status) => return status,
+    };
+
+    match png.png.remove_chunk(&chunk_type.to_string()) {
+        Ok(_) => PngmeStatus::Ok,
+        Err(e) => fail(e),
+    }
+}
+
+/// Serializes the PNG file into a new buffer, `*bytes` and `*len` must be
+/// released with `pngme_bytes_free`.
+///
+/// # Safety
+///
+/// `png` must be a valid handle, `bytes` and `len` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn pngme_png_to_bytes(
+    png: *const PngmePng,
+    bytes: *mut *mut u8,
+    len: *mut usize,
+) -> PngmeStatus {
+    let png = match png.as_ref() {
+        Some(png) => png,
+        None => return fail_with(PngmeStatus::NullPointer),
+    };
+
+    if bytes.is_null() || len.is_null() {
+        return fail_with(PngmeStatus::NullPointer);
+    }
+
+    let buffer = png.png.as_bytes().into_boxed_slice();
+
+    *len = buffer.len();
+    *bytes = Box::into_raw(buffer) as *mut u8;
+
+    PngmeStatus::Ok
+}
+
+/// Releases a buffer returned by `pngme_png_to_bytes`, null is ignored.
+///
+/// # Safety
+///
+/// `bytes` and `len` must be the values written by `pngme_png_to_bytes` and
+/// the buffer must not be released yet.
+#[no_mangle]
+pub unsafe extern "C" fn pngme_bytes_free(bytes: *mut u8, len: usize) {
+    if !bytes.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(bytes, len)));
+    }
+}
+
+/// Retrieves a static description of the status.
+#[no_mangle]
+pub extern "C" fn pngme_status_message(status: PngmeStatus) -> *const c_char {
+    status_message(status).as_ptr() as *const c_char
+}
+
+/// Retrieves the message of the last error on the calling thread, null if
+/// no function failed yet. The message is valid until the next failure.
+#[no_mangle]
+pub extern "C" fn pngme_last_error() -> *const c_char {
+    LAST_ERROR.with(|last_error| match last_error.borrow().as_ref() {
+        Some(message) => message.as_ptr(),
+        None => ptr::null(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn testing_bytes() -> Vec<u8> {
+        let chunks = ["IHDR", "IDAT", "IEND"]
+            .iter()
+            .map(|chunk_type| Chunk::new(ChunkType::from_str(chunk_type).unwrap(), Vec::new()))
+            .collect();
+
+        Png::from_chunks(chunks).as_bytes()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let bytes = testing_bytes();
+        let mut png = ptr::null_mut();
+        let mut output = ptr::null_mut();
+        let mut len = 0;
+
+        unsafe {
+            assert_eq!(
+                pngme_png_from_bytes(bytes.as_ptr(), bytes.len(), &mut png),
+                PngmeStatus::Ok
+            );
+            assert_eq!(
+                pngme_png_add_chunk(
+                    png,
+                    "ruSt\0".as_ptr() as *const c_char,
+                    b"hidden".as_ptr(),
+                    6
+                ),
+                PngmeStatus::Ok
+            );
+            assert_eq!(pngme_png_chunk_count(png), 4);
+            assert_eq!(
+                pngme_png_remove_chunk(png, "ruSt\0".as_ptr() as *const c_char),
+                PngmeStatus::Ok
+            );
+            assert_eq!(
+                pngme_png_to_bytes(png, &mut output, &mut len),
+                PngmeStatus::Ok
+            );
+            assert_eq!(slice::from_raw_parts(output, len), bytes.as_slice());
+
+            pngme_bytes_free(output, len);
+            pngme_png_free(png);
+        }
+    }
+
+    #[test]
+    fn test_errors_map_to_status() {
+        let mut bytes = testing_bytes();
+        let mut png = ptr::null_mut();
+
+        // Last CRC byte of the `IHDR` chunk
+        bytes[8 + 12 - 1] ^= 0xff;
+
+        unsafe {
+            assert_eq!(
+                pngme_png_from_bytes(bytes.as_ptr(), bytes.len(), &mut png),
+                PngmeStatus::InvalidCrc
+            );
+            assert_eq!(
+                CStr::from_ptr(pngme_last_error()).to_str().unwrap(),
+                PngMeError::InvalidCRC.to_string()
+            );
+            assert_eq!(
+                pngme_png_from_bytes(b"GIF89a".as_ptr(), 6, &mut png),
+                PngmeStatus::InvalidPngFileHeader
+            );
+            assert_eq!(
+                pngme_png_remove_chunk(ptr::null_mut(), ptr::null()),
+                PngmeStatus::NullPointer
+            );
+        }
+
+        assert!(png.is_null());
+    }
+
+    #[test]
+    fn test_add_critical_chunk() {
+        let bytes = testing_bytes();
+        let mut png = ptr::null_mut();
+
+        unsafe {
+            assert_eq!(
+                pngme_png_from_bytes(bytes.as_ptr(), bytes.len(), &mut png),
+                PngmeStatus::Ok
+            );
+            assert_eq!(
+                pngme_png_add_chunk(png, "IDAT\0".as_ptr() as *const c_char, ptr::null(), 0),
+                PngmeStatus::CriticalChunk
+            );
+            assert_eq!(
+                pngme_png_add_chunk(png, "fcTL\0".as_ptr() as *const c_char, ptr::null(), 0),
+                PngmeStatus::InvalidAnimation
+            );
+            assert_eq!(pngme_png_chunk_count(png), 3);
+
+            pngme_png_free(png);
+        }
+    }
+}
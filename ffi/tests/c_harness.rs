@@ -0,0 +1,44 @@
+//! Builds `harness.c` against the shared library and runs it on the
+//! `docs/dice.png` image
+#![cfg(unix)]
+
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+
+#[test]
+fn test_c_harness() {
+    let crate_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    // Integration tests run from `target/<profile>/deps` which is where the
+    // shared library is built before it is copied into `target/<profile>`
+    let deps_dir = env::current_exe().unwrap().parent().unwrap().to_path_buf();
+    let harness = deps_dir.join("pngme_harness");
+    let compiler = env::var("CC").unwrap_or_else(|_| String::from("cc"));
+
+    let status = Command::new(compiler)
+        .arg(crate_dir.join("tests").join("harness.c"))
+        .arg("-I")
+        .arg(crate_dir.join("include"))
+        .arg("-L")
+        .arg(&deps_dir)
+        .arg(format!("-Wl,-rpath,{}", deps_dir.display()))
+        .arg("-lpngme_ffi")
+        .arg("-o")
+        .arg(&harness)
+        .status()
+        .unwrap();
+
+    assert!(status.success());
+
+    let output = Command::new(&harness)
+        .arg(crate_dir.join("..").join("docs").join("dice.png"))
+        .output()
+        .unwrap();
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("IHDR 13 "));
+}
@@ -0,0 +1,110 @@
+/*
+ * Exercises the C API on the PNG file passed as first argument: its chunks
+ * are listed, a message chunk is added, serialized, read back and removed.
+ */
+#include <stdio.h>
+#include <string.h>
+
+#include "pngme.h"
+
+#define CHECK(call)                                                          \
+    do {                                                                     \
+        PngmeStatus status = (call);                                         \
+        if (status != PNGME_STATUS_OK) {                                     \
+            fprintf(stderr, "%s:%d: %s failed with %d: %s\n", __FILE__,      \
+                    __LINE__, #call, status, pngme_last_error());            \
+            return 1;                                                        \
+        }                                                                    \
+    } while (0)
+
+#define EXPECT(condition)                                                    \
+    do {                                                                     \
+        if (!(condition)) {                                                  \
+            fprintf(stderr, "%s:%d: expected %s\n", __FILE__, __LINE__,      \
+                    #condition);                                             \
+            return 1;                                                        \
+        }                                                                    \
+    } while (0)
+
+static int read_file(const char *path, uint8_t *buffer, size_t capacity,
+                     size_t *len) {
+    FILE *file = fopen(path, "rb");
+
+    if (file == NULL) {
+        return 0;
+    }
+
+    *len = fread(buffer, 1, capacity, file);
+    fclose(file);
+
+    return *len < capacity;
+}
+
+int main(int argc, char **argv) {
+    static uint8_t file[1 << 20];
+    const char message[] = "This is a secret message!";
+    size_t file_len = 0;
+    PngmePng *png = NULL;
+    PngmePng *copy = NULL;
+    uint8_t *bytes = NULL;
+    size_t len = 0;
+    char chunk_type[5];
+    const uint8_t *data = NULL;
+    size_t data_len = 0;
+    uint32_t crc = 0;
+    size_t count;
+    size_t i;
+
+    EXPECT(argc == 2);
+    EXPECT(read_file(argv[1], file, sizeof(file), &file_len));
+
+    CHECK(pngme_png_from_bytes(file, file_len, &png));
+
+    count = pngme_png_chunk_count(png);
+    EXPECT(count >= 3);
+
+    for (i = 0; i < count; i++) {
+        CHECK(pngme_png_chunk_type(png, i, chunk_type));
+        CHECK(pngme_png_chunk_data(png, i, &data, &data_len));
+        CHECK(pngme_png_chunk_crc(png, i, &crc));
+        printf("%s %zu %u\n", chunk_type, data_len, crc);
+    }
+
+    CHECK(pngme_png_chunk_type(png, 0, chunk_type));
+    EXPECT(strcmp(chunk_type, "IHDR") == 0);
+    EXPECT(pngme_png_chunk_type(png, count, chunk_type) ==
+           PNGME_STATUS_INDEX_OUT_OF_RANGE);
+
+    CHECK(pngme_png_add_chunk(png, "ruSt", (const uint8_t *)message,
+                              strlen(message)));
+    EXPECT(pngme_png_chunk_count(png) == count + 1);
+    CHECK(pngme_png_to_bytes(png, &bytes, &len));
+    CHECK(pngme_png_from_bytes(bytes, len, &copy));
+    pngme_bytes_free(bytes, len);
+
+    /* The message is added right before `IEND` */
+    CHECK(pngme_png_chunk_type(copy, count - 1, chunk_type));
+    EXPECT(strcmp(chunk_type, "ruSt") == 0);
+    CHECK(pngme_png_chunk_data(copy, count - 1, &data, &data_len));
+    EXPECT(data_len == strlen(message));
+    EXPECT(memcmp(data, message, data_len) == 0);
+
+    CHECK(pngme_png_remove_chunk(copy, "ruSt"));
+    EXPECT(pngme_png_remove_chunk(copy, "ruSt") ==
+           PNGME_STATUS_UNEXISTENT_CHUNK_TYPE);
+    EXPECT(pngme_png_add_chunk(copy, "ruS", NULL, 0) ==
+           PNGME_STATUS_INVALID_CHUNK_LENGTH);
+    EXPECT(strcmp(pngme_last_error(),
+                  "The chunk ruS have a 3 length, the max length is 4") == 0);
+    EXPECT(pngme_png_chunk_count(copy) == count);
+
+    EXPECT(pngme_png_from_bytes(file, 4, &png) ==
+           PNGME_STATUS_INVALID_PNG_FILE_HEADER);
+    EXPECT(strcmp(pngme_status_message(PNGME_STATUS_INVALID_CRC),
+                  "CRC IEEE Checksum didn't matched") == 0);
+
+    pngme_png_free(copy);
+    pngme_png_free(png);
+
+    return 0;
+}
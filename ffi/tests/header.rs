@@ -0,0 +1,27 @@
+//! Checks `include/pngme.h` matches the C API, the header is committed so
+//! building the crate never writes into the source tree
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+#[test]
+fn test_header_is_up_to_date() {
+    let crate_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let header_path = crate_dir.join("include").join("pngme.h");
+    let config = cbindgen::Config::from_file(crate_dir.join("cbindgen.toml")).unwrap();
+    let mut header = Vec::new();
+
+    cbindgen::generate_with_config(&crate_dir, config)
+        .expect("Unable to generate the C header")
+        .write(&mut header);
+
+    if env::var_os("PNGME_UPDATE_HEADER").is_some() {
+        fs::write(&header_path, &header).unwrap();
+    }
+
+    assert!(
+        fs::read(&header_path).unwrap() == header,
+        "include/pngme.h is out of date, regenerate it with \
+         PNGME_UPDATE_HEADER=1 cargo test -p pngme-ffi --test header"
+    );
+}
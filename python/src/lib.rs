@@ -0,0 +1,292 @@
+//! Python bindings over the library, built into the `pngme` module with
+//! maturin. See `python/tests` for how they are used.
+use std::convert::TryFrom;
+use std::str::FromStr;
+
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+use pngme::chunk::Chunk;
+use pngme::chunk_type::ChunkType;
+use pngme::error;
+use pngme::png::Png;
+use pngme::stego;
+use pngme::text::Text;
+
+create_exception!(
+    pngme,
+    PngMeError,
+    PyException,
+    "Base class of every pngme error"
+);
+create_exception!(pngme, CriticalChunkError, PngMeError);
+create_exception!(pngme, InvalidAnimationError, PngMeError);
+create_exception!(pngme, InvalidChunkLengthError, PngMeError);
+create_exception!(pngme, InvalidBytesError, PngMeError);
+create_exception!(pngme, InvalidChunkTypeError, PngMeError);
+create_exception!(pngme, InvalidCrcError, PngMeError);
+create_exception!(pngme, InvalidChunkDataError, PngMeError);
+create_exception!(pngme, InvalidChunkOrderError, PngMeError);
+create_exception!(pngme, InvalidExifError, PngMeError);
+create_exception!(pngme, InvalidIccProfileError, PngMeError);
+create_exception!(pngme, InvalidImageDataError, PngMeError);
+create_exception!(pngme, InvalidPngFileHeaderError, PngMeError);
+create_exception!(pngme, InvalidXmpError, PngMeError);
+create_exception!(pngme, PayloadNotFoundError, PngMeError);
+create_exception!(pngme, PayloadTooLargeError, PngMeError);
+create_exception!(pngme, TruncatedChunkError, PngMeError);
+create_exception!(pngme, UnexistentChunkTypeError, PngMeError);
+create_exception!(pngme, UnableToCreateFileError, PngMeError);
+create_exception!(pngme, UnableToWriteOutputFileError, PngMeError);
+
+/// Raises the exception matching the `PngMeError` variant, any other error
+/// raises the `PngMeError` base class
+fn to_py_err(error: pngme::Error) -> PyErr {
+    let message = error.to_string();
+
+    match error.downcast_ref::<error::PngMeError>() {
+        Some(error::PngMeError::CriticalChunk(_)) => CriticalChunkError::new_err(message),
+        Some(error::PngMeError::InvalidAnimation(_)) => InvalidAnimationError::new_err(message),
+        Some(error::PngMeError::InvalidChunkLength(_)) => InvalidChunkLengthError::new_err(message),
+        Some(error::PngMeError::InvalidBytes(_)) => InvalidBytesError::new_err(message),
+        Some(error::PngMeError::InvalidChunkType) => InvalidChunkTypeError::new_err(message),
+        Some(error::PngMeError::InvalidCRC) => InvalidCrcError::new_err(message),
+        Some(error::PngMeError::InvalidChunkData(_)) => InvalidChunkDataError::new_err(message),
+        Some(error::PngMeError::InvalidChunkOrder(_)) => InvalidChunkOrderError::new_err(message),
+        Some(error::PngMeError::InvalidExif(_)) => InvalidExifError::new_err(message),
+        Some(error::PngMeError::InvalidIccProfile(_)) => InvalidIccProfileError::new_err(message),
+        Some(error::PngMeError::InvalidImageData(_)) => InvalidImageDataError::new_err(message),
+        Some(error::PngMeError::InvalidPNGFileHeader) => {
+            InvalidPngFileHeaderError::new_err(message)
+        }
+        Some(error::PngMeError::InvalidXmp(_)) => InvalidXmpError::new_err(message),
+        Some(error::PngMeError::PayloadNotFound(_)) => PayloadNotFoundError::new_err(message),
+        Some(error::PngMeError::PayloadTooLarge(_, _)) => PayloadTooLargeError::new_err(message),
+        Some(error::PngMeError::TruncatedChunk) => TruncatedChunkError::new_err(message),
+        Some(error::PngMeError::UnexistentChunkType) => UnexistentChunkTypeError::new_err(message),
+        Some(error::PngMeError::UnableToCreateFile(_)) => UnableToCreateFileError::new_err(message),
+        Some(error::PngMeError::UnableToWriteOutputFile(_)) => {
+            UnableToWriteOutputFileError::new_err(message)
+        }
+        None => PngMeError::new_err(message),
+    }
+}
+
+fn chunk_type(chunk_type: &str) -> PyResult<ChunkType> {
+    ChunkType::from_str(chunk_type).map_err(|e| to_py_err(Box::new(e)))
+}
+
+/// A chunk of a PNG file, chunks are copied out of the `Png` they belong to
+#[pyclass(name = "Chunk", module = "pngme")]
+#[derive(Clone)]
+struct PyChunk {
+    chunk: Chunk,
+}
+
+#[pymethods]
+impl PyChunk {
+    #[getter(r#type)]
+    fn chunk_type(&self) -> String {
+        self.chunk.chunk_type().to_string()
+    }
+
+    #[getter]
+    fn data<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, self.chunk.data())
+    }
+
+    #[getter]
+    fn crc(&self) -> u32 {
+        self.chunk.crc()
+    }
+
+    fn __len__(&self) -> usize {
+        self.chunk.data().len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Chunk(type='{}', length={})",
+            self.chunk.chunk_type(),
+            self.chunk.length()
+        )
+    }
+}
+
+/// A PNG file read from its bytes
+#[pyclass(name = "Png", module = "pngme")]
+struct PyPng {
+    png: Png,
+}
+
+#[pymethods]
+impl PyPng {
+    #[staticmethod]
+    fn from_bytes(bytes: &[u8]) -> PyResult<Self> {
+        let png = Png::try_from(bytes).map_err(to_py_err)?;
+
+        Ok(PyPng { png })
+    }
+
+    #[getter]
+    fn chunks(&self) -> Vec<PyChunk> {
+        self.png
+            .chunks()
+            .iter()
+            .map(|chunk| PyChunk {
+                chunk: chunk.clone(),
+            })
+            .collect()
+    }
+
+    /// Retrieves the first chunk with the provided type, `None` if there is
+    /// no such chunk
+    fn chunk_by_type(&self, chunk_type: &str) -> Option<PyChunk> {
+        self.png.chunk_by_type(chunk_type).map(|chunk| PyChunk {
+            chunk: chunk.clone(),
+        })
+    }
+
+    /// Adds a `tEXt` chunk, the keyword and text must be Latin-1
+    fn add_text(&mut self, keyword: &str, text: &str) -> PyResult<()> {
+        let chunk = Text::new(keyword, text).to_chunk().map_err(to_py_err)?;
+
+        self.png.insert_chunk_ordered(chunk);
+
+        Ok(())
+    }
+
+    /// Hides the message the same way `pngme encode` does, `method` is one
+    /// of `chunk`, `apng-frames` and `palette`
+    #[pyo3(signature = (message, method = "chunk", chunk_type = "ruSt"))]
+    fn embed(&mut self, message: &str, method: &str, chunk_type: &str) -> PyResult<()> {
+        let tag = self::chunk_type(chunk_type)?;
+        let payload = message.as_bytes();
+
+        match method {
+            "chunk" => self
+                .png
+                .insert_message(Chunk::new(tag, payload.to_vec()))
+                .map_err(to_py_err),
+            "apng-frames" => {
+                self.png = stego::hide_in_frames(&self.png, &tag, payload).map_err(to_py_err)?;
+
+                Ok(())
+            }
+            "palette" => {
+                self.png = stego::hide_in_palette(&self.png, &tag, payload).map_err(to_py_err)?;
+
+                Ok(())
+            }
+            _ => Err(PyValueError::new_err(format!("Unknown method {}", method))),
+        }
+    }
+
+    /// Retrieves a message hidden with `embed`
+    #[pyo3(signature = (method = "chunk", chunk_type = "ruSt"))]
+    fn extract(&self, method: &str, chunk_type: &str) -> PyResult<String> {
+        let tag = self::chunk_type(chunk_type)?;
+        let payload = match method {
+            "chunk" => match self.png.chunk_by_type(chunk_type) {
+                Some(chunk) => chunk.data().to_vec(),
+                None => return Err(to_py_err(Box::new(error::PngMeError::UnexistentChunkType))),
+            },
+            "apng-frames" => stego::reveal_from_frames(&self.png, &tag).map_err(to_py_err)?,
+            "palette" => stego::reveal_from_palette(&self.png, &tag).map_err(to_py_err)?,
+            _ => return Err(PyValueError::new_err(format!("Unknown method {}", method))),
+        };
+
+        Ok(String::from_utf8_lossy(&payload).to_string())
+    }
+
+    /// Removes the first chunk with the provided type and returns it
+    fn remove_chunk(&mut self, chunk_type: &str) -> PyResult<PyChunk> {
+        let chunk = self.png.remove_chunk(chunk_type).map_err(to_py_err)?;
+
+        Ok(PyChunk { chunk })
+    }
+
+    fn to_bytes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.png.as_bytes())
+    }
+
+    fn __bytes__<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        self.to_bytes(py)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Png(chunks={})", self.png.chunks().len())
+    }
+}
+
+#[pymodule]
+#[pyo3(name = "pngme")]
+fn pngme_python(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    let py = m.py();
+
+    m.add_class::<PyPng>()?;
+    m.add_class::<PyChunk>()?;
+    m.add("PngMeError", py.get_type::<PngMeError>())?;
+    m.add("CriticalChunkError", py.get_type::<CriticalChunkError>())?;
+    m.add(
+        "InvalidAnimationError",
+        py.get_type::<InvalidAnimationError>(),
+    )?;
+    m.add(
+        "InvalidChunkLengthError",
+        py.get_type::<InvalidChunkLengthError>(),
+    )?;
+    m.add("InvalidBytesError", py.get_type::<InvalidBytesError>())?;
+    m.add(
+        "InvalidChunkTypeError",
+        py.get_type::<InvalidChunkTypeError>(),
+    )?;
+    m.add("InvalidCrcError", py.get_type::<InvalidCrcError>())?;
+    m.add(
+        "InvalidChunkDataError",
+        py.get_type::<InvalidChunkDataError>(),
+    )?;
+    m.add(
+        "InvalidChunkOrderError",
+        py.get_type::<InvalidChunkOrderError>(),
+    )?;
+    m.add("InvalidExifError", py.get_type::<InvalidExifError>())?;
+    m.add(
+        "InvalidIccProfileError",
+        py.get_type::<InvalidIccProfileError>(),
+    )?;
+    m.add(
+        "InvalidImageDataError",
+        py.get_type::<InvalidImageDataError>(),
+    )?;
+    m.add(
+        "InvalidPngFileHeaderError",
+        py.get_type::<InvalidPngFileHeaderError>(),
+    )?;
+    m.add("InvalidXmpError", py.get_type::<InvalidXmpError>())?;
+    m.add(
+        "PayloadNotFoundError",
+        py.get_type::<PayloadNotFoundError>(),
+    )?;
+    m.add(
+        "PayloadTooLargeError",
+        py.get_type::<PayloadTooLargeError>(),
+    )?;
+    m.add("TruncatedChunkError", py.get_type::<TruncatedChunkError>())?;
+    m.add(
+        "UnexistentChunkTypeError",
+        py.get_type::<UnexistentChunkTypeError>(),
+    )?;
+    m.add(
+        "UnableToCreateFileError",
+        py.get_type::<UnableToCreateFileError>(),
+    )?;
+    m.add(
+        "UnableToWriteOutputFileError",
+        py.get_type::<UnableToWriteOutputFileError>(),
+    )?;
+
+    Ok(())
+}
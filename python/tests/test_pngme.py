@@ -0,0 +1,86 @@
+from pathlib import Path
+
+import pytest
+
+import pngme
+
+DICE = Path(__file__).resolve().parents[2] / "docs" / "dice.png"
+
+
+@pytest.fixture
+def png():
+    return pngme.Png.from_bytes(DICE.read_bytes())
+
+
+def test_chunks(png):
+    chunks = png.chunks
+
+    assert chunks[0].type == "IHDR"
+    assert len(chunks[0].data) == 13
+    assert chunks[-1].type == "IEND"
+    assert chunks[-1].data == b""
+    assert isinstance(chunks[0].crc, int)
+
+
+def test_to_bytes_round_trip(png):
+    assert png.to_bytes() == DICE.read_bytes()
+    assert bytes(png) == DICE.read_bytes()
+
+
+def test_embed_and_extract(png):
+    png.embed("This is a secret message!")
+    copy = pngme.Png.from_bytes(png.to_bytes())
+
+    assert copy.extract() == "This is a secret message!"
+    assert copy.chunks[-2].type == "ruSt"
+
+    png.embed("Another one", chunk_type="teSt")
+
+    assert png.chunk_by_type("teSt").data == b"Another one"
+
+
+def test_embed_rejects_unsupported_arguments(png):
+    with pytest.raises(TypeError):
+        png.embed("secret", passphrase="hunter2")
+
+    with pytest.raises(ValueError):
+        png.embed("secret", method="lsb")
+
+    with pytest.raises(pngme.InvalidImageDataError):
+        png.extract(method="palette")
+
+
+def test_add_text(png):
+    png.add_text("Title", "Dice")
+
+    chunk = png.chunk_by_type("tEXt")
+
+    assert chunk.data == b"Title\x00Dice"
+
+
+def test_remove_chunk(png):
+    png.embed("secret")
+
+    assert png.remove_chunk("ruSt").data == b"secret"
+    assert png.chunk_by_type("ruSt") is None
+
+    with pytest.raises(pngme.UnexistentChunkTypeError):
+        png.remove_chunk("ruSt")
+
+
+def test_errors_map_to_exceptions(png):
+    data = bytearray(DICE.read_bytes())
+    # Last CRC byte of the `IHDR` chunk
+    data[8 + 25 - 1] ^= 0xFF
+
+    with pytest.raises(pngme.InvalidCrcError):
+        pngme.Png.from_bytes(bytes(data))
+
+    with pytest.raises(pngme.InvalidPngFileHeaderError):
+        pngme.Png.from_bytes(b"GIF89a")
+
+    with pytest.raises(pngme.InvalidChunkLengthError):
+        png.embed("secret", chunk_type="ruS")
+
+    with pytest.raises(pngme.PngMeError):
+        png.embed("secret", chunk_type="IDAT")
@@ -0,0 +1,840 @@
+use std::convert::{TryFrom, TryInto};
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::error::PngMeError;
+use crate::text::{compress, decompress, encode_keyword, split_keyword};
+use crate::Result;
+
+/// Image gamma stored in the `gAMA` chunk, times 100000
+///
+/// Reference: http://www.libpng.org/pub/png/spec/1.2/PNG-Chunks.html#C.gAMA
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gama {
+    pub gamma: u32,
+}
+
+/// Primary chromaticities stored in the `cHRM` chunk, every coordinate is
+/// times 100000
+///
+/// Reference: http://www.libpng.org/pub/png/spec/1.2/PNG-Chunks.html#C.cHRM
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chrm {
+    pub white_point: (u32, u32),
+    pub red: (u32, u32),
+    pub green: (u32, u32),
+    pub blue: (u32, u32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RenderingIntent {
+    Perceptual,
+    RelativeColorimetric,
+    Saturation,
+    AbsoluteColorimetric,
+}
+
+/// Standard RGB color space stored in the `sRGB` chunk
+///
+/// Reference: http://www.libpng.org/pub/png/spec/1.2/PNG-Chunks.html#C.sRGB
+#[derive(Debug, Clone, PartialEq)]
+pub struct Srgb {
+    pub rendering_intent: RenderingIntent,
+}
+
+/// Embedded ICC profile stored in the `iCCP` chunk, the profile is kept
+/// decompressed
+///
+/// Reference: http://www.libpng.org/pub/png/spec/1.2/PNG-Chunks.html#C.iCCP
+#[derive(Debug, Clone, PartialEq)]
+pub struct Iccp {
+    pub name: String,
+    pub profile: Vec<u8>,
+}
+
+/// Significant bits stored in the `sBIT` chunk, one value per channel of
+/// the image color type
+///
+/// Reference: http://www.libpng.org/pub/png/spec/1.2/PNG-Chunks.html#C.sBIT
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sbit {
+    pub significant_bits: Vec<u8>,
+}
+
+/// Background color stored in the `bKGD` chunk, its layout depends on the
+/// color type of the image
+///
+/// Reference: http://www.libpng.org/pub/png/spec/1.2/PNG-Chunks.html#C.bKGD
+#[derive(Debug, Clone, PartialEq)]
+pub enum Bkgd {
+    Indexed(u8),
+    Grayscale(u16),
+    Rgb(u16, u16, u16),
+}
+
+/// Palette histogram stored in the `hIST` chunk
+///
+/// Reference: http://www.libpng.org/pub/png/spec/1.2/PNG-Chunks.html#C.hIST
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hist {
+    pub frequencies: Vec<u16>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PhysUnit {
+    Unknown,
+    Meter,
+}
+
+/// Physical pixel dimensions stored in the `pHYs` chunk
+///
+/// Reference: http://www.libpng.org/pub/png/spec/1.2/PNG-Chunks.html#C.pHYs
+#[derive(Debug, Clone, PartialEq)]
+pub struct Phys {
+    pub pixels_per_unit_x: u32,
+    pub pixels_per_unit_y: u32,
+    pub unit: PhysUnit,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpltEntry {
+    pub red: u16,
+    pub green: u16,
+    pub blue: u16,
+    pub alpha: u16,
+    pub frequency: u16,
+}
+
+/// Suggested palette stored in the `sPLT` chunk, samples are 8 or 16 bits
+/// deep
+///
+/// Reference: http://www.libpng.org/pub/png/spec/1.2/PNG-Chunks.html#C.sPLT
+#[derive(Debug, Clone, PartialEq)]
+pub struct Splt {
+    pub name: String,
+    pub sample_depth: u8,
+    pub entries: Vec<SpltEntry>,
+}
+
+/// Last modification time stored in the `tIME` chunk, in UTC
+///
+/// Reference: http://www.libpng.org/pub/png/spec/1.2/PNG-Chunks.html#C.tIME
+#[derive(Debug, Clone, PartialEq)]
+pub struct Time {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+/// Exif profile stored in the `eXIf` chunk, a TIFF structure
+///
+/// Reference: https://www.w3.org/TR/png-3/#eXIf
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exif {
+    pub data: Vec<u8>,
+}
+
+/// Coding-independent code points stored in the `cICP` chunk
+///
+/// Reference: https://www.w3.org/TR/png-3/#cICP-chunk
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cicp {
+    pub colour_primaries: u8,
+    pub transfer_function: u8,
+    pub matrix_coefficients: u8,
+    pub video_full_range: bool,
+}
+
+impl Gama {
+    pub fn to_chunk(&self) -> Chunk {
+        new_chunk("gAMA", self.gamma.to_be_bytes().to_vec())
+    }
+}
+
+impl Chrm {
+    pub fn to_chunk(&self) -> Chunk {
+        let data = [self.white_point, self.red, self.green, self.blue]
+            .iter()
+            .flat_map(|(x, y)| [x.to_be_bytes(), y.to_be_bytes()].concat())
+            .collect();
+
+        new_chunk("cHRM", data)
+    }
+}
+
+impl Srgb {
+    pub fn to_chunk(&self) -> Chunk {
+        new_chunk("sRGB", vec![self.rendering_intent as u8])
+    }
+}
+
+impl Iccp {
+    pub fn to_chunk(&self) -> Result<Chunk> {
+        let mut data = encode_keyword(&self.name)?;
+
+        data.push(0);
+        data.extend(compress(&self.profile)?);
+
+        Ok(new_chunk("iCCP", data))
+    }
+}
+
+impl Sbit {
+    pub fn to_chunk(&self) -> Chunk {
+        new_chunk("sBIT", self.significant_bits.clone())
+    }
+}
+
+impl Bkgd {
+    pub fn to_chunk(&self) -> Chunk {
+        let data = match self {
+            Bkgd::Indexed(index) => vec![*index],
+            Bkgd::Grayscale(gray) => gray.to_be_bytes().to_vec(),
+            Bkgd::Rgb(red, green, blue) => {
+                [red.to_be_bytes(), green.to_be_bytes(), blue.to_be_bytes()].concat()
+            }
+        };
+
+        new_chunk("bKGD", data)
+    }
+}
+
+impl Hist {
+    pub fn to_chunk(&self) -> Chunk {
+        let data = self
+            .frequencies
+            .iter()
+            .flat_map(|f| f.to_be_bytes())
+            .collect();
+
+        new_chunk("hIST", data)
+    }
+}
+
+impl Phys {
+    pub fn to_chunk(&self) -> Chunk {
+        let mut data = [
+            self.pixels_per_unit_x.to_be_bytes(),
+            self.pixels_per_unit_y.to_be_bytes(),
+        ]
+        .concat();
+
+        data.push(self.unit as u8);
+
+        new_chunk("pHYs", data)
+    }
+}
+
+impl Splt {
+    pub fn to_chunk(&self) -> Result<Chunk> {
+        let mut data = encode_keyword(&self.name)?;
+
+        data.push(self.sample_depth);
+
+        for entry in self.entries.iter() {
+            let samples = [entry.red, entry.green, entry.blue, entry.alpha];
+
+            if self.sample_depth == 8 {
+                data.extend(samples.iter().map(|s| *s as u8));
+            } else {
+                data.extend(samples.iter().flat_map(|s| s.to_be_bytes()));
+            }
+
+            data.extend(entry.frequency.to_be_bytes());
+        }
+
+        Ok(new_chunk("sPLT", data))
+    }
+}
+
+impl Time {
+    pub fn to_chunk(&self) -> Chunk {
+        let mut data = self.year.to_be_bytes().to_vec();
+
+        data.extend([self.month, self.day, self.hour, self.minute, self.second]);
+
+        new_chunk("tIME", data)
+    }
+}
+
+impl Exif {
+    /// Returns true if the TIFF structure is big endian
+    pub fn is_big_endian(&self) -> bool {
+        self.data.starts_with(b"MM")
+    }
+
+    pub fn to_chunk(&self) -> Chunk {
+        new_chunk("eXIf", self.data.clone())
+    }
+}
+
+impl Cicp {
+    pub fn to_chunk(&self) -> Chunk {
+        new_chunk(
+            "cICP",
+            vec![
+                self.colour_primaries,
+                self.transfer_function,
+                self.matrix_coefficients,
+                self.video_full_range as u8,
+            ],
+        )
+    }
+}
+
+impl TryFrom<&Chunk> for Gama {
+    type Error = crate::Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        let data = expect(chunk, "gAMA", &[4])?;
+
+        Ok(Gama {
+            gamma: u32::from_be_bytes(data.try_into()?),
+        })
+    }
+}
+
+impl TryFrom<&Chunk> for Chrm {
+    type Error = crate::Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        let data = expect(chunk, "cHRM", &[32])?;
+        let point = |index: usize| {
+            (
+                u32::from_be_bytes(data[index * 8..index * 8 + 4].try_into().unwrap()),
+                u32::from_be_bytes(data[index * 8 + 4..index * 8 + 8].try_into().unwrap()),
+            )
+        };
+
+        Ok(Chrm {
+            white_point: point(0),
+            red: point(1),
+            green: point(2),
+            blue: point(3),
+        })
+    }
+}
+
+impl TryFrom<&Chunk> for Srgb {
+    type Error = crate::Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        let data = expect(chunk, "sRGB", &[1])?;
+        let rendering_intent = match data[0] {
+            0 => RenderingIntent::Perceptual,
+            1 => RenderingIntent::RelativeColorimetric,
+            2 => RenderingIntent::Saturation,
+            3 => RenderingIntent::AbsoluteColorimetric,
+            _ => return Err(invalid("sRGB has an unknown rendering intent")),
+        };
+
+        Ok(Srgb { rendering_intent })
+    }
+}
+
+impl TryFrom<&Chunk> for Iccp {
+    type Error = crate::Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        expect(chunk, "iCCP", &[])?;
+
+        let (name, rest) = split_keyword(chunk.data())?;
+
+        match rest.split_first() {
+            Some((0, profile)) => Ok(Iccp {
+                name,
+                profile: decompress(profile)?,
+            }),
+            _ => Err(invalid("iCCP has an unknown compression method")),
+        }
+    }
+}
+
+impl TryFrom<&Chunk> for Sbit {
+    type Error = crate::Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        let data = expect(chunk, "sBIT", &[1, 2, 3, 4])?;
+
+        if data.iter().any(|bits| *bits == 0 || *bits > 16) {
+            return Err(invalid("sBIT values must be between 1 and 16"));
+        }
+
+        Ok(Sbit {
+            significant_bits: data.to_vec(),
+        })
+    }
+}
+
+impl TryFrom<&Chunk> for Bkgd {
+    type Error = crate::Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        let data = expect(chunk, "bKGD", &[1, 2, 6])?;
+        let sample = |index: usize| u16::from_be_bytes([data[index], data[index + 1]]);
+
+        Ok(match data.len() {
+            1 => Bkgd::Indexed(data[0]),
+            2 => Bkgd::Grayscale(sample(0)),
+            _ => Bkgd::Rgb(sample(0), sample(2), sample(4)),
+        })
+    }
+}
+
+impl TryFrom<&Chunk> for Hist {
+    type Error = crate::Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        let data = expect(chunk, "hIST", &[])?;
+
+        if data.is_empty() || data.len() % 2 != 0 || data.len() > 512 {
+            return Err(invalid("hIST must hold between 1 and 256 frequencies"));
+        }
+
+        Ok(Hist {
+            frequencies: data
+                .chunks(2)
+                .map(|f| u16::from_be_bytes([f[0], f[1]]))
+                .collect(),
+        })
+    }
+}
+
+impl TryFrom<&Chunk> for Phys {
+    type Error = crate::Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        let data = expect(chunk, "pHYs", &[9])?;
+        let unit = match data[8] {
+            0 => PhysUnit::Unknown,
+            1 => PhysUnit::Meter,
+            _ => return Err(invalid("pHYs has an unknown unit")),
+        };
+
+        Ok(Phys {
+            pixels_per_unit_x: u32::from_be_bytes(data[0..4].try_into()?),
+            pixels_per_unit_y: u32::from_be_bytes(data[4..8].try_into()?),
+            unit,
+        })
+    }
+}
+
+impl TryFrom<&Chunk> for Splt {
+    type Error = crate::Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        expect(chunk, "sPLT", &[])?;
+
+        let (name, rest) = split_keyword(chunk.data())?;
+        let (sample_depth, entries) = rest
+            .split_first()
+            .ok_or_else(|| invalid("sPLT is missing the sample depth"))?;
+        let entry_length = match sample_depth {
+            8 => 6,
+            16 => 10,
+            _ => return Err(invalid("sPLT sample depth must be 8 or 16")),
+        };
+
+        if entries.len() % entry_length != 0 {
+            return Err(invalid("sPLT has an incomplete entry"));
+        }
+
+        let entries = entries
+            .chunks(entry_length)
+            .map(|entry| {
+                let sample = |index: usize| match sample_depth {
+                    8 => entry[index] as u16,
+                    _ => u16::from_be_bytes([entry[index * 2], entry[index * 2 + 1]]),
+                };
+
+                SpltEntry {
+                    red: sample(0),
+                    green: sample(1),
+                    blue: sample(2),
+                    alpha: sample(3),
+                    frequency: u16::from_be_bytes([
+                        entry[entry_length - 2],
+                        entry[entry_length - 1],
+                    ]),
+                }
+            })
+            .collect();
+
+        Ok(Splt {
+            name,
+            sample_depth: *sample_depth,
+            entries,
+        })
+    }
+}
+
+impl TryFrom<&Chunk> for Time {
+    type Error = crate::Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        let data = expect(chunk, "tIME", &[7])?;
+        let time = Time {
+            year: u16::from_be_bytes([data[0], data[1]]),
+            month: data[2],
+            day: data[3],
+            hour: data[4],
+            minute: data[5],
+            second: data[6],
+        };
+
+        if !(1..=12).contains(&time.month)
+            || !(1..=31).contains(&time.day)
+            || time.hour > 23
+            || time.minute > 59
+            || time.second > 60
+        {
+            return Err(invalid(&format!("tIME {} is out of range", time)));
+        }
+
+        Ok(time)
+    }
+}
+
+impl TryFrom<&Chunk> for Exif {
+    type Error = crate::Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        let data = expect(chunk, "eXIf", &[])?;
+
+        if !data.starts_with(b"MM\0*") && !data.starts_with(b"II*\0") {
+            return Err(invalid("eXIf must start with a TIFF header"));
+        }
+
+        Ok(Exif {
+            data: data.to_vec(),
+        })
+    }
+}
+
+impl TryFrom<&Chunk> for Cicp {
+    type Error = crate::Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        let data = expect(chunk, "cICP", &[4])?;
+
+        if data[2] != 0 || data[3] > 1 {
+            return Err(invalid(
+                "cICP only supports RGB matrix coefficients and a 0 or 1 range flag",
+            ));
+        }
+
+        Ok(Cicp {
+            colour_primaries: data[0],
+            transfer_function: data[1],
+            matrix_coefficients: data[2],
+            video_full_range: data[3] == 1,
+        })
+    }
+}
+
+impl Display for Gama {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "gamma {:.5}", self.gamma as f64 / 100_000.0)
+    }
+}
+
+impl Display for Chrm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let point = |(x, y): (u32, u32)| format!("({:.4}, {:.4})", x as f64 / 1e5, y as f64 / 1e5);
+
+        write!(
+            f,
+            "white point {}, red {}, green {}, blue {}",
+            point(self.white_point),
+            point(self.red),
+            point(self.green),
+            point(self.blue)
+        )
+    }
+}
+
+impl Display for RenderingIntent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderingIntent::Perceptual => write!(f, "perceptual"),
+            RenderingIntent::RelativeColorimetric => write!(f, "relative colorimetric"),
+            RenderingIntent::Saturation => write!(f, "saturation"),
+            RenderingIntent::AbsoluteColorimetric => write!(f, "absolute colorimetric"),
+        }
+    }
+}
+
+impl Display for Srgb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "sRGB, {} rendering intent", self.rendering_intent)
+    }
+}
+
+impl Display for Iccp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ICC profile {:?}, {} bytes",
+            self.name,
+            self.profile.len()
+        )
+    }
+}
+
+impl Display for Sbit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bits: Vec<String> = self
+            .significant_bits
+            .iter()
+            .map(|b| b.to_string())
+            .collect();
+
+        write!(f, "significant bits {}", bits.join(", "))
+    }
+}
+
+impl Display for Bkgd {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Bkgd::Indexed(index) => write!(f, "background palette index {}", index),
+            Bkgd::Grayscale(gray) => write!(f, "background gray {}", gray),
+            Bkgd::Rgb(red, green, blue) => {
+                write!(f, "background rgb({}, {}, {})", red, green, blue)
+            }
+        }
+    }
+}
+
+impl Display for Hist {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "histogram of {} palette entries", self.frequencies.len())
+    }
+}
+
+impl Display for Phys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.unit {
+            PhysUnit::Meter => write!(
+                f,
+                "{}x{} pixels per meter ({:.0}x{:.0} dpi)",
+                self.pixels_per_unit_x,
+                self.pixels_per_unit_y,
+                self.pixels_per_unit_x as f64 * 0.0254,
+                self.pixels_per_unit_y as f64 * 0.0254
+            ),
+            PhysUnit::Unknown => write!(
+                f,
+                "pixel aspect ratio {}:{}",
+                self.pixels_per_unit_x, self.pixels_per_unit_y
+            ),
+        }
+    }
+}
+
+impl Display for Splt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "suggested palette {:?}, {} entries of {}-bit samples",
+            self.name,
+            self.entries.len(),
+            self.sample_depth
+        )
+    }
+}
+
+impl Display for Time {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+impl Display for Exif {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Exif profile, {} bytes, {} endian",
+            self.data.len(),
+            if self.is_big_endian() {
+                "big"
+            } else {
+                "little"
+            }
+        )
+    }
+}
+
+impl Display for Cicp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "colour primaries {}, transfer function {}, matrix coefficients {}, {} range",
+            self.colour_primaries,
+            self.transfer_function,
+            self.matrix_coefficients,
+            if self.video_full_range {
+                "full"
+            } else {
+                "narrow"
+            }
+        )
+    }
+}
+
+fn new_chunk(chunk_type: &str, data: Vec<u8>) -> Chunk {
+    Chunk::new(ChunkType::from_str(chunk_type).unwrap(), data)
+}
+
+/// Checks the chunk type and that the data has one of the provided lengths,
+/// any length is accepted when no lengths are provided
+fn expect<'a>(chunk: &'a Chunk, chunk_type: &str, lengths: &[usize]) -> Result<&'a [u8]> {
+    if chunk.chunk_type().bytes() != chunk_type.as_bytes() {
+        return Err(invalid(&format!(
+            "expected a {} chunk, found {}",
+            chunk_type,
+            chunk.chunk_type()
+        )));
+    }
+
+    if !lengths.is_empty() && !lengths.contains(&chunk.data().len()) {
+        return Err(invalid(&format!(
+            "{} can't be {} bytes long",
+            chunk_type,
+            chunk.data().len()
+        )));
+    }
+
+    Ok(chunk.data())
+}
+
+fn invalid(reason: &str) -> crate::Error {
+    Box::new(PngMeError::InvalidChunkData(reason.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip<T>(value: T, chunk: Chunk)
+    where
+        T: for<'a> TryFrom<&'a Chunk, Error = crate::Error> + PartialEq + fmt::Debug,
+    {
+        assert_eq!(T::try_from(&chunk).unwrap(), value);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let gama = Gama { gamma: 45455 };
+        let chrm = Chrm {
+            white_point: (31270, 32900),
+            red: (64000, 33000),
+            green: (30000, 60000),
+            blue: (15000, 6000),
+        };
+        let srgb = Srgb {
+            rendering_intent: RenderingIntent::Perceptual,
+        };
+        let iccp = Iccp {
+            name: String::from("Display P3"),
+            profile: vec![1, 2, 3, 4],
+        };
+        let sbit = Sbit {
+            significant_bits: vec![5, 6, 5],
+        };
+        let hist = Hist {
+            frequencies: vec![1, 300],
+        };
+        let phys = Phys {
+            pixels_per_unit_x: 2835,
+            pixels_per_unit_y: 2835,
+            unit: PhysUnit::Meter,
+        };
+        let splt = Splt {
+            name: String::from("web"),
+            sample_depth: 16,
+            entries: vec![SpltEntry {
+                red: 1000,
+                green: 2,
+                blue: 3,
+                alpha: 65535,
+                frequency: 7,
+            }],
+        };
+        let time = Time {
+            year: 2024,
+            month: 2,
+            day: 29,
+            hour: 13,
+            minute: 5,
+            second: 0,
+        };
+        let exif = Exif {
+            data: b"II*\0\x08\0\0\0".to_vec(),
+        };
+        let cicp = Cicp {
+            colour_primaries: 1,
+            transfer_function: 13,
+            matrix_coefficients: 0,
+            video_full_range: true,
+        };
+
+        round_trip(gama.clone(), gama.to_chunk());
+        round_trip(chrm.clone(), chrm.to_chunk());
+        round_trip(srgb.clone(), srgb.to_chunk());
+        round_trip(iccp.clone(), iccp.to_chunk().unwrap());
+        round_trip(sbit.clone(), sbit.to_chunk());
+        round_trip(Bkgd::Indexed(3), Bkgd::Indexed(3).to_chunk());
+        round_trip(Bkgd::Rgb(1, 2, 3), Bkgd::Rgb(1, 2, 3).to_chunk());
+        round_trip(hist.clone(), hist.to_chunk());
+        round_trip(phys.clone(), phys.to_chunk());
+        round_trip(splt.clone(), splt.to_chunk().unwrap());
+        round_trip(time.clone(), time.to_chunk());
+        round_trip(exif.clone(), exif.to_chunk());
+        round_trip(cicp.clone(), cicp.to_chunk());
+    }
+
+    #[test]
+    fn test_invalid_data() {
+        assert!(Gama::try_from(&new_chunk("gAMA", vec![0; 3])).is_err());
+        assert!(Srgb::try_from(&new_chunk("sRGB", vec![4])).is_err());
+        assert!(Sbit::try_from(&new_chunk("sBIT", vec![0])).is_err());
+        assert!(Phys::try_from(&new_chunk("pHYs", vec![0, 0, 0, 1, 0, 0, 0, 1, 2])).is_err());
+        assert!(Time::try_from(&new_chunk("tIME", vec![7, 232, 13, 1, 0, 0, 0])).is_err());
+        assert!(Exif::try_from(&new_chunk("eXIf", b"JPEG".to_vec())).is_err());
+        assert!(Splt::try_from(&new_chunk("sPLT", b"web\0\x08\0\0".to_vec())).is_err());
+        assert!(Gama::try_from(&new_chunk("sRGB", vec![0; 4])).is_err());
+    }
+
+    #[test]
+    fn test_display() {
+        let phys = Phys {
+            pixels_per_unit_x: 2835,
+            pixels_per_unit_y: 2835,
+            unit: PhysUnit::Meter,
+        };
+
+        assert_eq!(Gama { gamma: 45455 }.to_string(), "gamma 0.45455");
+        assert_eq!(phys.to_string(), "2835x2835 pixels per meter (72x72 dpi)");
+        assert_eq!(
+            Time {
+                year: 2024,
+                month: 2,
+                day: 29,
+                hour: 13,
+                minute: 5,
+                second: 0,
+            }
+            .to_string(),
+            "2024-02-29 13:05:00 UTC"
+        );
+    }
+}
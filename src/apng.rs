@@ -0,0 +1,510 @@
+use alloc::{boxed::Box, format, string::ToString, vec::Vec};
+use core::convert::{TryFrom, TryInto};
+use core::fmt::{self, Display};
+use core::str::FromStr;
+
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::error::PngMeError;
+use crate::ihdr::Ihdr;
+use crate::png::Png;
+use crate::Result;
+
+/// Chunk types defined by the APNG specification
+pub const ANIMATION_CHUNK_TYPES: [&str; 3] = ["acTL", "fcTL", "fdAT"];
+
+/// Animation control stored in the `acTL` chunk
+///
+/// Reference: https://wiki.mozilla.org/APNG_Specification#.60acTL.60:_The_Animation_Control_Chunk
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationControl {
+    pub num_frames: u32,
+    /// Number of times the animation is played, 0 plays it forever
+    pub num_plays: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DisposeOp {
+    None,
+    Background,
+    Previous,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlendOp {
+    Source,
+    Over,
+}
+
+/// Frame control stored in the `fcTL` chunk
+///
+/// Reference: https://wiki.mozilla.org/APNG_Specification#.60fcTL.60:_The_Frame_Control_Chunk
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameControl {
+    pub sequence_number: u32,
+    pub width: u32,
+    pub height: u32,
+    pub x_offset: u32,
+    pub y_offset: u32,
+    pub delay_num: u16,
+    pub delay_den: u16,
+    pub dispose_op: DisposeOp,
+    pub blend_op: BlendOp,
+}
+
+/// Frame data stored in the `fdAT` chunk, the same as `IDAT` data preceded
+/// by a sequence number
+///
+/// Reference: https://wiki.mozilla.org/APNG_Specification#.60fdAT.60:_The_Frame_Data_Chunk
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameData {
+    pub sequence_number: u32,
+    pub data: Vec<u8>,
+}
+
+/// A frame of the animation along with the chunks holding its image data,
+/// either `IDAT` chunks for a default image which is part of the
+/// animation, or `fdAT` chunks
+#[derive(Debug)]
+pub struct Frame<'a> {
+    pub control: FrameControl,
+    pub chunks: Vec<&'a Chunk>,
+}
+
+impl AnimationControl {
+    /// Retrieves the animation control of an APNG file, `None` is returned
+    /// for static PNG files
+    pub fn from_png(png: &Png) -> Result<Option<Self>> {
+        png.chunk_by_type("acTL")
+            .map(AnimationControl::try_from)
+            .transpose()
+    }
+
+    pub fn to_chunk(&self) -> Chunk {
+        let data = [self.num_frames.to_be_bytes(), self.num_plays.to_be_bytes()].concat();
+
+        Chunk::new(ChunkType::from_str("acTL").unwrap(), data)
+    }
+}
+
+impl FrameControl {
+    /// Delay of the frame in seconds, a zero denominator stands for 1/100
+    /// of a second
+    pub fn delay(&self) -> f64 {
+        let den = if self.delay_den == 0 {
+            100
+        } else {
+            self.delay_den
+        };
+
+        self.delay_num as f64 / den as f64
+    }
+
+    pub fn to_chunk(&self) -> Chunk {
+        let mut data = Vec::with_capacity(26);
+
+        data.extend(self.sequence_number.to_be_bytes());
+        data.extend(self.width.to_be_bytes());
+        data.extend(self.height.to_be_bytes());
+        data.extend(self.x_offset.to_be_bytes());
+        data.extend(self.y_offset.to_be_bytes());
+        data.extend(self.delay_num.to_be_bytes());
+        data.extend(self.delay_den.to_be_bytes());
+        data.push(self.dispose_op as u8);
+        data.push(self.blend_op as u8);
+
+        Chunk::new(ChunkType::from_str("fcTL").unwrap(), data)
+    }
+}
+
+impl FrameData {
+    pub fn to_chunk(&self) -> Chunk {
+        let data = [&self.sequence_number.to_be_bytes()[..], &self.data].concat();
+
+        Chunk::new(ChunkType::from_str("fdAT").unwrap(), data)
+    }
+}
+
+impl<'a> Frame<'a> {
+    /// Retrieves the compressed image data of the frame, the same data an
+    /// `IDAT` chunk would hold
+    pub fn data(&self) -> Vec<u8> {
+        self.chunks
+            .iter()
+            .flat_map(|chunk| match &chunk.chunk_type().bytes() {
+                b"fdAT" => chunk.data().get(4..).unwrap_or_default(),
+                _ => chunk.data(),
+            })
+            .copied()
+            .collect()
+    }
+
+    /// Returns true if the frame image data is the default image
+    pub fn is_default_image(&self) -> bool {
+        self.chunks
+            .iter()
+            .any(|c| &c.chunk_type().bytes() == b"IDAT")
+    }
+}
+
+impl TryFrom<&Chunk> for AnimationControl {
+    type Error = crate::Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        let data = expect(chunk, "acTL", 8)?;
+
+        Ok(AnimationControl {
+            num_frames: u32::from_be_bytes(data[0..4].try_into()?),
+            num_plays: u32::from_be_bytes(data[4..8].try_into()?),
+        })
+    }
+}
+
+impl TryFrom<&Chunk> for FrameControl {
+    type Error = crate::Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        let data = expect(chunk, "fcTL", 26)?;
+        let dispose_op = match data[24] {
+            0 => DisposeOp::None,
+            1 => DisposeOp::Background,
+            2 => DisposeOp::Previous,
+            _ => return Err(invalid("fcTL has an unknown dispose operation")),
+        };
+        let blend_op = match data[25] {
+            0 => BlendOp::Source,
+            1 => BlendOp::Over,
+            _ => return Err(invalid("fcTL has an unknown blend operation")),
+        };
+
+        Ok(FrameControl {
+            sequence_number: u32::from_be_bytes(data[0..4].try_into()?),
+            width: u32::from_be_bytes(data[4..8].try_into()?),
+            height: u32::from_be_bytes(data[8..12].try_into()?),
+            x_offset: u32::from_be_bytes(data[12..16].try_into()?),
+            y_offset: u32::from_be_bytes(data[16..20].try_into()?),
+            delay_num: u16::from_be_bytes(data[20..22].try_into()?),
+            delay_den: u16::from_be_bytes(data[22..24].try_into()?),
+            dispose_op,
+            blend_op,
+        })
+    }
+}
+
+impl TryFrom<&Chunk> for FrameData {
+    type Error = crate::Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        if &chunk.chunk_type().bytes() != b"fdAT" || chunk.data().len() < 4 {
+            return Err(invalid("fdAT must hold a sequence number"));
+        }
+
+        Ok(FrameData {
+            sequence_number: u32::from_be_bytes(chunk.data()[0..4].try_into()?),
+            data: chunk.data()[4..].to_vec(),
+        })
+    }
+}
+
+impl Display for DisposeOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DisposeOp::None => write!(f, "none"),
+            DisposeOp::Background => write!(f, "background"),
+            DisposeOp::Previous => write!(f, "previous"),
+        }
+    }
+}
+
+impl Display for BlendOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlendOp::Source => write!(f, "source"),
+            BlendOp::Over => write!(f, "over"),
+        }
+    }
+}
+
+impl Display for AnimationControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.num_plays {
+            0 => write!(f, "{} frames, plays forever", self.num_frames),
+            plays => write!(f, "{} frames, plays {} times", self.num_frames, plays),
+        }
+    }
+}
+
+impl Display for FrameControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}x{} at ({}, {}), delay {:.3}s, dispose {}, blend {}",
+            self.width,
+            self.height,
+            self.x_offset,
+            self.y_offset,
+            self.delay(),
+            self.dispose_op,
+            self.blend_op
+        )
+    }
+}
+
+/// Checks wether the chunk type is one of the APNG chunk types
+pub fn is_animation_chunk_type(chunk_type: &ChunkType) -> bool {
+    ANIMATION_CHUNK_TYPES
+        .iter()
+        .any(|t| chunk_type.bytes() == t.as_bytes())
+}
+
+/// Groups the chunks of an APNG file in frames, every frame starts with a
+/// `fcTL` chunk followed by its image data chunks
+pub fn frames(png: &Png) -> Result<Vec<Frame<'_>>> {
+    let mut frames: Vec<Frame> = Vec::new();
+
+    for chunk in png.chunks() {
+        match &chunk.chunk_type().bytes() {
+            b"fcTL" => frames.push(Frame {
+                control: FrameControl::try_from(chunk)?,
+                chunks: Vec::new(),
+            }),
+            b"IDAT" | b"fdAT" => {
+                if let Some(frame) = frames.last_mut() {
+                    frame.chunks.push(chunk);
+                } else if &chunk.chunk_type().bytes() == b"fdAT" {
+                    return Err(invalid("fdAT found before any fcTL"));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(frames)
+}
+
+/// Checks the APNG chunks follow the rules of the APNG specification:
+/// `acTL` precedes the image data, the number of frames matches `acTL`,
+/// every frame fits in the image and the sequence numbers of `fcTL` and
+/// `fdAT` chunks start at 0 without gaps.
+///
+/// Static PNG files are always valid.
+pub fn validate(png: &Png) -> Result<()> {
+    let animation_control = match AnimationControl::from_png(png)? {
+        Some(animation_control) => animation_control,
+        None => {
+            if png
+                .chunks()
+                .iter()
+                .any(|c| is_animation_chunk_type(c.chunk_type()))
+            {
+                return Err(invalid("fcTL or fdAT found without acTL"));
+            }
+
+            return Ok(());
+        }
+    };
+    let ihdr = Ihdr::from_png(png)?;
+    let actl = png.chunk_position("acTL");
+    let idat = png.chunk_position("IDAT");
+
+    if actl > idat {
+        return Err(invalid("acTL must appear before IDAT"));
+    }
+
+    let frames = frames(png)?;
+
+    if frames.len() != animation_control.num_frames as usize || frames.is_empty() {
+        return Err(invalid(&format!(
+            "acTL declares {} frames but {} were found",
+            animation_control.num_frames,
+            frames.len()
+        )));
+    }
+
+    for frame in frames.iter() {
+        let control = &frame.control;
+
+        if control.width == 0
+            || control.height == 0
+            || control.x_offset as u64 + control.width as u64 > ihdr.width as u64
+            || control.y_offset as u64 + control.height as u64 > ihdr.height as u64
+        {
+            return Err(invalid(&format!(
+                "frame {} doesn't fit in the image",
+                control.sequence_number
+            )));
+        }
+
+        if frame.is_default_image()
+            && (control.x_offset != 0
+                || control.y_offset != 0
+                || control.width != ihdr.width
+                || control.height != ihdr.height)
+        {
+            return Err(invalid(
+                "the default image frame must cover the whole image",
+            ));
+        }
+    }
+
+    let sequence_numbers = png
+        .chunks()
+        .iter()
+        .filter_map(|c| match &c.chunk_type().bytes() {
+            b"fcTL" | b"fdAT" => c
+                .data()
+                .get(0..4)
+                .map(|n| u32::from_be_bytes(n.try_into().unwrap())),
+            _ => None,
+        });
+
+    for (expected, sequence_number) in sequence_numbers.enumerate() {
+        if sequence_number as usize != expected {
+            return Err(invalid(&format!(
+                "expected sequence number {}, found {}",
+                expected, sequence_number
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+fn expect<'a>(chunk: &'a Chunk, chunk_type: &str, length: usize) -> Result<&'a [u8]> {
+    if chunk.chunk_type().bytes() != chunk_type.as_bytes() || chunk.data().len() != length {
+        return Err(invalid(&format!(
+            "{} must be {} bytes long",
+            chunk_type, length
+        )));
+    }
+
+    Ok(chunk.data())
+}
+
+fn invalid(reason: &str) -> crate::Error {
+    Box::new(PngMeError::InvalidAnimation(reason.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame_control(sequence_number: u32, width: u32, height: u32) -> FrameControl {
+        FrameControl {
+            sequence_number,
+            width,
+            height,
+            x_offset: 0,
+            y_offset: 0,
+            delay_num: 1,
+            delay_den: 10,
+            dispose_op: DisposeOp::None,
+            blend_op: BlendOp::Over,
+        }
+    }
+
+    fn testing_png() -> Png {
+        let ihdr = Ihdr {
+            width: 4,
+            height: 4,
+            bit_depth: 8,
+            color_type: 6,
+            compression_method: 0,
+            filter_method: 0,
+            interlace_method: 0,
+        };
+
+        Png::from_chunks(vec![
+            ihdr.to_chunk(),
+            AnimationControl {
+                num_frames: 2,
+                num_plays: 0,
+            }
+            .to_chunk(),
+            frame_control(0, 4, 4).to_chunk(),
+            Chunk::new(ChunkType::from_str("IDAT").unwrap(), b"first".to_vec()),
+            frame_control(1, 2, 2).to_chunk(),
+            FrameData {
+                sequence_number: 2,
+                data: b"second".to_vec(),
+            }
+            .to_chunk(),
+            FrameData {
+                sequence_number: 3,
+                data: b" frame".to_vec(),
+            }
+            .to_chunk(),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()),
+        ])
+    }
+
+    #[test]
+    fn test_frame_control_round_trip() {
+        let expected = frame_control(7, 20, 10);
+        let chunk = expected.to_chunk();
+
+        assert_eq!(chunk.length(), 26);
+        assert_eq!(FrameControl::try_from(&chunk).unwrap(), expected);
+        assert!((expected.delay() - 0.1).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_frames() {
+        let png = testing_png();
+        let frames = frames(&png).unwrap();
+
+        assert_eq!(frames.len(), 2);
+        assert!(frames[0].is_default_image());
+        assert_eq!(frames[0].data(), b"first");
+        assert_eq!(frames[1].data(), b"second frame");
+        assert_eq!(frames[1].control.width, 2);
+    }
+
+    #[test]
+    fn test_validate() {
+        let png = testing_png();
+
+        assert!(validate(&png).is_ok());
+        assert!(validate(&Png::from_chunks(vec![])).is_ok());
+    }
+
+    #[test]
+    fn test_validate_wrong_sequence_number() {
+        let mut png = testing_png();
+
+        png.replace_chunk(frame_control(5, 2, 2).to_chunk())
+            .unwrap();
+
+        assert!(validate(&png).is_err());
+    }
+
+    #[test]
+    fn test_replace_message_rejects_animation_chunks() {
+        let mut png = testing_png();
+        let before = png.as_bytes();
+
+        assert!(png
+            .replace_message(frame_control(5, 2, 2).to_chunk())
+            .is_err());
+        assert!(png
+            .replace_message(frame_control(0, 4, 4).to_chunk())
+            .is_err());
+        assert_eq!(png.as_bytes(), before);
+    }
+
+    #[test]
+    fn test_validate_wrong_number_of_frames() {
+        let mut png = testing_png();
+
+        png.replace_chunk(
+            AnimationControl {
+                num_frames: 3,
+                num_plays: 0,
+            }
+            .to_chunk(),
+        )
+        .unwrap();
+
+        assert!(validate(&png).is_err());
+    }
+}
@@ -6,8 +6,21 @@ use structopt::StructOpt;
 pub enum Args {
     Encode(EncodeArgs),
     Decode(DecodeArgs),
+    Update(UpdateArgs),
     Remove(RemoveArgs),
     Print(PrintArgs),
+    Inspect(InspectArgs),
+    Apply(ApplyArgs),
+    Diff(DiffArgs),
+    Copy(CopyArgs),
+    Strip(StripArgs),
+    Repair(RepairArgs),
+    Info(InfoArgs),
+    Exif(ExifArgs),
+    Xmp(XmpArgs),
+    Icc(IccArgs),
+    Serve(ServeArgs),
+    Tui(TuiArgs),
 }
 
 #[derive(StructOpt, Debug)]
@@ -16,7 +29,21 @@ pub struct EncodeArgs {
     pub file_path: PathBuf,
     pub chunk_type: String,
     pub message: String,
+    #[structopt(conflicts_with = "in-place")]
     pub output_file: Option<PathBuf>,
+    /// Writes the message into the provided PNG file instead of `output.png`
+    #[structopt(long)]
+    pub in_place: bool,
+    /// Where the message is hidden, `apng-frames` spreads it across the
+    /// least significant bits of the frames of an animated PNG file and
+    /// `palette` in the order of the palette entries of an indexed image
+    #[structopt(long, default_value = "chunk", possible_values = &["chunk", "apng-frames", "palette"])]
+    pub method: String,
+    /// Keeps a copy of the replaced file with the provided suffix (e.g. `.bak`)
+    #[structopt(long)]
+    pub backup: Option<String>,
+    #[structopt(flatten)]
+    pub batch: BatchArgs,
 }
 
 #[derive(StructOpt, Debug)]
@@ -24,6 +51,37 @@ pub struct EncodeArgs {
 pub struct DecodeArgs {
     pub file_path: PathBuf,
     pub chunk_type: String,
+    /// Where the message was hidden, `apng-frames` spreads it across the
+    /// least significant bits of the frames of an animated PNG file and
+    /// `palette` in the order of the palette entries of an indexed image
+    #[structopt(long, default_value = "chunk", possible_values = &["chunk", "apng-frames", "palette"])]
+    pub method: String,
+    /// Which chunks have their CRC verified, `accessed` only verifies the
+    /// chunks read to decode the message
+    #[structopt(long, default_value = "all", possible_values = &["all", "accessed", "none"])]
+    pub verify_crc: String,
+    #[structopt(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(
+    name = "update",
+    about = "Replaces the message stored in the provided chunk type"
+)]
+pub struct UpdateArgs {
+    pub file_path: PathBuf,
+    pub chunk_type: String,
+    pub message: String,
+    pub output_file: Option<PathBuf>,
+    /// Appends the chunk if the PNG file doesn't have one of the provided type
+    #[structopt(long)]
+    pub create: bool,
+    /// Keeps a copy of the replaced file with the provided suffix (e.g. `.bak`)
+    #[structopt(long)]
+    pub backup: Option<String>,
+    #[structopt(flatten)]
+    pub batch: BatchArgs,
 }
 
 #[derive(StructOpt, Debug)]
@@ -34,10 +92,330 @@ pub struct DecodeArgs {
 pub struct RemoveArgs {
     pub file_path: PathBuf,
     pub chunk_type: String,
+    /// Keeps a copy of the replaced file with the provided suffix (e.g. `.bak`)
+    #[structopt(long)]
+    pub backup: Option<String>,
+    #[structopt(flatten)]
+    pub batch: BatchArgs,
 }
 
 #[derive(StructOpt, Debug)]
 #[structopt(name = "print", about = "Print chunks stored in the PNG file")]
 pub struct PrintArgs {
+    /// PNG files or glob patterns to process
+    #[structopt(required = true)]
+    pub file_paths: Vec<PathBuf>,
+    /// How strictly the file is parsed, problems found are printed after the chunks
+    #[structopt(long, default_value = "lenient", possible_values = &["strict", "lenient", "forensic"])]
+    pub parse_mode: String,
+    #[structopt(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(
+    name = "inspect",
+    about = "Lists the type, length and CRC of the chunks without reading their data"
+)]
+pub struct InspectArgs {
+    /// PNG files or glob patterns to process
+    #[structopt(required = true)]
+    pub file_paths: Vec<PathBuf>,
+    #[structopt(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(
+    name = "apply",
+    about = "Applies the chunk changes described in a TOML manifest"
+)]
+pub struct ApplyArgs {
+    pub manifest_path: PathBuf,
+    /// Prints the changes without writing any file
+    #[structopt(long)]
+    pub dry_run: bool,
+    /// Keeps a copy of the replaced file with the provided suffix (e.g. `.bak`)
+    #[structopt(long)]
+    pub backup: Option<String>,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "diff", about = "Compares the chunks of two PNG files")]
+pub struct DiffArgs {
+    pub file_a: PathBuf,
+    pub file_b: PathBuf,
+    /// Output format
+    #[structopt(long, default_value = "text", possible_values = &["text", "json"])]
+    pub format: String,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "copy", about = "Copies ancillary chunks between PNG files")]
+pub struct CopyArgs {
+    /// PNG file to copy the chunks from
+    #[structopt(long)]
+    pub from: PathBuf,
+    /// PNG file to copy the chunks into
+    #[structopt(long)]
+    pub to: PathBuf,
+    /// Comma separated chunk types to copy (e.g. `tEXt,iCCP,ruSt`)
+    #[structopt(long, use_delimiter = true, required = true)]
+    pub types: Vec<String>,
+    /// Writes the result into this file instead of the destination file
+    #[structopt(long)]
+    pub output: Option<PathBuf>,
+    /// Keeps a copy of the replaced file with the provided suffix (e.g. `.bak`)
+    #[structopt(long)]
+    pub backup: Option<String>,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(
+    name = "strip",
+    about = "Removes metadata chunks and any data after the image end"
+)]
+pub struct StripArgs {
+    pub file_path: PathBuf,
+    pub output_file: Option<PathBuf>,
+    /// Comma separated ancillary chunk types to keep (e.g. `sRGB,eXIf,tEXt`)
+    #[structopt(long, use_delimiter = true, default_value = "sRGB,gAMA,iCCP")]
+    pub keep: Vec<String>,
+    /// Keeps a copy of the replaced file with the provided suffix (e.g. `.bak`)
+    #[structopt(long)]
+    pub backup: Option<String>,
+    #[structopt(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "repair", about = "Repairs damaged or truncated PNG files")]
+pub struct RepairArgs {
+    pub file_path: PathBuf,
+    pub output_file: Option<PathBuf>,
+    /// Recomputes wrong CRCs instead of dropping the chunks
+    #[structopt(long)]
+    pub fix_crc: bool,
+    /// Keeps a copy of the replaced file with the provided suffix (e.g. `.bak`)
+    #[structopt(long)]
+    pub backup: Option<String>,
+    #[structopt(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "info", about = "Prints the image header and animation details")]
+pub struct InfoArgs {
+    /// PNG files or glob patterns to process
+    #[structopt(required = true)]
+    pub file_paths: Vec<PathBuf>,
+    #[structopt(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(
+    name = "exif",
+    about = "Lists and edits the Exif profile stored in eXIf chunks"
+)]
+pub enum ExifArgs {
+    /// Lists every Exif tag
+    List(ExifListArgs),
+    /// Prints the value of an Exif tag
+    Get(ExifGetArgs),
+    /// Sets the value of an Exif tag, creating the eXIf chunk if needed
+    Set(ExifSetArgs),
+    /// Removes Exif tags
+    Remove(ExifRemoveArgs),
+}
+
+#[derive(StructOpt, Debug)]
+pub struct ExifListArgs {
+    /// PNG files or glob patterns to process
+    #[structopt(required = true)]
+    pub file_paths: Vec<PathBuf>,
+    #[structopt(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct ExifGetArgs {
+    pub file_path: PathBuf,
+    /// Tag name (e.g. `Model`) or number (e.g. `0x0110`)
+    pub tag: String,
+    #[structopt(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct ExifSetArgs {
+    pub file_path: PathBuf,
+    /// Tag name (e.g. `Model`) or number (e.g. `0x0110`)
+    pub tag: String,
+    /// Lists are comma separated and rationals written as `1/250` or `0.004`
+    pub value: String,
+    /// Keeps a copy of the replaced file with the provided suffix (e.g. `.bak`)
+    #[structopt(long)]
+    pub backup: Option<String>,
+    #[structopt(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct ExifRemoveArgs {
+    pub file_path: PathBuf,
+    /// Tag names or numbers to remove
+    pub tags: Vec<String>,
+    /// Removes every GPS tag
+    #[structopt(long)]
+    pub remove_gps: bool,
+    /// Keeps a copy of the replaced file with the provided suffix (e.g. `.bak`)
+    #[structopt(long)]
+    pub backup: Option<String>,
+    #[structopt(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(
+    name = "xmp",
+    about = "Reads and edits the XMP packet stored in an iTXt chunk"
+)]
+pub enum XmpArgs {
+    /// Prints a property or the whole XMP packet
+    Get(XmpGetArgs),
+    /// Sets a property, creating the XMP packet if needed
+    Set(XmpSetArgs),
+    /// Copies every property of an XMP sidecar file into the packet
+    Merge(XmpMergeArgs),
+}
+
+#[derive(StructOpt, Debug)]
+pub struct XmpGetArgs {
+    pub file_path: PathBuf,
+    /// Qualified property name (e.g. `dc:title`), prints the whole packet
+    /// when omitted
+    pub property: Option<String>,
+    #[structopt(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct XmpSetArgs {
+    pub file_path: PathBuf,
+    /// Qualified property name (e.g. `dc:title`)
+    pub property: String,
+    /// Items of `dc:creator`, `dc:subject` and other arrays are separated by `;`
+    pub value: String,
+    /// Namespace URI of the property prefix, required for custom namespaces
+    #[structopt(long)]
+    pub namespace: Option<String>,
+    /// Keeps a copy of the replaced file with the provided suffix (e.g. `.bak`)
+    #[structopt(long)]
+    pub backup: Option<String>,
+    #[structopt(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct XmpMergeArgs {
+    pub file_path: PathBuf,
+    /// XMP sidecar file whose properties are merged into the packet
+    pub xmp_file: PathBuf,
+    /// Keeps a copy of the replaced file with the provided suffix (e.g. `.bak`)
+    #[structopt(long)]
+    pub backup: Option<String>,
+    #[structopt(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(
+    name = "icc",
+    about = "Extracts and embeds the ICC profile stored in the iCCP chunk"
+)]
+pub enum IccArgs {
+    /// Prints the description and color space of the ICC profile
+    Info(IccInfoArgs),
+    /// Writes the ICC profile into a file
+    Extract(IccExtractArgs),
+    /// Embeds an ICC profile, replacing the current one and the sRGB chunk
+    Embed(IccEmbedArgs),
+}
+
+#[derive(StructOpt, Debug)]
+pub struct IccInfoArgs {
+    /// PNG files or glob patterns to process
+    #[structopt(required = true)]
+    pub file_paths: Vec<PathBuf>,
+    #[structopt(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct IccExtractArgs {
+    pub file_path: PathBuf,
+    /// File to write the ICC profile into (e.g. `profile.icc`)
+    pub output_file: PathBuf,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct IccEmbedArgs {
     pub file_path: PathBuf,
+    /// ICC profile file to embed
+    pub profile_file: PathBuf,
+    /// Profile name stored in the iCCP chunk, defaults to the profile
+    /// description
+    #[structopt(long)]
+    pub name: Option<String>,
+    /// Keeps a copy of the replaced file with the provided suffix (e.g. `.bak`)
+    #[structopt(long)]
+    pub backup: Option<String>,
+    #[structopt(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(
+    name = "serve",
+    about = "Serves the encode, decode, inspect and strip commands over HTTP"
+)]
+pub struct ServeArgs {
+    /// Address the server listens on
+    #[structopt(long, default_value = "127.0.0.1:8080")]
+    pub bind: String,
+    /// Largest request body accepted, in bytes
+    #[structopt(long, default_value = "10485760")]
+    pub max_body_size: usize,
+    /// Number of requests handled at the same time
+    #[structopt(long, default_value = "4")]
+    pub max_concurrency: usize,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "tui", about = "Browses and edits the chunks of a PNG file")]
+pub struct TuiArgs {
+    pub file_path: PathBuf,
+    /// Keeps a copy of the replaced file with the provided suffix (e.g. `.bak`)
+    #[structopt(long)]
+    pub backup: Option<String>,
+}
+
+/// Options to run a command over many PNG files, the `file_path` provided
+/// to a command can also be a glob pattern (e.g. `"assets/**/*.png"`).
+/// Commands taking no other positional argument accept several of them.
+#[derive(StructOpt, Debug)]
+pub struct BatchArgs {
+    /// Additional PNG files or glob patterns to process
+    #[structopt(long = "input", short = "i", number_of_values = 1)]
+    pub inputs: Vec<PathBuf>,
+    /// Processes every PNG file inside the provided directory and its subdirectories
+    #[structopt(long, number_of_values = 1)]
+    pub recursive: Vec<PathBuf>,
+    /// Number of files processed in parallel, defaults to the number of CPUs
+    #[structopt(long, short = "j")]
+    pub jobs: Option<usize>,
+    /// Keeps processing the remaining files when one of them fails
+    #[structopt(long)]
+    pub continue_on_error: bool,
 }
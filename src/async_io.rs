@@ -0,0 +1,260 @@
+use std::convert::TryFrom;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::chunk::Chunk;
+use crate::error::PngMeError;
+use crate::png::{Png, PNG_FILE_SIGNATURE};
+use crate::Result;
+
+/// Reads the chunks of a PNG file one at a time from an `AsyncRead` such as
+/// a network stream. Chunks are validated the same way `Png::try_from`
+/// validates them and anything after `IEND` which is not a chunk is kept as
+/// trailing data.
+pub struct AsyncChunkReader<R> {
+    reader: R,
+    signature_read: bool,
+    after_iend: bool,
+    trailing_data: Vec<u8>,
+}
+
+impl<R: AsyncRead + Unpin> AsyncChunkReader<R> {
+    pub fn new(reader: R) -> Self {
+        AsyncChunkReader {
+            reader,
+            signature_read: false,
+            after_iend: false,
+            trailing_data: Vec::new(),
+        }
+    }
+
+    /// Reads the next chunk, `None` is returned once the stream ends. The
+    /// PNG signature is read and checked before the first chunk.
+    pub async fn next_chunk(&mut self) -> Result<Option<Chunk>> {
+        if !self.signature_read {
+            let mut signature = [0; 8];
+
+            self.reader
+                .read_exact(&mut signature)
+                .await
+                .map_err(|_| PngMeError::InvalidPNGFileHeader)?;
+
+            if signature != PNG_FILE_SIGNATURE {
+                return Err(Box::new(PngMeError::InvalidPNGFileHeader));
+            }
+
+            self.signature_read = true;
+        }
+
+        let mut bytes = Vec::with_capacity(12);
+        let header_length = (&mut self.reader).take(8).read_to_end(&mut bytes).await?;
+
+        if header_length == 0 {
+            return Ok(None);
+        }
+
+        let length = bytes
+            .get(..4)
+            .map(|length| u32::from_be_bytes([length[0], length[1], length[2], length[3]]));
+        let chunk = match length {
+            Some(length) if header_length == 8 => {
+                // The data is read as it arrives so a wrong length can't
+                // allocate more than the stream holds
+                (&mut self.reader)
+                    .take(length as u64 + 4)
+                    .read_to_end(&mut bytes)
+                    .await?;
+
+                Chunk::try_from(bytes.as_slice())
+            }
+            _ => Err(PngMeError::TruncatedChunk),
+        };
+
+        match chunk {
+            Ok(chunk) => {
+                self.after_iend |= &chunk.chunk_type().bytes() == b"IEND";
+
+                Ok(Some(chunk))
+            }
+            Err(_) if self.after_iend => {
+                // Anything after `IEND` which is not a chunk is kept
+                // as it is
+                self.reader.read_to_end(&mut bytes).await?;
+                self.trailing_data = bytes;
+
+                Ok(None)
+            }
+            Err(e) => Err(Box::new(e)),
+        }
+    }
+
+    /// Reads every remaining chunk into a `Png`
+    pub async fn read_png(mut self) -> Result<Png> {
+        let mut chunks = Vec::new();
+
+        while let Some(chunk) = self.next_chunk().await? {
+            chunks.push(chunk);
+        }
+
+        Ok(Png::from_chunks_and_trailing_data(
+            chunks,
+            self.trailing_data,
+        ))
+    }
+
+    /// Retrieves the bytes found after the `IEND` chunk which couldn't be
+    /// read as chunks, available once `next_chunk` returns `None`
+    pub fn trailing_data(&self) -> &[u8] {
+        self.trailing_data.as_slice()
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+/// Writes a PNG file one chunk at a time into an `AsyncWrite`, the PNG
+/// signature is written before the first chunk
+pub struct AsyncChunkWriter<W> {
+    writer: W,
+    signature_written: bool,
+}
+
+impl<W: AsyncWrite + Unpin> AsyncChunkWriter<W> {
+    pub fn new(writer: W) -> Self {
+        AsyncChunkWriter {
+            writer,
+            signature_written: false,
+        }
+    }
+
+    pub async fn write_chunk(&mut self, chunk: &Chunk) -> Result<()> {
+        self.write_signature().await?;
+        self.writer.write_all(&chunk.as_bytes()).await?;
+
+        Ok(())
+    }
+
+    /// Writes every chunk of the PNG file followed by its trailing data
+    pub async fn write_png(&mut self, png: &Png) -> Result<()> {
+        for chunk in png.chunks() {
+            self.write_chunk(chunk).await?;
+        }
+
+        self.writer.write_all(png.trailing_data()).await?;
+
+        Ok(())
+    }
+
+    /// Flushes the writer, writing the signature if no chunk was written,
+    /// and returns it
+    pub async fn finish(mut self) -> Result<W> {
+        self.write_signature().await?;
+        self.writer.flush().await?;
+
+        Ok(self.writer)
+    }
+
+    async fn write_signature(&mut self) -> Result<()> {
+        if !self.signature_written {
+            self.writer.write_all(&PNG_FILE_SIGNATURE).await?;
+            self.signature_written = true;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk_type::ChunkType;
+    use std::str::FromStr;
+    use tokio::io::duplex;
+
+    fn testing_png() -> Png {
+        let chunks = ["IHDR", "tEXt", "IDAT", "IEND"]
+            .iter()
+            .map(|chunk_type| {
+                Chunk::new(
+                    ChunkType::from_str(chunk_type).unwrap(),
+                    chunk_type.repeat(100).into_bytes(),
+                )
+            })
+            .collect();
+
+        Png::from_chunks(chunks)
+    }
+
+    #[tokio::test]
+    async fn test_reads_the_same_chunks_as_the_sync_parser() {
+        let mut bytes = testing_png().as_bytes();
+
+        bytes.extend(b"trailing");
+
+        let expected = Png::try_from(bytes.as_slice()).unwrap();
+        // A small buffer makes chunks arrive in several reads
+        let (mut client, server) = duplex(64);
+        let write = async move {
+            client.write_all(&bytes).await.unwrap();
+        };
+        let (png, _) = tokio::join!(AsyncChunkReader::new(server).read_png(), write);
+        let png = png.unwrap();
+
+        assert_eq!(png.as_bytes(), expected.as_bytes());
+        assert_eq!(png.trailing_data(), b"trailing");
+    }
+
+    #[tokio::test]
+    async fn test_strips_chunks_between_streams() {
+        let bytes = testing_png().as_bytes();
+        let (mut client, server) = duplex(64);
+        let (output, mut received) = duplex(64);
+        let write = async move {
+            client.write_all(&bytes).await.unwrap();
+        };
+        let strip = async move {
+            let mut reader = AsyncChunkReader::new(server);
+            let mut writer = AsyncChunkWriter::new(output);
+
+            while let Some(chunk) = reader.next_chunk().await.unwrap() {
+                if &chunk.chunk_type().bytes() != b"tEXt" {
+                    writer.write_chunk(&chunk).await.unwrap();
+                }
+            }
+
+            writer.finish().await.unwrap();
+        };
+        let read = async move {
+            let mut bytes = Vec::new();
+
+            received.read_to_end(&mut bytes).await.unwrap();
+            bytes
+        };
+        let (_, _, stripped) = tokio::join!(write, strip, read);
+        let png = Png::try_from(stripped.as_slice()).unwrap();
+
+        assert_eq!(png.chunks().len(), 3);
+        assert!(png.chunk_by_type("tEXt").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_rejects_invalid_streams() {
+        let mut bytes = testing_png().as_bytes();
+        let mut reader = AsyncChunkReader::new(&b"GIF89a"[..]);
+
+        assert!(reader.next_chunk().await.is_err());
+
+        // Last CRC byte of the `IHDR` chunk
+        bytes[8 + 12 + 400 - 1] ^= 0xff;
+
+        let mut reader = AsyncChunkReader::new(bytes.as_slice());
+
+        assert!(reader.next_chunk().await.is_err());
+
+        let bytes = testing_png().as_bytes();
+        let mut reader = AsyncChunkReader::new(&bytes[..100]);
+
+        assert!(reader.next_chunk().await.is_err());
+    }
+}
@@ -0,0 +1,167 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use crate::error::PngMeError;
+use crate::Result;
+
+/// Writes `bytes` into `path` without ever leaving a partially written
+/// file behind.
+///
+/// The bytes are written into a temporary file in the same directory as
+/// `path`, flushed to disk and then renamed over `path`, which is atomic
+/// on the same filesystem. When `path` already exists its permissions are
+/// kept, and if a `backup_suffix` is provided a copy of the original file
+/// is stored next to it (e.g. `image.png.bak` for `.bak`).
+pub fn write(path: &Path, bytes: &[u8], backup_suffix: Option<&str>) -> Result<()> {
+    let original = fs::metadata(path).ok();
+    let (temp_path, mut temp_file) = create_temp_file(path)?;
+
+    let result = (|| -> Result<()> {
+        temp_file
+            .write_all(bytes)
+            .and_then(|_| temp_file.sync_all())
+            .map_err(|e| PngMeError::UnableToWriteOutputFile(e.to_string()))?;
+
+        if let Some(metadata) = original.as_ref() {
+            fs::set_permissions(&temp_path, metadata.permissions())
+                .map_err(|e| PngMeError::UnableToWriteOutputFile(e.to_string()))?;
+
+            if let Some(suffix) = backup_suffix {
+                fs::copy(path, backup_path(path, suffix))
+                    .map_err(|e| PngMeError::UnableToCreateFile(e.to_string()))?;
+            }
+        }
+
+        fs::rename(&temp_path, path)
+            .map_err(|e| PngMeError::UnableToWriteOutputFile(e.to_string()))?;
+
+        Ok(())
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+        return result;
+    }
+
+    sync_parent_dir(path);
+
+    Ok(())
+}
+
+/// Builds the path of the backup for `path` by appending `suffix`
+/// to its file name
+pub fn backup_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+
+    file_name.push(suffix);
+    path.with_file_name(file_name)
+}
+
+/// Creates an empty temporary file next to `path`, the temporary file
+/// must live in the same directory so the final rename doesn't cross
+/// filesystems
+fn create_temp_file(path: &Path) -> Result<(PathBuf, File)> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| PngMeError::UnableToCreateFile(format!("{} is not a file", path.display())))?
+        .to_string_lossy()
+        .to_string();
+
+    for attempt in 0..100 {
+        let temp_path = path.with_file_name(format!(
+            ".{}.{}.{}.tmp",
+            file_name,
+            std::process::id(),
+            attempt
+        ));
+
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp_path)
+        {
+            Ok(file) => return Ok((temp_path, file)),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(Box::new(PngMeError::UnableToCreateFile(e.to_string()))),
+        }
+    }
+
+    Err(Box::new(PngMeError::UnableToCreateFile(format!(
+        "unable to create a temporary file for {}",
+        path.display()
+    ))))
+}
+
+/// Flushes the directory entry of the renamed file, errors are ignored
+/// given that the file contents are already on disk
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    if let Ok(dir) = File::open(parent) {
+        let _ = dir.sync_all();
+    }
+}
+
+#[cfg(not(unix))]
+fn sync_parent_dir(_path: &Path) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn testing_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("pngme-{}-{}", name, std::process::id()));
+
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_write_new_file() {
+        let dir = testing_dir("atomic-new");
+        let path = dir.join("image.png");
+
+        write(&path, b"new contents", None).unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), b"new contents");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_with_backup() {
+        let dir = testing_dir("atomic-backup");
+        let path = dir.join("image.png");
+
+        fs::write(&path, b"original").unwrap();
+        write(&path, b"replaced", Some(".bak")).unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), b"replaced");
+        assert_eq!(fs::read(dir.join("image.png.bak")).unwrap(), b"original");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = testing_dir("atomic-permissions");
+        let path = dir.join("image.png");
+
+        fs::write(&path, b"original").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+        write(&path, b"replaced", None).unwrap();
+
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+
+        assert_eq!(mode & 0o777, 0o640);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,343 @@
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use rayon::prelude::*;
+
+use pngme::png::PNG_FILE_SIGNATURE;
+
+use crate::args::BatchArgs;
+use crate::Result;
+
+/// Set of files a command runs on.
+///
+/// A command runs over a single file unless a glob pattern, additional
+/// `--input` paths or a `--recursive` directory are provided, in which case
+/// files are processed in parallel reporting the outcome of each file and
+/// a summary at the end.
+pub struct Batch {
+    targets: Vec<PathBuf>,
+    single: bool,
+    jobs: usize,
+    continue_on_error: bool,
+    check_signature: bool,
+}
+
+enum Outcome {
+    Done(String),
+    Skipped(String),
+    Failed(String),
+}
+
+impl Batch {
+    pub fn new(file_path: &Path, batch_args: &BatchArgs) -> Result<Self> {
+        Batch::from_paths(&[file_path.to_path_buf()], batch_args)
+    }
+
+    /// Builds the set of files from several paths or glob patterns provided
+    /// to the command, along with the `--input` and `--recursive` ones
+    pub fn from_paths(file_paths: &[PathBuf], batch_args: &BatchArgs) -> Result<Self> {
+        let mut targets = Vec::new();
+
+        for file_path in file_paths.iter().chain(batch_args.inputs.iter()) {
+            targets.extend(expand(file_path)?);
+        }
+
+        let single = file_paths.len() == 1
+            && targets.len() == 1
+            && targets[0] == file_paths[0]
+            && batch_args.inputs.is_empty()
+            && batch_args.recursive.is_empty();
+
+        for dir in batch_args.recursive.iter() {
+            walk(dir, &mut targets)?;
+        }
+
+        let mut seen = HashSet::new();
+        targets.retain(|path| seen.insert(path.clone()));
+
+        Ok(Batch {
+            targets,
+            single,
+            jobs: batch_args.jobs.unwrap_or(0),
+            continue_on_error: batch_args.continue_on_error,
+            check_signature: true,
+        })
+    }
+
+    /// Runs the command on files which don't start with the
+    /// `PNG_FILE_SIGNATURE` instead of skipping them, for commands which
+    /// fix damaged signatures
+    pub fn without_signature_check(mut self) -> Self {
+        self.check_signature = false;
+        self
+    }
+
+    /// Returns true if the command runs over a single file provided
+    /// explicitly by the user
+    pub fn is_single(&self) -> bool {
+        self.single
+    }
+
+    /// Runs `task` over every target file. The `String` returned by `task`
+    /// is printed as the output for that file.
+    pub fn run<F>(self, task: F) -> Result<()>
+    where
+        F: Fn(&Path) -> Result<String> + Sync,
+    {
+        if self.single {
+            let path = &self.targets[0];
+
+            if self.check_signature && !has_png_signature(path)? {
+                return Err(format!("{} is not a PNG file", path.display()).into());
+            }
+
+            let output = task(path)?;
+
+            if !output.is_empty() {
+                println!("{}", output);
+            }
+
+            return Ok(());
+        }
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(self.jobs)
+            .build()?;
+        let stop = AtomicBool::new(false);
+        let done = AtomicUsize::new(0);
+        let skipped = AtomicUsize::new(0);
+        let failed = AtomicUsize::new(0);
+
+        pool.install(|| {
+            self.targets.par_iter().for_each(|path| {
+                if stop.load(Ordering::SeqCst) {
+                    return;
+                }
+
+                match process(path, &task, self.check_signature) {
+                    Outcome::Done(output) => {
+                        done.fetch_add(1, Ordering::SeqCst);
+
+                        if output.is_empty() {
+                            println!("[ok] {}", path.display());
+                        } else {
+                            println!("[ok] {}\n{}", path.display(), output);
+                        }
+                    }
+                    Outcome::Skipped(reason) => {
+                        skipped.fetch_add(1, Ordering::SeqCst);
+                        println!("[skipped] {}: {}", path.display(), reason);
+                    }
+                    Outcome::Failed(reason) => {
+                        failed.fetch_add(1, Ordering::SeqCst);
+                        eprintln!("[failed] {}: {}", path.display(), reason);
+
+                        if !self.continue_on_error {
+                            stop.store(true, Ordering::SeqCst);
+                        }
+                    }
+                }
+            });
+        });
+
+        let done = done.into_inner();
+        let skipped = skipped.into_inner();
+        let failed = failed.into_inner();
+        let processed = done + skipped + failed;
+
+        println!(
+            "{} files: {} succeeded, {} skipped, {} failed",
+            processed, done, skipped, failed
+        );
+
+        if processed < self.targets.len() {
+            println!(
+                "Stopped after the first failure, {} files were not processed",
+                self.targets.len() - processed
+            );
+        }
+
+        if failed > 0 {
+            return Err(format!("{} files failed", failed).into());
+        }
+
+        Ok(())
+    }
+}
+
+fn process<F>(path: &Path, task: &F, check_signature: bool) -> Outcome
+where
+    F: Fn(&Path) -> Result<String>,
+{
+    let is_png = if check_signature {
+        has_png_signature(path)
+    } else {
+        Ok(true)
+    };
+
+    match is_png {
+        Ok(true) => match task(path) {
+            Ok(output) => Outcome::Done(output),
+            Err(e) => Outcome::Failed(e.to_string()),
+        },
+        Ok(false) => Outcome::Skipped(String::from("not a PNG file")),
+        Err(e) => Outcome::Failed(e.to_string()),
+    }
+}
+
+/// Checks wether the file starts with the `PNG_FILE_SIGNATURE`
+fn has_png_signature(path: &Path) -> Result<bool> {
+    let mut header = [0; 8];
+    let mut file = File::open(path)?;
+
+    match file.read_exact(&mut header) {
+        Ok(_) => Ok(header == PNG_FILE_SIGNATURE),
+        Err(_) => Ok(false),
+    }
+}
+
+/// Expands a glob pattern into the paths it matches, paths which exist or
+/// doesn't look like a glob pattern are kept as they are
+fn expand(path: &Path) -> Result<Vec<PathBuf>> {
+    let pattern = path.to_string_lossy();
+
+    if path.exists() || !pattern.contains(['*', '?', '[']) {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut paths = Vec::new();
+
+    for entry in glob::glob(&pattern)? {
+        let entry = entry?;
+
+        if entry.is_file() {
+            paths.push(entry);
+        }
+    }
+
+    if paths.is_empty() {
+        return Err(format!("No files matched the pattern {}", pattern).into());
+    }
+
+    Ok(paths)
+}
+
+/// Collects every file inside `dir` and its subdirectories
+fn walk(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<()> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<Vec<PathBuf>>>()?;
+
+    entries.sort();
+
+    for entry in entries {
+        if entry.is_dir() {
+            walk(&entry, paths)?;
+        } else if entry.is_file() {
+            paths.push(entry);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn testing_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("pngme-{}-{}", name, std::process::id()));
+
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        fs::write(dir.join("a.png"), PNG_FILE_SIGNATURE).unwrap();
+        fs::write(dir.join("nested").join("b.png"), PNG_FILE_SIGNATURE).unwrap();
+        fs::write(dir.join("nested").join("c.txt"), b"not a png").unwrap();
+        dir
+    }
+
+    fn batch_args(inputs: Vec<PathBuf>, recursive: Vec<PathBuf>) -> BatchArgs {
+        BatchArgs {
+            inputs,
+            recursive,
+            jobs: None,
+            continue_on_error: false,
+        }
+    }
+
+    #[test]
+    fn test_single_target() {
+        let dir = testing_dir("batch-single");
+        let batch = Batch::new(&dir.join("a.png"), &batch_args(vec![], vec![])).unwrap();
+
+        assert!(batch.is_single());
+        assert_eq!(batch.targets, vec![dir.join("a.png")]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_glob_and_recursive_targets() {
+        let dir = testing_dir("batch-many");
+        let batch = Batch::new(
+            &dir.join("*.png"),
+            &batch_args(vec![dir.join("nested/b.png")], vec![dir.clone()]),
+        )
+        .unwrap();
+
+        assert!(!batch.is_single());
+        assert_eq!(
+            batch.targets,
+            vec![
+                dir.join("a.png"),
+                dir.join("nested/b.png"),
+                dir.join("nested/c.txt"),
+            ]
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_several_paths() {
+        let dir = testing_dir("batch-paths");
+        let batch = Batch::from_paths(
+            &[dir.join("a.png"), dir.join("nested/*.png")],
+            &batch_args(vec![], vec![]),
+        )
+        .unwrap();
+
+        assert!(!batch.is_single());
+        assert_eq!(
+            batch.targets,
+            vec![dir.join("a.png"), dir.join("nested/b.png")]
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_skips_files_without_signature() {
+        let dir = testing_dir("batch-skip");
+
+        assert!(has_png_signature(&dir.join("a.png")).unwrap());
+        assert!(!has_png_signature(&dir.join("nested/c.txt")).unwrap());
+        assert!(matches!(
+            process(
+                &dir.join("nested/c.txt"),
+                &|_: &Path| Ok(String::new()),
+                true
+            ),
+            Outcome::Skipped(_)
+        ));
+        assert!(matches!(
+            process(
+                &dir.join("nested/c.txt"),
+                &|_: &Path| Ok(String::new()),
+                false
+            ),
+            Outcome::Done(_)
+        ));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
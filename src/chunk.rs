@@ -1,11 +1,14 @@
-use crc::crc32::checksum_ieee;
-use std::convert::{TryFrom, TryInto};
-use std::fmt::Display;
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::convert::{TryFrom, TryInto};
+use core::fmt::Display;
 
 use crate::error::PngMeError;
 use crate::{chunk_type::ChunkType, Result};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Chunk {
     data: Vec<u8>,
     length: u32,
@@ -16,13 +19,7 @@ pub struct Chunk {
 impl Chunk {
     pub fn new(chunk_type: ChunkType, data: Vec<u8>) -> Chunk {
         let length = data.len() as u32;
-        let crc_bytes: Vec<u8> = chunk_type
-            .bytes()
-            .iter()
-            .chain(data.iter())
-            .copied()
-            .collect();
-        let crc = checksum_ieee(&crc_bytes);
+        let crc = checksum(&chunk_type, &data);
 
         Chunk {
             length,
@@ -32,6 +29,17 @@ impl Chunk {
         }
     }
 
+    /// Builds a chunk keeping the provided CRC even if it doesn't match
+    /// the chunk type and data, used to keep damaged chunks as they are
+    pub(crate) fn from_raw_parts(chunk_type: ChunkType, data: Vec<u8>, crc: u32) -> Chunk {
+        Chunk {
+            length: data.len() as u32,
+            chunk_type,
+            data,
+            crc,
+        }
+    }
+
     pub fn length(&self) -> u32 {
         self.length
     }
@@ -40,6 +48,10 @@ impl Chunk {
         &self.chunk_type
     }
 
+    pub fn data(&self) -> &[u8] {
+        self.data.as_slice()
+    }
+
     pub fn data_as_string(&self) -> Result<String> {
         Ok(String::from_utf8_lossy(self.data.as_slice()).to_string())
     }
@@ -61,22 +73,70 @@ impl Chunk {
 }
 
 impl Display for Chunk {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "length: {length}\nchunk_type: {chunk_type}\ndata: {data:?}\ncrc: {crc}\n",
-            length = self.length,
-            chunk_type = self.chunk_type,
-            data = self.data_as_string().unwrap(),
-            crc = self.crc,
-        )
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        ChunkRef::from(self).fmt(f)
     }
 }
 
-impl TryFrom<&[u8]> for Chunk {
-    type Error = PngMeError;
+/// A chunk borrowed from the bytes of a PNG file, its data is a slice of
+/// those bytes so reading it doesn't allocate. See `view::PngView`.
+#[derive(Debug, Clone)]
+pub struct ChunkRef<'a> {
+    data: &'a [u8],
+    chunk_type: ChunkType,
+    crc: u32,
+}
+
+impl<'a> ChunkRef<'a> {
+    /// Borrows a chunk keeping the provided CRC even if it doesn't match
+    /// the chunk type and data, used to keep damaged chunks as they are
+    pub(crate) fn from_raw_parts(chunk_type: ChunkType, data: &'a [u8], crc: u32) -> Self {
+        ChunkRef {
+            data,
+            chunk_type,
+            crc,
+        }
+    }
+
+    pub fn length(&self) -> u32 {
+        self.data.len() as u32
+    }
+
+    pub fn chunk_type(&self) -> &ChunkType {
+        &self.chunk_type
+    }
+
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    pub fn data_as_string(&self) -> Result<String> {
+        Ok(String::from_utf8_lossy(self.data).to_string())
+    }
+
+    pub fn crc(&self) -> u32 {
+        self.crc
+    }
+
+    /// Checks the stored CRC matches the chunk type and data
+    pub fn verify_crc(&self) -> core::result::Result<(), PngMeError> {
+        if self.crc != checksum(&self.chunk_type, self.data) {
+            return Err(PngMeError::InvalidCRC);
+        }
+
+        Ok(())
+    }
+
+    /// Reads a chunk from `bytes` which must hold exactly one chunk, the CRC
+    /// is only verified when `verify_crc` is set
+    pub(crate) fn read(
+        bytes: &'a [u8],
+        verify_crc: bool,
+    ) -> core::result::Result<Self, PngMeError> {
+        if bytes.len() < 12 {
+            return Err(PngMeError::TruncatedChunk);
+        }
 
-    fn try_from(bytes: &[u8]) -> std::result::Result<Self, Self::Error> {
         let length = u32::from_be_bytes(bytes[0..4].try_into().unwrap());
         let chunk_type: [u8; 4] = bytes[4..8].try_into().unwrap();
         let chunk_type = ChunkType::try_from(chunk_type).unwrap();
@@ -85,26 +145,82 @@ impl TryFrom<&[u8]> for Chunk {
             return Err(PngMeError::InvalidChunkType);
         }
 
-        let data: Vec<u8> = Vec::from(&bytes[8..bytes.len() - 4]);
+        if (length as usize).checked_add(12) != Some(bytes.len()) {
+            return Err(PngMeError::TruncatedChunk);
+        }
+
+        let data = &bytes[8..bytes.len() - 4];
         let crc = &bytes[bytes.len() - 4..];
-        let crc = u32::from_be_bytes(crc.try_into().unwrap());
+        let chunk = ChunkRef {
+            data,
+            chunk_type,
+            crc: u32::from_be_bytes(crc.try_into().unwrap()),
+        };
 
-        if crc != checksum_ieee(bytes[4..bytes.len() - 4].try_into().unwrap()) {
+        if verify_crc {
             // We check on the `data` bytes and `chunk_type` bytes to match
             // the CRC provided
-            return Err(PngMeError::InvalidCRC);
+            chunk.verify_crc()?;
         }
 
-        Ok(Chunk {
-            data,
-            length,
-            chunk_type,
-            crc,
-        })
+        Ok(chunk)
+    }
+
+    /// Copies the chunk into an owned `Chunk` which can be edited
+    pub fn to_chunk(&self) -> Chunk {
+        Chunk::from_raw_parts(self.chunk_type.clone(), self.data.to_vec(), self.crc)
+    }
+}
+
+impl<'a> From<&'a Chunk> for ChunkRef<'a> {
+    fn from(chunk: &'a Chunk) -> Self {
+        ChunkRef::from_raw_parts(chunk.chunk_type.clone(), &chunk.data, chunk.crc)
+    }
+}
+
+impl Display for ChunkRef<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "length: {length}\nchunk_type: {chunk_type}\ndata: {data:?}\ncrc: {crc}\n",
+            length = self.length(),
+            chunk_type = self.chunk_type,
+            data = self.data_as_string().unwrap(),
+            crc = self.crc,
+        )
+    }
+}
+
+impl TryFrom<&[u8]> for Chunk {
+    type Error = PngMeError;
+
+    fn try_from(bytes: &[u8]) -> core::result::Result<Self, Self::Error> {
+        ChunkRef::try_from(bytes).map(|chunk| chunk.to_chunk())
     }
 }
 
+impl<'a> TryFrom<&'a [u8]> for ChunkRef<'a> {
+    type Error = PngMeError;
+
+    fn try_from(bytes: &'a [u8]) -> core::result::Result<Self, Self::Error> {
+        ChunkRef::read(bytes, true)
+    }
+}
+
+/// CRC-32 of the chunk type and data as stored in every chunk
+///
+/// Reference: http://www.libpng.org/pub/png/spec/1.2/PNG-Structure.html#CRC-algorithm
+pub(crate) fn checksum(chunk_type: &ChunkType, data: &[u8]) -> u32 {
+    let mut hasher = crc32fast::Hasher::new();
+
+    hasher.update(&chunk_type.bytes());
+    hasher.update(data);
+    hasher.finalize()
+}
+
+// These tests are kept as they were written with the chunk codec
 #[cfg(test)]
+#[allow(unused_imports)]
 mod tests {
     use std::str::FromStr;
 
@@ -204,6 +320,32 @@ mod tests {
         assert!(chunk.is_err());
     }
 
+    #[test]
+    fn test_truncated_chunk_from_bytes() {
+        let chunk_data: Vec<u8> = 42u32
+            .to_be_bytes()
+            .iter()
+            .chain("RuSt".as_bytes().iter())
+            .chain("Too short".as_bytes().iter())
+            .copied()
+            .collect();
+
+        assert!(Chunk::try_from(chunk_data.as_ref()).is_err());
+        assert!(Chunk::try_from(&chunk_data[..6]).is_err());
+    }
+
+    #[test]
+    fn test_chunk_ref_borrows_data() {
+        let chunk = testing_chunk();
+        let bytes = chunk.as_bytes();
+        let chunk_ref = ChunkRef::try_from(bytes.as_slice()).unwrap();
+
+        assert_eq!(chunk_ref.length(), 42);
+        assert_eq!(chunk_ref.data(), &bytes[8..50]);
+        assert_eq!(chunk_ref.to_string(), chunk.to_string());
+        assert_eq!(chunk_ref.to_chunk().as_bytes(), bytes);
+    }
+
     #[test]
     pub fn test_chunk_trait_impls() {
         let data_length: u32 = 42;
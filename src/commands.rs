@@ -1,56 +1,668 @@
-use std::fs::{read, File};
-use std::path::PathBuf;
+use std::convert::TryFrom;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use std::{convert::TryFrom, io::Write};
 
-use crate::args::{DecodeArgs, EncodeArgs, PrintArgs, RemoveArgs};
-use crate::chunk::Chunk;
-use crate::chunk_type::ChunkType;
-use crate::error::PngMeError;
-use crate::png::Png;
+use pngme::ancillary::Iccp;
+use pngme::apng::AnimationControl;
+use pngme::chunk::Chunk;
+use pngme::chunk_type::ChunkType;
+use pngme::copy::copy_chunks;
+use pngme::diff::PngDiff;
+use pngme::exif::{self as exif_data, ExifData, Ifd, Value};
+use pngme::icc::{self, IccProfile};
+use pngme::ihdr::Ihdr;
+use pngme::known_chunk::KnownChunk;
+use pngme::palette::Plte;
+use pngme::parse::ParseOptions;
+use pngme::png::Png;
+use pngme::repair::RepairOptions;
+use pngme::stego;
+use pngme::strip::StripOptions;
+use pngme::view::{self, PngView, VerifyCrc};
+use pngme::xmp::Xmp;
+
+use crate::args::{
+    ApplyArgs, CopyArgs, DecodeArgs, DiffArgs, EncodeArgs, ExifArgs, IccArgs, InfoArgs,
+    InspectArgs, PrintArgs, RemoveArgs, RepairArgs, ServeArgs, StripArgs, TuiArgs, UpdateArgs,
+    XmpArgs,
+};
+use crate::batch::Batch;
+use crate::manifest::{FileEntry, Manifest};
+use crate::serve::{self as server, Limits};
+use crate::tui as chunk_browser;
 use crate::Result;
 
 pub fn encode(encode_args: EncodeArgs) -> Result<()> {
-    let mut png = Png::from_file(encode_args.file_path)?;
-    let chunk_type = ChunkType::from_str(encode_args.chunk_type.as_str())?;
-    let chunk = Chunk::new(chunk_type, encode_args.message.as_bytes().to_vec());
-    let output_file_path = if let Some(output_file_name) = encode_args.output_file {
-        output_file_name
-    } else {
-        PathBuf::from_str("output.png").unwrap()
+    let batch = Batch::new(&encode_args.file_path, &encode_args.batch)?;
+
+    if !batch.is_single() && !encode_args.in_place {
+        return Err("Encoding multiple files requires --in-place".into());
+    }
+
+    batch.run(|path| {
+        let mut png = Png::from_file(path.to_path_buf())?;
+        let chunk_type = ChunkType::from_str(encode_args.chunk_type.as_str())?;
+        let chunk = Chunk::new(chunk_type, encode_args.message.as_bytes().to_vec());
+        let output_file_path = if encode_args.in_place {
+            path.to_path_buf()
+        } else if let Some(output_file_name) = encode_args.output_file.clone() {
+            output_file_name
+        } else {
+            PathBuf::from_str("output.png").unwrap()
+        };
+
+        embed_message(&mut png, chunk, encode_args.method.as_str())?;
+
+        write_png(&png, output_file_path, &encode_args.backup)?;
+
+        Ok(String::new())
+    })
+}
+
+pub fn decode(decode_args: DecodeArgs) -> Result<()> {
+    let verify_crc = match decode_args.verify_crc.as_str() {
+        "accessed" => VerifyCrc::Accessed,
+        "none" => VerifyCrc::None,
+        _ => VerifyCrc::All,
     };
 
-    png.append_chunk(chunk);
-    png.write_file(output_file_path)?;
+    Batch::new(&decode_args.file_path, &decode_args.batch)?.run(|path| {
+        let file = view::map_file(path)?;
+        let png = PngView::scan(&file, verify_crc)?;
+
+        if decode_args.method != "chunk" {
+            let chunk_type = ChunkType::from_str(decode_args.chunk_type.as_str())?;
+            let png = png.to_png()?;
+            let payload = match decode_args.method.as_str() {
+                "apng-frames" => stego::reveal_from_frames(&png, &chunk_type)?,
+                _ => stego::reveal_from_palette(&png, &chunk_type)?,
+            };
+
+            return Ok(String::from_utf8_lossy(&payload).to_string());
+        }
+
+        if let Some(chunk) = png.chunk_by_type(decode_args.chunk_type.as_str())? {
+            Ok(chunk.to_string())
+        } else {
+            Err(format!("Chunk type: {} not found", decode_args.chunk_type).into())
+        }
+    })
+}
+
+pub fn update(update_args: UpdateArgs) -> Result<()> {
+    let batch = Batch::new(&update_args.file_path, &update_args.batch)?;
+
+    if !batch.is_single() && update_args.output_file.is_some() {
+        return Err("An output file can't be used when updating multiple files".into());
+    }
+
+    batch.run(|path| {
+        let mut png = Png::from_file(path.to_path_buf())?;
+        let chunk_type = ChunkType::from_str(update_args.chunk_type.as_str())?;
+        let chunk = Chunk::new(chunk_type, update_args.message.as_bytes().to_vec());
+        let output_file_path = update_args
+            .output_file
+            .clone()
+            .unwrap_or_else(|| path.to_path_buf());
+
+        if update_args.create && png.chunk_by_type(update_args.chunk_type.as_str()).is_none() {
+            png.insert_message(chunk)?;
+        } else {
+            png.replace_message(chunk)?;
+        }
+
+        write_png(&png, output_file_path, &update_args.backup)?;
+
+        Ok(String::new())
+    })
+}
+
+pub fn remove(remove_args: RemoveArgs) -> Result<()> {
+    Batch::new(&remove_args.file_path, &remove_args.batch)?.run(|path| {
+        let mut png = Png::from_file(path.to_path_buf())?;
+
+        png.remove_chunk(remove_args.chunk_type.as_str())?;
+        write_png(&png, path.to_path_buf(), &remove_args.backup)?;
+
+        Ok(String::new())
+    })
+}
+
+pub fn print(print_args: PrintArgs) -> Result<()> {
+    let options = match print_args.parse_mode.as_str() {
+        "strict" => ParseOptions::strict(),
+        "forensic" => ParseOptions::forensic(),
+        _ => ParseOptions::lenient(),
+    };
+
+    Batch::from_paths(&print_args.file_paths, &print_args.batch)?.run(|path: &Path| {
+        let file = view::map_file(path)?;
+        let png = PngView::parse(&file, &options)?;
+        let ihdr = png
+            .chunk_by_type("IHDR")?
+            .and_then(|chunk| Ihdr::try_from(&chunk.to_chunk()).ok());
+        let mut lines: Vec<String> = png
+            .chunks()
+            .iter()
+            .map(|chunk| {
+                // Only the chunks with a typed representation are copied,
+                // image data is never known
+                let known_chunk = if KnownChunk::is_known(chunk.chunk_type()) {
+                    KnownChunk::parse(&chunk.to_chunk(), ihdr.as_ref()).ok()
+                } else {
+                    None
+                };
+
+                match known_chunk {
+                    Some(known_chunk) => format!(
+                        "length: {}\nchunk_type: {}\ndata: {}\ncrc: {}\n",
+                        chunk.length(),
+                        chunk.chunk_type(),
+                        known_chunk,
+                        chunk.crc()
+                    ),
+                    None => chunk.to_string(),
+                }
+            })
+            .collect();
+
+        for diagnostic in png.diagnostics() {
+            lines.push(format!("Warning: {}", diagnostic));
+
+            if let Some(raw) = &diagnostic.raw {
+                lines.push(format!("raw: {:02x?}", raw));
+            }
+        }
+
+        Ok(lines.join("\n"))
+    })
+}
+
+pub fn inspect(inspect_args: InspectArgs) -> Result<()> {
+    Batch::from_paths(&inspect_args.file_paths, &inspect_args.batch)?.run(|path: &Path| {
+        let file = view::map_file(path)?;
+        // CRCs are checked below so chunks with a wrong CRC are listed too
+        let png = PngView::scan(&file, VerifyCrc::None)?;
+        let mut lines: Vec<String> = png
+            .chunks()
+            .iter()
+            .map(|chunk| {
+                format!(
+                    "{} length: {} crc: {}{}",
+                    chunk.chunk_type(),
+                    chunk.length(),
+                    chunk.crc(),
+                    if chunk.verify_crc().is_err() {
+                        " (wrong CRC)"
+                    } else {
+                        ""
+                    }
+                )
+            })
+            .collect();
+
+        if !png.trailing_data().is_empty() {
+            lines.push(format!(
+                "Trailing data: {} bytes",
+                png.trailing_data().len()
+            ));
+        }
+
+        Ok(lines.join("\n"))
+    })
+}
+
+pub fn apply(apply_args: ApplyArgs) -> Result<()> {
+    let manifest = Manifest::from_file(&apply_args.manifest_path)?;
+    let base_dir = match apply_args.manifest_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let mut files: Vec<(PathBuf, Vec<&FileEntry>)> = Vec::new();
+
+    // Entries are grouped by file so every file is written once
+    for entry in manifest.files.iter() {
+        for path in entry.paths(base_dir)? {
+            match files.iter_mut().find(|(p, _)| *p == path) {
+                Some((_, entries)) => entries.push(entry),
+                None => files.push((path, vec![entry])),
+            }
+        }
+    }
+
+    let env = |name: &str| std::env::var(name).ok();
+    let mut outputs = Vec::new();
+
+    // Every file is changed in memory first, so a manifest which fails on
+    // one of them leaves all of them untouched
+    for (path, entries) in files {
+        let mut png = Png::from_file(path.clone())?;
+
+        println!("{}", path.display());
+
+        for entry in entries {
+            for change in entry.apply(&mut png, base_dir, &env)? {
+                println!("  {}", change);
+            }
+        }
+
+        outputs.push((path, png));
+    }
+
+    if apply_args.dry_run {
+        return Ok(());
+    }
+
+    let mut written: Vec<String> = Vec::new();
+
+    for (path, png) in outputs {
+        if let Err(e) = write_png(&png, path.clone(), &apply_args.backup) {
+            if written.is_empty() {
+                return Err(e);
+            }
+
+            return Err(format!(
+                "{}: {}, these files were already written: {}",
+                path.display(),
+                e,
+                written.join(", ")
+            )
+            .into());
+        }
+
+        written.push(path.display().to_string());
+    }
 
     Ok(())
 }
 
-pub fn decode(decode_args: DecodeArgs) -> Result<()> {
-    let png = Png::from_file(decode_args.file_path)?;
+pub fn diff(diff_args: DiffArgs) -> Result<()> {
+    let a = Png::from_file(diff_args.file_a)?;
+    let b = Png::from_file(diff_args.file_b)?;
+    let diff = PngDiff::new(&a, &b);
 
-    if let Some(chunk) = png.chunk_by_type(decode_args.chunk_type.as_str()) {
-        println!("{}", chunk);
+    if diff_args.format == "json" {
+        println!("{}", serde_json::to_string_pretty(&diff)?);
     } else {
-        eprintln!("Chunk type: {} not found", decode_args.chunk_type);
+        println!("{}", diff);
     }
 
     Ok(())
 }
 
-pub fn remove(remove_args: RemoveArgs) -> Result<()> {
-    let mut png = Png::from_file(remove_args.file_path.clone())?;
+pub fn copy(copy_args: CopyArgs) -> Result<()> {
+    let src = Png::from_file(copy_args.from)?;
+    let mut dst = Png::from_file(copy_args.to.clone())?;
+    let chunk_types = copy_args
+        .types
+        .iter()
+        .map(|t| ChunkType::from_str(t.as_str()))
+        .collect::<std::result::Result<Vec<ChunkType>, _>>()?;
+    let report = copy_chunks(&src, &mut dst, &chunk_types)?;
+
+    for warning in report.warnings.iter() {
+        eprintln!("Warning: {}", warning);
+    }
+
+    for (chunk_type, index) in report.copied.iter() {
+        println!("Copied {} into index {}", chunk_type, index);
+    }
 
-    png.remove_chunk(remove_args.chunk_type.as_str())?;
-    png.write_file(remove_args.file_path)
+    let output_file_path = copy_args.output.unwrap_or(copy_args.to);
+
+    write_png(&dst, output_file_path, &copy_args.backup)
 }
 
-pub fn print(print_args: PrintArgs) -> Result<()> {
-    let png = Png::from_file(print_args.file_path)?;
+pub fn strip(strip_args: StripArgs) -> Result<()> {
+    let batch = Batch::new(&strip_args.file_path, &strip_args.batch)?;
+    let options = StripOptions {
+        keep: strip_args.keep.clone(),
+    };
 
-    for chunk in png.chunks().iter() {
-        println!("{}", chunk);
+    if !batch.is_single() && strip_args.output_file.is_some() {
+        return Err("An output file can't be used when stripping multiple files".into());
+    }
+
+    batch.run(|path| {
+        let mut png = Png::from_file(path.to_path_buf())?;
+        let report = pngme::strip::strip(&mut png, &options)?;
+        let output_file_path = strip_args
+            .output_file
+            .clone()
+            .unwrap_or_else(|| path.to_path_buf());
+        let mut lines: Vec<String> = report
+            .removed
+            .iter()
+            .map(|(chunk_type, length)| format!("Removed {} ({} bytes)", chunk_type, length))
+            .collect();
+
+        if report.bytes_after_iend > 0 {
+            lines.push(format!(
+                "Removed {} bytes after IEND",
+                report.bytes_after_iend
+            ));
+        }
+
+        if lines.is_empty() {
+            lines.push(String::from("Nothing to remove"));
+        }
+
+        write_png(&png, output_file_path, &strip_args.backup)?;
+
+        Ok(lines.join("\n"))
+    })
+}
+
+pub fn repair(repair_args: RepairArgs) -> Result<()> {
+    let batch = Batch::new(&repair_args.file_path, &repair_args.batch)?.without_signature_check();
+    let options = RepairOptions {
+        fix_crc: repair_args.fix_crc,
+    };
+
+    if !batch.is_single() && repair_args.output_file.is_some() {
+        return Err("An output file can't be used when repairing multiple files".into());
+    }
+
+    batch.run(|path| {
+        let bytes = std::fs::read(path)?;
+        let (png, repairs) = pngme::repair::repair(&bytes, &options)?;
+        let output_file_path = repair_args
+            .output_file
+            .clone()
+            .unwrap_or_else(|| path.to_path_buf());
+
+        if repairs.is_empty() {
+            return Ok(String::from("Nothing to repair"));
+        }
+
+        write_png(&png, output_file_path, &repair_args.backup)?;
+
+        Ok(repairs
+            .iter()
+            .map(|r| r.to_string())
+            .collect::<Vec<String>>()
+            .join("\n"))
+    })
+}
+
+pub fn info(info_args: InfoArgs) -> Result<()> {
+    Batch::from_paths(&info_args.file_paths, &info_args.batch)?.run(|path| {
+        let png = Png::from_file(path.to_path_buf())?;
+        let ihdr = Ihdr::from_png(&png)?;
+        let mut lines = vec![
+            format!("Image: {}", ihdr),
+            format!("Chunks: {}", png.chunks().len()),
+        ];
+
+        if let Some(plte) = Plte::from_png(&png)? {
+            lines.push(format!("Palette: {} entries", plte.entries.len()));
+
+            if ihdr.color_type == 3 && AnimationControl::from_png(&png)?.is_none() {
+                lines.push(format!(
+                    "Palette capacity: {} bytes",
+                    stego::palette_capacity(&png)?
+                ));
+            }
+        }
+
+        for chunk in png.chunks() {
+            match KnownChunk::parse(chunk, Some(&ihdr)) {
+                Ok(KnownChunk::Ihdr(_))
+                | Ok(KnownChunk::Plte(_))
+                | Ok(KnownChunk::AnimationControl(_))
+                | Ok(KnownChunk::FrameControl(_)) => {}
+                Ok(known_chunk) => lines.push(format!("{}: {}", chunk.chunk_type(), known_chunk)),
+                Err(_) => {}
+            }
+        }
+
+        if let Some(animation_control) = AnimationControl::from_png(&png)? {
+            lines.push(format!("Animation: {}", animation_control));
+
+            for (index, frame) in png.frames()?.iter().enumerate() {
+                lines.push(format!(
+                    "  Frame {}: {}{}",
+                    index,
+                    frame.control,
+                    if frame.is_default_image() {
+                        " (default image)"
+                    } else {
+                        ""
+                    }
+                ));
+            }
+        }
+
+        Ok(lines.join("\n"))
+    })
+}
+
+pub fn exif(exif_args: ExifArgs) -> Result<()> {
+    match exif_args {
+        ExifArgs::List(args) => Batch::from_paths(&args.file_paths, &args.batch)?.run(|path| {
+            let exif = read_exif(path)?;
+            let lines: Vec<String> = exif
+                .entries()
+                .map(|(ifd, entry)| match exif_data::tag_name(ifd, entry.tag) {
+                    Some(name) => {
+                        format!("{} {} (0x{:04x}): {}", ifd, name, entry.tag, entry.value)
+                    }
+                    None => format!("{} 0x{:04x}: {}", ifd, entry.tag, entry.value),
+                })
+                .collect();
+
+            Ok(lines.join("\n"))
+        }),
+        ExifArgs::Get(args) => Batch::new(&args.file_path, &args.batch)?.run(|path| {
+            let exif = read_exif(path)?;
+            let (ifd, tag) = resolve_tag(&exif, &args.tag)?;
+
+            match exif.get(ifd, tag) {
+                Some(entry) => Ok(entry.value.to_string()),
+                None => Err(format!("Exif tag {} not found", args.tag).into()),
+            }
+        }),
+        ExifArgs::Set(args) => Batch::new(&args.file_path, &args.batch)?.run(|path| {
+            let mut png = Png::from_file(path.to_path_buf())?;
+            let mut exif = ExifData::from_png(&png)?.unwrap_or_else(ExifData::new);
+            let (ifd, tag) = resolve_tag(&exif, &args.tag)?;
+            let current = exif.get(ifd, tag).map(|e| &e.value);
+            let value = Value::parse_for(ifd, tag, current, &args.value)?;
+
+            exif.set(ifd, tag, value);
+
+            let chunk = exif.to_chunk()?;
+
+            if png.replace_chunk(chunk.clone()).is_err() {
+                png.insert_chunk_ordered(chunk);
+            }
+
+            write_png(&png, path.to_path_buf(), &args.backup)?;
+
+            Ok(String::new())
+        }),
+        ExifArgs::Remove(args) => {
+            if args.tags.is_empty() && !args.remove_gps {
+                return Err("Provide the tags to remove or --remove-gps".into());
+            }
+
+            Batch::new(&args.file_path, &args.batch)?.run(|path| {
+                let mut png = Png::from_file(path.to_path_buf())?;
+                let mut exif = match ExifData::from_png(&png)? {
+                    Some(exif) => exif,
+                    None => return Ok(String::from("No Exif profile found")),
+                };
+                let mut removed = if args.remove_gps {
+                    exif.remove_gps()
+                } else {
+                    0
+                };
+
+                for name in args.tags.iter() {
+                    let (ifd, tag) = resolve_tag(&exif, name)?;
+
+                    removed += exif.remove(ifd, tag) as usize;
+                }
+
+                if exif.is_empty() {
+                    png.remove_chunk("eXIf")?;
+                } else {
+                    png.replace_chunk(exif.to_chunk()?)?;
+                }
+
+                write_png(&png, path.to_path_buf(), &args.backup)?;
+
+                Ok(format!("Removed {} Exif tags", removed))
+            })
+        }
+    }
+}
+
+pub fn xmp(xmp_args: XmpArgs) -> Result<()> {
+    match xmp_args {
+        XmpArgs::Get(args) => Batch::new(&args.file_path, &args.batch)?.run(|path| {
+            let png = Png::from_file(path.to_path_buf())?;
+            let xmp = Xmp::from_png(&png)?.ok_or("No XMP packet found")?;
+
+            match &args.property {
+                Some(property) => xmp
+                    .get(property)?
+                    .ok_or_else(|| format!("XMP property {} not found", property).into()),
+                None => Ok(xmp.packet().to_string()),
+            }
+        }),
+        XmpArgs::Set(args) => Batch::new(&args.file_path, &args.batch)?.run(|path| {
+            let mut png = Png::from_file(path.to_path_buf())?;
+            let mut xmp = Xmp::from_png(&png)?.unwrap_or_else(Xmp::empty);
+
+            xmp.set(&args.property, &args.value, args.namespace.as_deref())?;
+            xmp.write_to(&mut png)?;
+            write_png(&png, path.to_path_buf(), &args.backup)?;
+
+            Ok(String::new())
+        }),
+        XmpArgs::Merge(args) => {
+            let sidecar = Xmp::new(&std::fs::read_to_string(&args.xmp_file)?)?;
+
+            Batch::new(&args.file_path, &args.batch)?.run(|path| {
+                let mut png = Png::from_file(path.to_path_buf())?;
+                let mut xmp = Xmp::from_png(&png)?.unwrap_or_else(Xmp::empty);
+
+                xmp.merge(&sidecar)?;
+                xmp.write_to(&mut png)?;
+                write_png(&png, path.to_path_buf(), &args.backup)?;
+
+                Ok(String::new())
+            })
+        }
+    }
+}
+
+pub fn icc(icc_args: IccArgs) -> Result<()> {
+    match icc_args {
+        IccArgs::Info(args) => Batch::from_paths(&args.file_paths, &args.batch)?.run(|path| {
+            let iccp = read_iccp(path)?;
+            let profile = IccProfile::parse(&iccp.profile)?;
+
+            Ok(format!(
+                "Name: {}\nSize: {} bytes\n{}",
+                iccp.name,
+                iccp.profile.len(),
+                profile
+            ))
+        }),
+        IccArgs::Extract(args) => {
+            let iccp = read_iccp(&args.file_path)?;
+
+            std::fs::write(&args.output_file, &iccp.profile)?;
+
+            Ok(())
+        }
+        IccArgs::Embed(args) => {
+            let profile = std::fs::read(&args.profile_file)?;
+            let iccp = Iccp {
+                name: match &args.name {
+                    Some(name) => name.clone(),
+                    None => IccProfile::parse(&profile)?.profile_name(),
+                },
+                profile,
+            };
+
+            Batch::new(&args.file_path, &args.batch)?.run(|path| {
+                let mut png = Png::from_file(path.to_path_buf())?;
+
+                icc::embed(&mut png, &iccp)?;
+                write_png(&png, path.to_path_buf(), &args.backup)?;
+
+                Ok(String::new())
+            })
+        }
+    }
+}
+
+pub fn serve(serve_args: ServeArgs) -> Result<()> {
+    server::run(
+        &serve_args.bind,
+        Limits {
+            max_body_size: serve_args.max_body_size,
+            max_concurrency: serve_args.max_concurrency,
+        },
+    )
+}
+
+pub fn tui(tui_args: TuiArgs) -> Result<()> {
+    chunk_browser::run(&tui_args.file_path, tui_args.backup)
+}
+
+fn read_iccp(path: &Path) -> Result<Iccp> {
+    let png = Png::from_file(path.to_path_buf())?;
+
+    icc::extract(&png)?.ok_or_else(|| "No ICC profile found".into())
+}
+
+fn read_exif(path: &Path) -> Result<ExifData> {
+    let png = Png::from_file(path.to_path_buf())?;
+
+    ExifData::from_png(&png)?.ok_or_else(|| "No Exif profile found".into())
+}
+
+/// Resolves a tag name, or a tag number looked up in the IFDs of the
+/// profile, numbers of unknown tags belong to IFD0
+fn resolve_tag(exif: &ExifData, name: &str) -> Result<(Ifd, u16)> {
+    if let Some(found) = exif_data::find_tag(name) {
+        return Ok(found);
+    }
+
+    let tag = match name.strip_prefix("0x") {
+        Some(hex) => u16::from_str_radix(hex, 16),
+        None => name.parse(),
+    }
+    .map_err(|_| format!("Unknown Exif tag {}", name))?;
+    let ifd = exif
+        .entries()
+        .find(|(_, e)| e.tag == tag)
+        .map(|(ifd, _)| ifd)
+        .unwrap_or(Ifd::Primary);
+
+    Ok((ifd, tag))
+}
+
+/// Hides the message held by the chunk with the provided method, see
+/// `EncodeArgs::method`
+pub fn embed_message(png: &mut Png, chunk: Chunk, method: &str) -> Result<()> {
+    match method {
+        "chunk" => png.insert_message(chunk)?,
+        "apng-frames" => *png = stego::hide_in_frames(png, chunk.chunk_type(), chunk.data())?,
+        "palette" => *png = stego::hide_in_palette(png, chunk.chunk_type(), chunk.data())?,
+        _ => return Err(format!("Unknown method {}", method).into()),
     }
 
     Ok(())
 }
+
+fn write_png(png: &Png, path: PathBuf, backup: &Option<String>) -> Result<()> {
+    match backup {
+        Some(suffix) => png.write_file_with_backup(path, suffix.as_str()),
+        None => png.write_file(path),
+    }
+}
@@ -0,0 +1,307 @@
+use crate::apng;
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::error::PngMeError;
+use crate::png::Png;
+use crate::Result;
+
+/// Ancillary chunks which may appear more than once in a PNG file, other
+/// standard chunks replace the destination chunk of the same type when
+/// copied
+const MULTIPLE_CHUNK_TYPES: [&str; 4] = ["iTXt", "sPLT", "tEXt", "zTXt"];
+
+/// Pairs of chunk types which must not appear together in a PNG file, the
+/// destination chunk of the other type is removed when one of them is
+/// copied
+const EXCLUSIVE_CHUNK_TYPES: [(&str, &str); 1] = [("iCCP", "sRGB")];
+
+/// Standard chunk types, chunks of any other type are treated as
+/// private chunks
+const STANDARD_CHUNK_TYPES: [&str; 23] = [
+    "IHDR", "PLTE", "IDAT", "IEND", "acTL", "bKGD", "cHRM", "cICP", "eXIf", "fcTL", "fdAT", "gAMA",
+    "hIST", "iCCP", "iTXt", "pHYs", "sBIT", "sPLT", "sRGB", "tEXt", "tIME", "tRNS", "zTXt",
+];
+
+/// Outcome of copying chunks between PNG files
+#[derive(Debug, Default)]
+pub struct CopyReport {
+    /// Type and destination index of every copied chunk
+    pub copied: Vec<(String, usize)>,
+    pub warnings: Vec<String>,
+}
+
+/// Copies every chunk from `src` matching one of `chunk_types` into `dst`,
+/// each chunk is inserted in the position the PNG specification requires
+/// for its type.
+///
+/// Critical chunks are never copied given that they define the image
+/// itself, neither are APNG chunks as their sequence numbers belong to the
+/// source animation. `dst` is left unchanged when a type is refused. Chunks which are not safe to copy depend on the image data, a
+/// warning is reported when they are copied into an image with different
+/// critical chunks.
+///
+/// Copying `iCCP` removes the `sRGB` chunk of `dst` and the other way
+/// around, as a file must not hold both. Copying both is refused.
+pub fn copy_chunks(src: &Png, dst: &mut Png, chunk_types: &[ChunkType]) -> Result<CopyReport> {
+    let mut report = CopyReport::default();
+    let same_image = critical_chunks(src) == critical_chunks(dst);
+
+    for (a, b) in EXCLUSIVE_CHUNK_TYPES.iter() {
+        let requested = |name: &str| chunk_types.iter().any(|t| t.to_string() == name);
+
+        if requested(a) && requested(b) {
+            return Err(format!("{} and {} can't be copied into the same file", a, b).into());
+        }
+    }
+
+    for chunk_type in chunk_types.iter() {
+        if chunk_type.is_critical() {
+            return Err(Box::new(PngMeError::CriticalChunk(chunk_type.to_string())));
+        }
+
+        if apng::is_animation_chunk_type(chunk_type) {
+            return Err(Box::new(PngMeError::InvalidAnimation(format!(
+                "{} chunks can't be copied between files",
+                chunk_type
+            ))));
+        }
+
+        if src.chunk_by_type(&chunk_type.to_string()).is_none() {
+            return Err(format!("Chunk type: {} not found in the source file", chunk_type).into());
+        }
+    }
+
+    for chunk_type in chunk_types.iter() {
+        let name = chunk_type.to_string();
+        let chunks: Vec<&Chunk> = src
+            .chunks()
+            .iter()
+            .filter(|c| c.chunk_type() == chunk_type)
+            .collect();
+
+        if !chunk_type.is_safe_to_copy() && !same_image {
+            report.warnings.push(format!(
+                "{} is not safe to copy and the destination image data differs from the source",
+                name
+            ));
+        }
+
+        if is_single_instance(&name) {
+            while dst.remove_chunk(&name).is_ok() {}
+        }
+
+        if let Some(other) = exclusive_chunk_type(&name) {
+            if dst.chunk_by_type(other).is_some() {
+                while dst.remove_chunk(other).is_ok() {}
+                report.warnings.push(format!(
+                    "Removed {} from the destination as it must not appear along with {}",
+                    other, name
+                ));
+            }
+        }
+
+        for chunk in chunks {
+            let exists = dst
+                .chunks()
+                .iter()
+                .any(|c| c.chunk_type() == chunk_type && c.data() == chunk.data());
+
+            if !exists {
+                report
+                    .copied
+                    .push((name.clone(), dst.insert_chunk_ordered(chunk.clone())));
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+fn is_single_instance(chunk_type: &str) -> bool {
+    STANDARD_CHUNK_TYPES.contains(&chunk_type) && !MULTIPLE_CHUNK_TYPES.contains(&chunk_type)
+}
+
+fn exclusive_chunk_type(chunk_type: &str) -> Option<&'static str> {
+    EXCLUSIVE_CHUNK_TYPES.iter().find_map(|(a, b)| {
+        if *a == chunk_type {
+            Some(*b)
+        } else if *b == chunk_type {
+            Some(*a)
+        } else {
+            None
+        }
+    })
+}
+
+fn critical_chunks(png: &Png) -> Vec<([u8; 4], &[u8])> {
+    png.chunks()
+        .iter()
+        .filter(|c| c.chunk_type().is_critical())
+        .map(|c| (c.chunk_type().bytes(), c.data()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::apng::{AnimationControl, BlendOp, DisposeOp, FrameControl};
+
+    fn chunk_from_strings(chunk_type: &str, data: &str) -> Chunk {
+        Chunk::new(
+            ChunkType::from_str(chunk_type).unwrap(),
+            data.as_bytes().to_vec(),
+        )
+    }
+
+    fn testing_png(image_data: &str, extra: Vec<Chunk>) -> Png {
+        let mut png = Png::from_chunks(vec![
+            chunk_from_strings("IHDR", "header"),
+            chunk_from_strings("IDAT", image_data),
+            chunk_from_strings("IEND", ""),
+        ]);
+
+        for chunk in extra {
+            png.insert_chunk_ordered(chunk);
+        }
+
+        png
+    }
+
+    fn types(png: &Png) -> Vec<String> {
+        png.chunks()
+            .iter()
+            .map(|c| c.chunk_type().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_copy_chunks() {
+        let src = testing_png(
+            "pixels",
+            vec![
+                chunk_from_strings("gAMA", "new gamma"),
+                chunk_from_strings("tEXt", "Comment\0Hello"),
+                chunk_from_strings("ruSt", "secret"),
+            ],
+        );
+        let mut dst = testing_png("pixels", vec![chunk_from_strings("gAMA", "old gamma")]);
+        let chunk_types = vec![
+            ChunkType::from_str("gAMA").unwrap(),
+            ChunkType::from_str("tEXt").unwrap(),
+            ChunkType::from_str("ruSt").unwrap(),
+        ];
+        let report = copy_chunks(&src, &mut dst, &chunk_types).unwrap();
+
+        assert_eq!(report.copied.len(), 3);
+        assert!(report.warnings.is_empty());
+        assert_eq!(
+            types(&dst),
+            vec!["IHDR", "gAMA", "IDAT", "tEXt", "ruSt", "IEND"]
+        );
+        assert_eq!(
+            &dst.chunk_by_type("gAMA").unwrap().data_as_string().unwrap(),
+            "new gamma"
+        );
+    }
+
+    #[test]
+    fn test_copy_critical_chunk() {
+        let src = testing_png("pixels", vec![]);
+        let mut dst = testing_png("pixels", vec![]);
+        let result = copy_chunks(&src, &mut dst, &[ChunkType::from_str("IDAT").unwrap()]);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_copy_unsafe_chunk_into_different_image() {
+        let src = testing_png("pixels", vec![chunk_from_strings("ruST", "unsafe")]);
+        let mut same = testing_png("pixels", vec![]);
+        let mut different = testing_png("other pixels", vec![]);
+        let chunk_types = vec![ChunkType::from_str("ruST").unwrap()];
+
+        assert!(copy_chunks(&src, &mut same, &chunk_types)
+            .unwrap()
+            .warnings
+            .is_empty());
+        assert_eq!(
+            copy_chunks(&src, &mut different, &chunk_types)
+                .unwrap()
+                .warnings
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_copy_exclusive_chunks() {
+        let src = testing_png(
+            "pixels",
+            vec![
+                chunk_from_strings("iCCP", "profile"),
+                chunk_from_strings("sRGB", "0"),
+            ],
+        );
+        let mut dst = testing_png("pixels", vec![chunk_from_strings("sRGB", "0")]);
+        let report = copy_chunks(&src, &mut dst, &[ChunkType::from_str("iCCP").unwrap()]).unwrap();
+
+        assert_eq!(report.warnings.len(), 1);
+        assert_eq!(types(&dst), vec!["IHDR", "iCCP", "IDAT", "IEND"]);
+        assert!(copy_chunks(
+            &src,
+            &mut dst,
+            &[
+                ChunkType::from_str("iCCP").unwrap(),
+                ChunkType::from_str("sRGB").unwrap()
+            ]
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_copy_animation_chunk() {
+        let testing_apng = |delay_num| {
+            let frame_control = FrameControl {
+                sequence_number: 0,
+                width: 1,
+                height: 1,
+                x_offset: 0,
+                y_offset: 0,
+                delay_num,
+                delay_den: 10,
+                dispose_op: DisposeOp::None,
+                blend_op: BlendOp::Over,
+            };
+
+            testing_png(
+                "pixels",
+                vec![
+                    AnimationControl {
+                        num_frames: 1,
+                        num_plays: 0,
+                    }
+                    .to_chunk(),
+                    frame_control.to_chunk(),
+                ],
+            )
+        };
+        let src = testing_apng(1);
+        let mut dst = testing_apng(2);
+        let before = dst.as_bytes();
+        let result = copy_chunks(&src, &mut dst, &[ChunkType::from_str("fcTL").unwrap()]);
+
+        assert!(result.is_err());
+        assert_eq!(dst.as_bytes(), before);
+    }
+
+    #[test]
+    fn test_copy_missing_chunk() {
+        let src = testing_png("pixels", vec![]);
+        let mut dst = testing_png("pixels", vec![]);
+        let result = copy_chunks(&src, &mut dst, &[ChunkType::from_str("ruSt").unwrap()]);
+
+        assert!(result.is_err());
+    }
+}
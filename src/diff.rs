@@ -0,0 +1,472 @@
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+
+use serde::Serialize;
+
+use crate::chunk::Chunk;
+use crate::png::Png;
+use crate::text::{decompress, keyword_and_text};
+
+/// Chunk level differences between two PNG files.
+///
+/// Chunks are matched by type and occurrence, textual chunks are matched
+/// by their keyword instead, so a `tEXt` chunk with a new value is reported
+/// as a change rather than as a removal followed by an addition. `IDAT`
+/// chunks are compared as a whole in `pixel_data` given that encoders are
+/// free to split the image data in any number of chunks.
+#[derive(Debug, Serialize)]
+pub struct PngDiff {
+    pub differences: Vec<ChunkDifference>,
+    pub pixel_data: PixelData,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ChunkDifference {
+    Added {
+        chunk_type: String,
+        index: usize,
+        length: u32,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        keyword: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        text: Option<String>,
+    },
+    Removed {
+        chunk_type: String,
+        index: usize,
+        length: u32,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        keyword: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        text: Option<String>,
+    },
+    Reordered {
+        chunk_type: String,
+        from: usize,
+        to: usize,
+    },
+    Changed {
+        chunk_type: String,
+        index: usize,
+        old_length: u32,
+        new_length: u32,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        keyword: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        old_text: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        new_text: Option<String>,
+    },
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PixelData {
+    /// `IDAT` data is byte-identical
+    Identical,
+    /// `IDAT` data is compressed differently but the decompressed
+    /// image data is byte-identical
+    IdenticalAfterDecompression,
+    Different,
+}
+
+struct Entry<'a> {
+    key: String,
+    index: usize,
+    chunk: &'a Chunk,
+    text: Option<(String, String)>,
+}
+
+impl PngDiff {
+    pub fn new(a: &Png, b: &Png) -> Self {
+        let entries_a = entries(a);
+        let entries_b = entries(b);
+        let by_key_a: HashMap<&str, &Entry> =
+            entries_a.iter().map(|e| (e.key.as_str(), e)).collect();
+        let by_key_b: HashMap<&str, &Entry> =
+            entries_b.iter().map(|e| (e.key.as_str(), e)).collect();
+        let common_a: Vec<&str> = entries_a
+            .iter()
+            .map(|e| e.key.as_str())
+            .filter(|k| by_key_b.contains_key(k))
+            .collect();
+        let common_b: Vec<&str> = entries_b
+            .iter()
+            .map(|e| e.key.as_str())
+            .filter(|k| by_key_a.contains_key(k))
+            .collect();
+        let in_order = longest_common_subsequence(&common_a, &common_b);
+        let mut differences = Vec::new();
+
+        for entry in entries_a.iter() {
+            if !by_key_b.contains_key(entry.key.as_str()) {
+                let (keyword, text) = split_text(&entry.text);
+
+                differences.push(ChunkDifference::Removed {
+                    chunk_type: entry.chunk.chunk_type().to_string(),
+                    index: entry.index,
+                    length: entry.chunk.length(),
+                    keyword,
+                    text,
+                });
+            }
+        }
+
+        for entry in entries_b.iter() {
+            let chunk_type = entry.chunk.chunk_type().to_string();
+            let old = match by_key_a.get(entry.key.as_str()) {
+                Some(old) => old,
+                None => {
+                    let (keyword, text) = split_text(&entry.text);
+
+                    differences.push(ChunkDifference::Added {
+                        chunk_type,
+                        index: entry.index,
+                        length: entry.chunk.length(),
+                        keyword,
+                        text,
+                    });
+                    continue;
+                }
+            };
+
+            if !in_order.contains(&entry.key.as_str()) {
+                differences.push(ChunkDifference::Reordered {
+                    chunk_type: chunk_type.clone(),
+                    from: old.index,
+                    to: entry.index,
+                });
+            }
+
+            if old.chunk.data() != entry.chunk.data() {
+                let (keyword, old_text) = split_text(&old.text);
+                let (_, new_text) = split_text(&entry.text);
+
+                differences.push(ChunkDifference::Changed {
+                    chunk_type,
+                    index: entry.index,
+                    old_length: old.chunk.length(),
+                    new_length: entry.chunk.length(),
+                    keyword,
+                    old_text,
+                    new_text,
+                });
+            }
+        }
+
+        PngDiff {
+            differences,
+            pixel_data: compare_image_data(a, b),
+        }
+    }
+
+    /// Returns true if both files have the same chunks in the same order
+    /// and the same image data
+    pub fn is_empty(&self) -> bool {
+        self.differences.is_empty() && self.pixel_data == PixelData::Identical
+    }
+}
+
+impl Display for ChunkDifference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChunkDifference::Added {
+                chunk_type,
+                index,
+                length,
+                keyword,
+                text,
+            } => {
+                write!(f, "+ {}", chunk_type)?;
+                write_text(f, keyword, text)?;
+                write!(f, " (index {}, {} bytes)", index, length)
+            }
+            ChunkDifference::Removed {
+                chunk_type,
+                index,
+                length,
+                keyword,
+                text,
+            } => {
+                write!(f, "- {}", chunk_type)?;
+                write_text(f, keyword, text)?;
+                write!(f, " (index {}, {} bytes)", index, length)
+            }
+            ChunkDifference::Reordered {
+                chunk_type,
+                from,
+                to,
+            } => write!(f, "> {} moved from index {} to {}", chunk_type, from, to),
+            ChunkDifference::Changed {
+                chunk_type,
+                index,
+                old_length,
+                new_length,
+                keyword,
+                old_text,
+                new_text,
+            } => match (keyword, old_text, new_text) {
+                (Some(keyword), Some(old_text), Some(new_text)) => write!(
+                    f,
+                    "~ {} [{}] {:?} -> {:?}",
+                    chunk_type, keyword, old_text, new_text
+                ),
+                _ => write!(
+                    f,
+                    "~ {} (index {}, {} -> {} bytes)",
+                    chunk_type, index, old_length, new_length
+                ),
+            },
+        }
+    }
+}
+
+impl Display for PixelData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PixelData::Identical => write!(f, "identical"),
+            PixelData::IdenticalAfterDecompression => {
+                write!(f, "identical (compressed differently)")
+            }
+            PixelData::Different => write!(f, "different"),
+        }
+    }
+}
+
+impl Display for PngDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for difference in self.differences.iter() {
+            writeln!(f, "{}", difference)?;
+        }
+
+        write!(f, "Pixel data: {}", self.pixel_data)
+    }
+}
+
+fn write_text(
+    f: &mut fmt::Formatter<'_>,
+    keyword: &Option<String>,
+    text: &Option<String>,
+) -> fmt::Result {
+    if let (Some(keyword), Some(text)) = (keyword, text) {
+        write!(f, " [{}] {:?}", keyword, text)?;
+    }
+
+    Ok(())
+}
+
+fn split_text(text: &Option<(String, String)>) -> (Option<String>, Option<String>) {
+    match text {
+        Some((keyword, text)) => (Some(keyword.clone()), Some(text.clone())),
+        None => (None, None),
+    }
+}
+
+/// Builds the entries used to match chunks between files, `IDAT` chunks
+/// are left out
+fn entries(png: &Png) -> Vec<Entry<'_>> {
+    let mut occurrences: HashMap<String, usize> = HashMap::new();
+
+    png.chunks()
+        .iter()
+        .enumerate()
+        .filter(|(_, chunk)| &chunk.chunk_type().bytes() != b"IDAT")
+        .map(|(index, chunk)| {
+            let text = keyword_and_text(chunk).and_then(|t| t.ok());
+            let name = match &text {
+                Some((keyword, _)) => format!("{}:{}", chunk.chunk_type(), keyword),
+                None => chunk.chunk_type().to_string(),
+            };
+            let occurrence = occurrences.entry(name.clone()).or_insert(0);
+            let key = format!("{}#{}", name, occurrence);
+
+            *occurrence += 1;
+
+            Entry {
+                key,
+                index,
+                chunk,
+                text,
+            }
+        })
+        .collect()
+}
+
+fn longest_common_subsequence<'a>(a: &[&'a str], b: &[&'a str]) -> Vec<&'a str> {
+    let mut lengths = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lengths[i][j] = if a[i] == b[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut sequence = Vec::new();
+    let (mut i, mut j) = (0, 0);
+
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            sequence.push(a[i]);
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+
+    sequence
+}
+
+fn image_data(png: &Png) -> Vec<u8> {
+    png.chunks()
+        .iter()
+        .filter(|chunk| &chunk.chunk_type().bytes() == b"IDAT")
+        .flat_map(|chunk| chunk.data().iter().copied())
+        .collect()
+}
+
+fn compare_image_data(a: &Png, b: &Png) -> PixelData {
+    let data_a = image_data(a);
+    let data_b = image_data(b);
+
+    if data_a == data_b {
+        return PixelData::Identical;
+    }
+
+    match (decompress(&data_a), decompress(&data_b)) {
+        (Ok(a), Ok(b)) if a == b => PixelData::IdenticalAfterDecompression,
+        _ => PixelData::Different,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::chunk_type::ChunkType;
+    use crate::text::{compress, Text};
+
+    fn chunk_from_strings(chunk_type: &str, data: &[u8]) -> Chunk {
+        Chunk::new(ChunkType::from_str(chunk_type).unwrap(), data.to_vec())
+    }
+
+    fn testing_png(extra: Vec<Chunk>) -> Png {
+        let mut chunks = vec![
+            chunk_from_strings("IHDR", b"header"),
+            Text::new("Comment", "Hello").to_chunk().unwrap(),
+            chunk_from_strings("gAMA", &[0, 0, 177, 143]),
+            chunk_from_strings("IDAT", &compress(b"pixels").unwrap()),
+        ];
+
+        chunks.extend(extra);
+        chunks.push(chunk_from_strings("IEND", b""));
+
+        Png::from_chunks(chunks)
+    }
+
+    #[test]
+    fn test_identical_files() {
+        let diff = PngDiff::new(&testing_png(vec![]), &testing_png(vec![]));
+
+        assert!(diff.is_empty());
+    }
+
+    #[test]
+    fn test_added_and_removed_chunks() {
+        let a = testing_png(vec![chunk_from_strings("ruSt", b"secret")]);
+        let b = testing_png(vec![chunk_from_strings("tIME", &[7, 228, 1, 1, 0, 0, 0])]);
+        let diff = PngDiff::new(&a, &b);
+
+        assert_eq!(diff.differences.len(), 2);
+        assert!(matches!(
+            &diff.differences[0],
+            ChunkDifference::Removed { chunk_type, index: 4, .. } if chunk_type == "ruSt"
+        ));
+        assert!(matches!(
+            &diff.differences[1],
+            ChunkDifference::Added { chunk_type, index: 4, .. } if chunk_type == "tIME"
+        ));
+        assert_eq!(diff.pixel_data, PixelData::Identical);
+    }
+
+    #[test]
+    fn test_changed_text() {
+        let a = testing_png(vec![]);
+        let mut b = testing_png(vec![]);
+
+        b.replace_chunk(Text::new("Comment", "Bye").to_chunk().unwrap())
+            .unwrap();
+
+        let diff = PngDiff::new(&a, &b);
+
+        assert_eq!(
+            diff.differences,
+            vec![ChunkDifference::Changed {
+                chunk_type: String::from("tEXt"),
+                index: 1,
+                old_length: 13,
+                new_length: 11,
+                keyword: Some(String::from("Comment")),
+                old_text: Some(String::from("Hello")),
+                new_text: Some(String::from("Bye")),
+            }]
+        );
+        assert_eq!(
+            diff.differences[0].to_string(),
+            "~ tEXt [Comment] \"Hello\" -> \"Bye\""
+        );
+    }
+
+    #[test]
+    fn test_reordered_chunks() {
+        let a = testing_png(vec![]);
+        let mut b = testing_png(vec![]);
+        let gama = b.remove_chunk("gAMA").unwrap();
+
+        b.insert_chunk(1, gama).unwrap();
+
+        let diff = PngDiff::new(&a, &b);
+
+        assert_eq!(diff.differences.len(), 1);
+        assert!(matches!(
+            &diff.differences[0],
+            ChunkDifference::Reordered { from: 1, to: 2, .. }
+                | ChunkDifference::Reordered { from: 2, to: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn test_pixel_data_compressed_differently() {
+        let a = testing_png(vec![]);
+        let mut b = testing_png(vec![]);
+        let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::none());
+
+        std::io::Write::write_all(&mut encoder, b"pixels").unwrap();
+        b.replace_chunk(chunk_from_strings("IDAT", &encoder.finish().unwrap()))
+            .unwrap();
+
+        let diff = PngDiff::new(&a, &b);
+
+        assert_eq!(diff.pixel_data, PixelData::IdenticalAfterDecompression);
+        assert!(diff.differences.is_empty());
+    }
+
+    #[test]
+    fn test_diff_as_json() {
+        let a = testing_png(vec![]);
+        let b = testing_png(vec![chunk_from_strings("ruSt", b"secret")]);
+        let json = serde_json::to_value(PngDiff::new(&a, &b)).unwrap();
+
+        assert_eq!(json["differences"][0]["kind"], "added");
+        assert_eq!(json["differences"][0]["chunk_type"], "ruSt");
+        assert_eq!(json["pixel_data"], "identical");
+    }
+}
@@ -1,12 +1,24 @@
-use std::fmt;
+use alloc::string::String;
+use core::fmt;
 
 #[derive(Debug)]
 pub enum PngMeError {
+    CriticalChunk(String),
+    InvalidAnimation(String),
     InvalidChunkLength(String),
     InvalidBytes(String),
     InvalidChunkType,
     InvalidCRC,
+    InvalidChunkData(String),
+    InvalidChunkOrder(String),
+    InvalidExif(String),
+    InvalidIccProfile(String),
+    InvalidImageData(String),
     InvalidPNGFileHeader,
+    InvalidXmp(String),
+    PayloadNotFound(String),
+    PayloadTooLarge(usize, usize),
+    TruncatedChunk,
     UnexistentChunkType,
     UnableToCreateFile(String),
     UnableToWriteOutputFile(String),
@@ -15,6 +27,10 @@ pub enum PngMeError {
 impl fmt::Display for PngMeError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            PngMeError::CriticalChunk(chunk) => {
+                write!(f, "The chunk {} is critical and can't be added", chunk)
+            }
+            PngMeError::InvalidAnimation(reason) => write!(f, "Invalid animation: {}", reason),
             PngMeError::InvalidChunkLength(chunk) => write!(
                 f,
                 "The chunk {} have a {} length, the max length is 4",
@@ -24,7 +40,22 @@ impl fmt::Display for PngMeError {
             PngMeError::InvalidBytes(chunk) => write!(f, "The chunk {} has invalid bytes", chunk),
             PngMeError::InvalidChunkType => write!(f, "Invalid chunk type provided"),
             PngMeError::InvalidCRC => write!(f, "CRC IEEE Checksum didn't matched"),
+            PngMeError::InvalidChunkData(reason) => write!(f, "Invalid chunk data: {}", reason),
+            PngMeError::InvalidChunkOrder(reason) => write!(f, "Invalid chunk order: {}", reason),
+            PngMeError::InvalidExif(reason) => write!(f, "Invalid Exif profile: {}", reason),
+            PngMeError::InvalidIccProfile(reason) => write!(f, "Invalid ICC profile: {}", reason),
+            PngMeError::InvalidImageData(reason) => write!(f, "Invalid image data: {}", reason),
             PngMeError::InvalidPNGFileHeader => write!(f, "Invalid PNG file header"),
+            PngMeError::InvalidXmp(reason) => write!(f, "Invalid XMP packet: {}", reason),
+            PngMeError::PayloadNotFound(tag) => {
+                write!(f, "No payload tagged {} was found", tag)
+            }
+            PngMeError::PayloadTooLarge(needed, available) => write!(
+                f,
+                "The payload needs {} bytes but only {} are available",
+                needed, available
+            ),
+            PngMeError::TruncatedChunk => write!(f, "The chunk is truncated"),
             PngMeError::UnexistentChunkType => write!(f, "The provided chunk type doesn't exists"),
             PngMeError::UnableToCreateFile(err_message) => {
                 write!(f, "Unable to create file: {}", err_message)
@@ -36,4 +67,4 @@ impl fmt::Display for PngMeError {
     }
 }
 
-impl std::error::Error for PngMeError {}
+impl core::error::Error for PngMeError {}
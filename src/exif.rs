@@ -0,0 +1,937 @@
+use std::convert::{TryFrom, TryInto};
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use crate::ancillary::Exif;
+use crate::chunk::Chunk;
+use crate::error::PngMeError;
+use crate::png::Png;
+use crate::Result;
+
+/// Tag of the IFD0 entry pointing to the Exif IFD
+const EXIF_POINTER: u16 = 0x8769;
+/// Tag of the IFD0 entry pointing to the GPS IFD
+const GPS_POINTER: u16 = 0x8825;
+/// Tag of the Exif IFD entry pointing to the interoperability IFD
+const INTEROPERABILITY_POINTER: u16 = 0xa005;
+/// Tag of the IFD1 entry pointing to the JPEG thumbnail
+const THUMBNAIL_POINTER: u16 = 0x0201;
+/// Tag of the IFD1 entry holding the length of the JPEG thumbnail
+const THUMBNAIL_LENGTH: u16 = 0x0202;
+
+/// Tags whose data holds offsets into the TIFF structure which can't be
+/// moved when the profile is written back: `StripOffsets`, `TileOffsets`,
+/// `SubIFDs` and `MakerNote`
+const UNMOVABLE_TAGS: [u16; 4] = [0x0111, 0x0144, 0x014a, 0x927c];
+
+/// Image file directories holding the tags of an Exif profile
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Ifd {
+    Primary,
+    Exif,
+    Gps,
+    Interoperability,
+    /// IFD1, describing the thumbnail
+    Thumbnail,
+}
+
+/// Value of a TIFF field, every field holds a list of values of its type
+/// except `Ascii` which holds a single string
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Byte(Vec<u8>),
+    Ascii(String),
+    Short(Vec<u16>),
+    Long(Vec<u32>),
+    Rational(Vec<(u32, u32)>),
+    SByte(Vec<i8>),
+    Undefined(Vec<u8>),
+    SShort(Vec<i16>),
+    SLong(Vec<i32>),
+    SRational(Vec<(i32, i32)>),
+    Float(Vec<f32>),
+    Double(Vec<f64>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub tag: u16,
+    pub value: Value,
+}
+
+/// Exif profile parsed from the TIFF structure of an `eXIf` chunk. The
+/// primary image, Exif, GPS, interoperability and thumbnail IFDs are
+/// supported along with a JPEG thumbnail, they are all written back.
+///
+/// Profiles holding tags whose data points into the TIFF structure, such as
+/// `MakerNote`, can't be written back as those offsets would be wrong once
+/// the profile is laid out again.
+///
+/// Reference: https://www.cipa.jp/std/documents/e/DC-X008-Translation-2019-E.pdf
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExifData {
+    pub big_endian: bool,
+    pub primary: Vec<Entry>,
+    pub exif: Vec<Entry>,
+    pub gps: Vec<Entry>,
+    pub interoperability: Vec<Entry>,
+    pub thumbnail: Vec<Entry>,
+    /// JPEG thumbnail referenced by IFD1
+    pub thumbnail_data: Vec<u8>,
+}
+
+/// Names and TIFF types of the most common tags
+const TAGS: [(Ifd, u16, &str, u16); 39] = [
+    (Ifd::Primary, 0x010e, "ImageDescription", 2),
+    (Ifd::Primary, 0x010f, "Make", 2),
+    (Ifd::Primary, 0x0110, "Model", 2),
+    (Ifd::Primary, 0x0112, "Orientation", 3),
+    (Ifd::Primary, 0x011a, "XResolution", 5),
+    (Ifd::Primary, 0x011b, "YResolution", 5),
+    (Ifd::Primary, 0x0128, "ResolutionUnit", 3),
+    (Ifd::Primary, 0x0131, "Software", 2),
+    (Ifd::Primary, 0x0132, "DateTime", 2),
+    (Ifd::Primary, 0x013b, "Artist", 2),
+    (Ifd::Primary, 0x8298, "Copyright", 2),
+    (Ifd::Exif, 0x829a, "ExposureTime", 5),
+    (Ifd::Exif, 0x829d, "FNumber", 5),
+    (Ifd::Exif, 0x8822, "ExposureProgram", 3),
+    (Ifd::Exif, 0x8827, "ISOSpeedRatings", 3),
+    (Ifd::Exif, 0x9000, "ExifVersion", 7),
+    (Ifd::Exif, 0x9003, "DateTimeOriginal", 2),
+    (Ifd::Exif, 0x9004, "DateTimeDigitized", 2),
+    (Ifd::Exif, 0x9201, "ShutterSpeedValue", 10),
+    (Ifd::Exif, 0x9202, "ApertureValue", 5),
+    (Ifd::Exif, 0x9209, "Flash", 3),
+    (Ifd::Exif, 0x920a, "FocalLength", 5),
+    (Ifd::Exif, 0x927c, "MakerNote", 7),
+    (Ifd::Exif, 0x9286, "UserComment", 7),
+    (Ifd::Exif, 0xa001, "ColorSpace", 3),
+    (Ifd::Exif, 0xa002, "PixelXDimension", 4),
+    (Ifd::Exif, 0xa003, "PixelYDimension", 4),
+    (Ifd::Exif, 0xa433, "LensMake", 2),
+    (Ifd::Exif, 0xa434, "LensModel", 2),
+    (Ifd::Gps, 0x0000, "GPSVersionID", 1),
+    (Ifd::Gps, 0x0001, "GPSLatitudeRef", 2),
+    (Ifd::Gps, 0x0002, "GPSLatitude", 5),
+    (Ifd::Gps, 0x0003, "GPSLongitudeRef", 2),
+    (Ifd::Gps, 0x0004, "GPSLongitude", 5),
+    (Ifd::Gps, 0x0005, "GPSAltitudeRef", 1),
+    (Ifd::Gps, 0x0006, "GPSAltitude", 5),
+    (Ifd::Gps, 0x0007, "GPSTimeStamp", 5),
+    (Ifd::Gps, 0x001d, "GPSDateStamp", 2),
+    (Ifd::Interoperability, 0x0001, "InteroperabilityIndex", 2),
+];
+
+/// Name of a known tag of the provided IFD
+pub fn tag_name(ifd: Ifd, tag: u16) -> Option<&'static str> {
+    TAGS.iter()
+        .find(|(i, t, _, _)| *i == ifd && *t == tag)
+        .map(|(_, _, name, _)| *name)
+}
+
+/// Looks up a tag by its name, case insensitive
+pub fn find_tag(name: &str) -> Option<(Ifd, u16)> {
+    TAGS.iter()
+        .find(|(_, _, n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(ifd, tag, _, _)| (*ifd, *tag))
+}
+
+impl ExifData {
+    /// Creates an empty big endian profile
+    pub fn new() -> Self {
+        ExifData {
+            big_endian: true,
+            primary: Vec::new(),
+            exif: Vec::new(),
+            gps: Vec::new(),
+            interoperability: Vec::new(),
+            thumbnail: Vec::new(),
+            thumbnail_data: Vec::new(),
+        }
+    }
+
+    /// Retrieves the Exif profile of the provided PNG file, if any
+    pub fn from_png(png: &Png) -> Result<Option<Self>> {
+        png.chunk_by_type("eXIf")
+            .map(|chunk| ExifData::parse(&Exif::try_from(chunk)?.data))
+            .transpose()
+    }
+
+    /// Parses a TIFF structure
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        let reader = Reader {
+            bytes,
+            big_endian: match bytes.get(0..4) {
+                Some(b"MM\0*") => true,
+                Some(b"II*\0") => false,
+                _ => return Err(invalid("missing TIFF header")),
+            },
+        };
+        let primary_offset = reader.u32(4)?;
+        let mut primary = reader.ifd(primary_offset)?;
+        let mut exif = reader.linked_ifd(&primary, EXIF_POINTER)?;
+        let gps = reader.linked_ifd(&primary, GPS_POINTER)?;
+        let interoperability = reader.linked_ifd(&exif, INTEROPERABILITY_POINTER)?;
+        let mut thumbnail = match reader.next_ifd(primary_offset)? {
+            0 => Vec::new(),
+            offset => reader.ifd(offset)?,
+        };
+        let thumbnail_data = match pointer(&thumbnail, THUMBNAIL_POINTER)? {
+            Some(offset) => {
+                let length = pointer(&thumbnail, THUMBNAIL_LENGTH)?
+                    .ok_or_else(|| invalid("the thumbnail length is missing"))?;
+
+                reader.slice(offset as usize, length as usize)?.to_vec()
+            }
+            None => Vec::new(),
+        };
+
+        primary.retain(|e| e.tag != EXIF_POINTER && e.tag != GPS_POINTER);
+        exif.retain(|e| e.tag != INTEROPERABILITY_POINTER);
+        thumbnail.retain(|e| e.tag != THUMBNAIL_POINTER && e.tag != THUMBNAIL_LENGTH);
+
+        Ok(ExifData {
+            big_endian: reader.big_endian,
+            primary,
+            exif,
+            gps,
+            interoperability,
+            thumbnail,
+            thumbnail_data,
+        })
+    }
+
+    /// Writes the TIFF structure, IFD0 is followed by the Exif,
+    /// interoperability, GPS and thumbnail IFDs and the JPEG thumbnail.
+    /// Entries of every IFD are written in ascending tag order.
+    ///
+    /// Fails if a tag holding offsets into the TIFF structure is found, see
+    /// `UNMOVABLE_TAGS`.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        if let Some((ifd, entry)) = self
+            .entries()
+            .find(|(_, e)| UNMOVABLE_TAGS.contains(&e.tag))
+        {
+            return Err(invalid(&format!(
+                "the {} tag 0x{:04x} holds offsets which can't be moved, remove it to edit the profile",
+                ifd, entry.tag
+            )));
+        }
+
+        let writer = Writer {
+            big_endian: self.big_endian,
+        };
+        let mut primary = self.primary.clone();
+        let mut exif = self.exif.clone();
+        let mut thumbnail = self.thumbnail.clone();
+
+        // Pointers are added before the offsets are known, their values
+        // always fit in the entry so the length of each IFD doesn't change
+        // once they are set
+        if !self.exif.is_empty() || !self.interoperability.is_empty() {
+            set_long(&mut primary, EXIF_POINTER, 0);
+        }
+
+        if !self.gps.is_empty() {
+            set_long(&mut primary, GPS_POINTER, 0);
+        }
+
+        if !self.interoperability.is_empty() {
+            set_long(&mut exif, INTEROPERABILITY_POINTER, 0);
+        }
+
+        if !self.thumbnail_data.is_empty() {
+            set_long(&mut thumbnail, THUMBNAIL_POINTER, 0);
+            set_long(
+                &mut thumbnail,
+                THUMBNAIL_LENGTH,
+                self.thumbnail_data.len() as u32,
+            );
+        }
+
+        let exif_offset = 8 + writer.ifd_length(&primary);
+        let interoperability_offset = exif_offset + writer.ifd_length(&exif);
+        let gps_offset = interoperability_offset + writer.ifd_length(&self.interoperability);
+        let thumbnail_offset = gps_offset + writer.ifd_length(&self.gps);
+        let thumbnail_data_offset = thumbnail_offset + writer.ifd_length(&thumbnail);
+
+        set_pointer(&mut primary, EXIF_POINTER, exif_offset);
+        set_pointer(&mut primary, GPS_POINTER, gps_offset);
+        set_pointer(&mut exif, INTEROPERABILITY_POINTER, interoperability_offset);
+        set_pointer(&mut thumbnail, THUMBNAIL_POINTER, thumbnail_data_offset);
+
+        let mut bytes = if self.big_endian {
+            b"MM\0*".to_vec()
+        } else {
+            b"II*\0".to_vec()
+        };
+        let next = if thumbnail.is_empty() {
+            0
+        } else {
+            thumbnail_offset
+        };
+
+        bytes.extend(writer.u32(8));
+        writer.ifd(&mut bytes, &primary, next);
+
+        for entries in [&exif, &self.interoperability, &self.gps, &thumbnail] {
+            if !entries.is_empty() {
+                writer.ifd(&mut bytes, entries, 0);
+            }
+        }
+
+        bytes.extend(&self.thumbnail_data);
+
+        Ok(bytes)
+    }
+
+    /// Builds an `eXIf` chunk holding the profile, see `to_bytes`
+    pub fn to_chunk(&self) -> Result<Chunk> {
+        Ok(Exif {
+            data: self.to_bytes()?,
+        }
+        .to_chunk())
+    }
+
+    /// Returns true if none of the IFDs hold a tag and there is no thumbnail
+    pub fn is_empty(&self) -> bool {
+        self.entries().next().is_none() && self.thumbnail_data.is_empty()
+    }
+
+    /// Entries of every IFD along with the IFD holding them
+    pub fn entries(&self) -> impl Iterator<Item = (Ifd, &Entry)> {
+        self.primary
+            .iter()
+            .map(|e| (Ifd::Primary, e))
+            .chain(self.exif.iter().map(|e| (Ifd::Exif, e)))
+            .chain(self.gps.iter().map(|e| (Ifd::Gps, e)))
+            .chain(
+                self.interoperability
+                    .iter()
+                    .map(|e| (Ifd::Interoperability, e)),
+            )
+            .chain(self.thumbnail.iter().map(|e| (Ifd::Thumbnail, e)))
+    }
+
+    pub fn get(&self, ifd: Ifd, tag: u16) -> Option<&Entry> {
+        self.ifd(ifd).iter().find(|e| e.tag == tag)
+    }
+
+    /// Sets the value of a tag, replacing the current value if any
+    pub fn set(&mut self, ifd: Ifd, tag: u16, value: Value) {
+        let entries = self.ifd_mut(ifd);
+
+        match entries.iter_mut().find(|e| e.tag == tag) {
+            Some(entry) => entry.value = value,
+            None => {
+                entries.push(Entry { tag, value });
+                entries.sort_by_key(|e| e.tag);
+            }
+        }
+    }
+
+    /// Removes a tag, returns true if the tag was found
+    pub fn remove(&mut self, ifd: Ifd, tag: u16) -> bool {
+        let entries = self.ifd_mut(ifd);
+        let length = entries.len();
+
+        entries.retain(|e| e.tag != tag);
+        entries.len() != length
+    }
+
+    /// Removes every GPS tag, returns the number of removed tags
+    pub fn remove_gps(&mut self) -> usize {
+        self.gps.drain(..).count()
+    }
+
+    fn ifd(&self, ifd: Ifd) -> &Vec<Entry> {
+        match ifd {
+            Ifd::Primary => &self.primary,
+            Ifd::Exif => &self.exif,
+            Ifd::Gps => &self.gps,
+            Ifd::Interoperability => &self.interoperability,
+            Ifd::Thumbnail => &self.thumbnail,
+        }
+    }
+
+    fn ifd_mut(&mut self, ifd: Ifd) -> &mut Vec<Entry> {
+        match ifd {
+            Ifd::Primary => &mut self.primary,
+            Ifd::Exif => &mut self.exif,
+            Ifd::Gps => &mut self.gps,
+            Ifd::Interoperability => &mut self.interoperability,
+            Ifd::Thumbnail => &mut self.thumbnail,
+        }
+    }
+}
+
+impl Default for ExifData {
+    fn default() -> Self {
+        ExifData::new()
+    }
+}
+
+/// Reads the offset held by a pointer entry, if the IFD has one
+fn pointer(entries: &[Entry], tag: u16) -> Result<Option<u32>> {
+    match entries.iter().find(|e| e.tag == tag).map(|e| &e.value) {
+        Some(Value::Long(offset)) if offset.len() == 1 => Ok(Some(offset[0])),
+        Some(_) => Err(invalid("IFD pointers must be a single long")),
+        None => Ok(None),
+    }
+}
+
+/// Sets the offset of a pointer entry previously added by `set_long`
+fn set_pointer(entries: &mut [Entry], tag: u16, offset: u32) {
+    if let Some(entry) = entries.iter_mut().find(|e| e.tag == tag) {
+        entry.value = Value::Long(vec![offset]);
+    }
+}
+
+/// Sets a single long entry, adding it if the IFD doesn't hold it
+fn set_long(entries: &mut Vec<Entry>, tag: u16, value: u32) {
+    entries.retain(|e| e.tag != tag);
+    entries.push(Entry {
+        tag,
+        value: Value::Long(vec![value]),
+    });
+    entries.sort_by_key(|e| e.tag);
+}
+
+impl Value {
+    /// TIFF type of the value
+    pub fn type_code(&self) -> u16 {
+        match self {
+            Value::Byte(_) => 1,
+            Value::Ascii(_) => 2,
+            Value::Short(_) => 3,
+            Value::Long(_) => 4,
+            Value::Rational(_) => 5,
+            Value::SByte(_) => 6,
+            Value::Undefined(_) => 7,
+            Value::SShort(_) => 8,
+            Value::SLong(_) => 9,
+            Value::SRational(_) => 10,
+            Value::Float(_) => 11,
+            Value::Double(_) => 12,
+        }
+    }
+
+    /// Parses a value of the provided TIFF type, lists are comma separated
+    /// and rationals are written as `numerator/denominator` or as decimals
+    pub fn parse(type_code: u16, text: &str) -> Result<Self> {
+        fn list<T: FromStr>(text: &str) -> Result<Vec<T>> {
+            text.split(',')
+                .map(|item| {
+                    item.trim()
+                        .parse()
+                        .map_err(|_| invalid(&format!("{:?} isn't a valid number", item)))
+                })
+                .collect()
+        }
+
+        fn rationals(text: &str) -> Result<Vec<(i64, i64)>> {
+            text.split(',')
+                .map(|item| match item.trim().split_once('/') {
+                    Some((n, d)) => Ok((list(n)?[0], list(d)?[0])),
+                    None if !item.contains('.') => Ok((list(item)?[0], 1)),
+                    None => {
+                        let decimal: f64 = list(item)?[0];
+
+                        Ok(((decimal * 10_000.0).round() as i64, 10_000))
+                    }
+                })
+                .collect()
+        }
+
+        Ok(match type_code {
+            1 => Value::Byte(list(text)?),
+            2 => Value::Ascii(text.to_string()),
+            3 => Value::Short(list(text)?),
+            4 => Value::Long(list(text)?),
+            5 => Value::Rational(
+                rationals(text)?
+                    .into_iter()
+                    .map(|(n, d)| Ok((n.try_into()?, d.try_into()?)))
+                    .collect::<Result<_>>()?,
+            ),
+            6 => Value::SByte(list(text)?),
+            7 => Value::Undefined(text.as_bytes().to_vec()),
+            8 => Value::SShort(list(text)?),
+            9 => Value::SLong(list(text)?),
+            10 => Value::SRational(
+                rationals(text)?
+                    .into_iter()
+                    .map(|(n, d)| Ok((n.try_into()?, d.try_into()?)))
+                    .collect::<Result<_>>()?,
+            ),
+            11 => Value::Float(list(text)?),
+            12 => Value::Double(list(text)?),
+            _ => return Err(invalid(&format!("unknown TIFF type {}", type_code))),
+        })
+    }
+
+    /// Parses a value for a tag, using the type of the current value or the
+    /// type of the known tag, unknown tags hold ASCII strings
+    pub fn parse_for(ifd: Ifd, tag: u16, current: Option<&Value>, text: &str) -> Result<Self> {
+        let type_code = current.map(|v| v.type_code()).unwrap_or_else(|| {
+            TAGS.iter()
+                .find(|(i, t, _, _)| *i == ifd && *t == tag)
+                .map_or(2, |(_, _, _, type_code)| *type_code)
+        });
+
+        Value::parse(type_code, text)
+    }
+
+    fn count(&self) -> usize {
+        match self {
+            Value::Byte(v) | Value::Undefined(v) => v.len(),
+            Value::Ascii(s) => s.len() + 1,
+            Value::Short(v) => v.len(),
+            Value::Long(v) => v.len(),
+            Value::Rational(v) => v.len(),
+            Value::SByte(v) => v.len(),
+            Value::SShort(v) => v.len(),
+            Value::SLong(v) => v.len(),
+            Value::SRational(v) => v.len(),
+            Value::Float(v) => v.len(),
+            Value::Double(v) => v.len(),
+        }
+    }
+}
+
+impl Display for Ifd {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Ifd::Primary => write!(f, "IFD0"),
+            Ifd::Exif => write!(f, "Exif"),
+            Ifd::Gps => write!(f, "GPS"),
+            Ifd::Interoperability => write!(f, "Interop"),
+            Ifd::Thumbnail => write!(f, "IFD1"),
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn join<T: Display>(values: &[T]) -> String {
+            values
+                .iter()
+                .map(|v| v.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        }
+
+        match self {
+            Value::Ascii(text) => write!(f, "{}", text),
+            Value::Undefined(bytes) if bytes.iter().all(|b| b.is_ascii_graphic() || *b == b' ') => {
+                write!(f, "{}", String::from_utf8_lossy(bytes))
+            }
+            Value::Byte(bytes) | Value::Undefined(bytes) => write!(f, "{:02x?}", bytes),
+            Value::Short(v) => write!(f, "{}", join(v)),
+            Value::Long(v) => write!(f, "{}", join(v)),
+            Value::SByte(v) => write!(f, "{}", join(v)),
+            Value::SShort(v) => write!(f, "{}", join(v)),
+            Value::SLong(v) => write!(f, "{}", join(v)),
+            Value::Float(v) => write!(f, "{}", join(v)),
+            Value::Double(v) => write!(f, "{}", join(v)),
+            Value::Rational(v) => write!(
+                f,
+                "{}",
+                join(
+                    &v.iter()
+                        .map(|(n, d)| format!("{}/{}", n, d))
+                        .collect::<Vec<_>>()
+                )
+            ),
+            Value::SRational(v) => write!(
+                f,
+                "{}",
+                join(
+                    &v.iter()
+                        .map(|(n, d)| format!("{}/{}", n, d))
+                        .collect::<Vec<_>>()
+                )
+            ),
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    big_endian: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn slice(&self, offset: usize, length: usize) -> Result<&'a [u8]> {
+        offset
+            .checked_add(length)
+            .and_then(|end| self.bytes.get(offset..end))
+            .ok_or_else(|| invalid("offset out of range"))
+    }
+
+    fn u16(&self, offset: usize) -> Result<u16> {
+        let bytes = self.slice(offset, 2)?.try_into()?;
+
+        Ok(if self.big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    }
+
+    fn u32(&self, offset: usize) -> Result<u32> {
+        let bytes = self.slice(offset, 4)?.try_into()?;
+
+        Ok(if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    }
+
+    fn u64(&self, offset: usize) -> Result<u64> {
+        let bytes = self.slice(offset, 8)?.try_into()?;
+
+        Ok(if self.big_endian {
+            u64::from_be_bytes(bytes)
+        } else {
+            u64::from_le_bytes(bytes)
+        })
+    }
+
+    fn ifd(&self, offset: u32) -> Result<Vec<Entry>> {
+        let offset = offset as usize;
+        let count = self.u16(offset)? as usize;
+
+        (0..count)
+            .map(|index| self.entry(offset + 2 + index * 12))
+            .collect()
+    }
+
+    /// Reads the IFD an entry of `entries` points to, the IFD is empty if
+    /// there is no such entry
+    fn linked_ifd(&self, entries: &[Entry], tag: u16) -> Result<Vec<Entry>> {
+        match pointer(entries, tag)? {
+            Some(offset) => self.ifd(offset),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Offset of the IFD linked after the IFD at `offset`, 0 if none
+    fn next_ifd(&self, offset: u32) -> Result<u32> {
+        let offset = offset as usize;
+        let count = self.u16(offset)? as usize;
+
+        self.u32(offset + 2 + count * 12)
+    }
+
+    fn entry(&self, offset: usize) -> Result<Entry> {
+        let tag = self.u16(offset)?;
+        let type_code = self.u16(offset + 2)?;
+        let count = self.u32(offset + 4)? as usize;
+        let size = match type_code {
+            1 | 2 | 6 | 7 => 1,
+            3 | 8 => 2,
+            4 | 9 | 11 => 4,
+            5 | 10 | 12 => 8,
+            _ => return Err(invalid(&format!("unknown TIFF type {}", type_code))),
+        };
+        let length = count
+            .checked_mul(size)
+            .ok_or_else(|| invalid("field too large"))?;
+        let start = if length <= 4 {
+            offset + 8
+        } else {
+            self.u32(offset + 8)? as usize
+        };
+        let data = self.slice(start, length)?;
+        let values = |size: usize| (0..count).map(move |i| start + i * size);
+
+        let value = match type_code {
+            1 => Value::Byte(data.to_vec()),
+            2 => Value::Ascii(
+                String::from_utf8_lossy(data)
+                    .trim_end_matches('\0')
+                    .to_string(),
+            ),
+            3 => Value::Short(values(2).map(|o| self.u16(o)).collect::<Result<_>>()?),
+            4 => Value::Long(values(4).map(|o| self.u32(o)).collect::<Result<_>>()?),
+            5 => Value::Rational(
+                values(8)
+                    .map(|o| Ok((self.u32(o)?, self.u32(o + 4)?)))
+                    .collect::<Result<_>>()?,
+            ),
+            6 => Value::SByte(data.iter().map(|b| *b as i8).collect()),
+            7 => Value::Undefined(data.to_vec()),
+            8 => Value::SShort(
+                values(2)
+                    .map(|o| Ok(self.u16(o)? as i16))
+                    .collect::<Result<_>>()?,
+            ),
+            9 => Value::SLong(
+                values(4)
+                    .map(|o| Ok(self.u32(o)? as i32))
+                    .collect::<Result<_>>()?,
+            ),
+            10 => Value::SRational(
+                values(8)
+                    .map(|o| Ok((self.u32(o)? as i32, self.u32(o + 4)? as i32)))
+                    .collect::<Result<_>>()?,
+            ),
+            11 => Value::Float(
+                values(4)
+                    .map(|o| Ok(f32::from_bits(self.u32(o)?)))
+                    .collect::<Result<_>>()?,
+            ),
+            _ => Value::Double(
+                values(8)
+                    .map(|o| Ok(f64::from_bits(self.u64(o)?)))
+                    .collect::<Result<_>>()?,
+            ),
+        };
+
+        Ok(Entry { tag, value })
+    }
+}
+
+struct Writer {
+    big_endian: bool,
+}
+
+impl Writer {
+    fn u16(&self, value: u16) -> [u8; 2] {
+        if self.big_endian {
+            value.to_be_bytes()
+        } else {
+            value.to_le_bytes()
+        }
+    }
+
+    fn u32(&self, value: u32) -> [u8; 4] {
+        if self.big_endian {
+            value.to_be_bytes()
+        } else {
+            value.to_le_bytes()
+        }
+    }
+
+    fn u64(&self, value: u64) -> [u8; 8] {
+        if self.big_endian {
+            value.to_be_bytes()
+        } else {
+            value.to_le_bytes()
+        }
+    }
+
+    fn value(&self, value: &Value) -> Vec<u8> {
+        match value {
+            Value::Byte(v) | Value::Undefined(v) => v.clone(),
+            Value::Ascii(s) => [s.as_bytes(), &[0]].concat(),
+            Value::Short(v) => v.iter().flat_map(|n| self.u16(*n)).collect(),
+            Value::Long(v) => v.iter().flat_map(|n| self.u32(*n)).collect(),
+            Value::Rational(v) => v
+                .iter()
+                .flat_map(|(n, d)| [self.u32(*n), self.u32(*d)].concat())
+                .collect(),
+            Value::SByte(v) => v.iter().map(|n| *n as u8).collect(),
+            Value::SShort(v) => v.iter().flat_map(|n| self.u16(*n as u16)).collect(),
+            Value::SLong(v) => v.iter().flat_map(|n| self.u32(*n as u32)).collect(),
+            Value::SRational(v) => v
+                .iter()
+                .flat_map(|(n, d)| [self.u32(*n as u32), self.u32(*d as u32)].concat())
+                .collect(),
+            Value::Float(v) => v.iter().flat_map(|n| self.u32(n.to_bits())).collect(),
+            Value::Double(v) => v.iter().flat_map(|n| self.u64(n.to_bits())).collect(),
+        }
+    }
+
+    /// Length of the IFD along with the values which don't fit in its
+    /// entries, values are aligned to 2 bytes. Empty IFDs aren't written.
+    fn ifd_length(&self, entries: &[Entry]) -> u32 {
+        if entries.is_empty() {
+            return 0;
+        }
+
+        let values: usize = entries
+            .iter()
+            .map(|e| self.value(&e.value).len())
+            .filter(|length| *length > 4)
+            .map(|length| length + length % 2)
+            .sum();
+
+        (2 + entries.len() * 12 + 4 + values) as u32
+    }
+
+    /// Appends the IFD followed by its values, entries are sorted by tag as
+    /// TIFF requires. `next` is the offset of the IFD linked after it.
+    fn ifd(&self, bytes: &mut Vec<u8>, entries: &[Entry], next: u32) {
+        let mut entries = entries.to_vec();
+        let mut values_offset = bytes.len() + 2 + entries.len() * 12 + 4;
+        let mut values = Vec::new();
+
+        entries.sort_by_key(|e| e.tag);
+        bytes.extend(self.u16(entries.len() as u16));
+
+        for entry in entries.iter() {
+            let mut data = self.value(&entry.value);
+
+            bytes.extend(self.u16(entry.tag));
+            bytes.extend(self.u16(entry.value.type_code()));
+            bytes.extend(self.u32(entry.value.count() as u32));
+
+            if data.len() <= 4 {
+                data.resize(4, 0);
+                bytes.extend(data);
+            } else {
+                bytes.extend(self.u32(values_offset as u32));
+
+                if data.len() % 2 == 1 {
+                    data.push(0);
+                }
+
+                values_offset += data.len();
+                values.extend(data);
+            }
+        }
+
+        bytes.extend(self.u32(next));
+        bytes.extend(values);
+    }
+}
+
+fn invalid(reason: &str) -> crate::Error {
+    Box::new(PngMeError::InvalidExif(reason.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn testing_exif(big_endian: bool) -> ExifData {
+        ExifData {
+            big_endian,
+            primary: vec![
+                Entry {
+                    tag: 0x010f,
+                    value: Value::Ascii(String::from("Camera Maker")),
+                },
+                Entry {
+                    tag: 0x0112,
+                    value: Value::Short(vec![1]),
+                },
+            ],
+            exif: vec![
+                Entry {
+                    tag: 0x829a,
+                    value: Value::Rational(vec![(1, 250)]),
+                },
+                Entry {
+                    tag: 0x9000,
+                    value: Value::Undefined(b"0232".to_vec()),
+                },
+            ],
+            gps: vec![
+                Entry {
+                    tag: 0x0001,
+                    value: Value::Ascii(String::from("N")),
+                },
+                Entry {
+                    tag: 0x0002,
+                    value: Value::Rational(vec![(40, 1), (26, 1), (4630, 100)]),
+                },
+            ],
+            interoperability: vec![Entry {
+                tag: 0x0001,
+                value: Value::Ascii(String::from("R98")),
+            }],
+            thumbnail: vec![Entry {
+                tag: 0x0103,
+                value: Value::Short(vec![6]),
+            }],
+            thumbnail_data: b"\xff\xd8 thumbnail \xff\xd9".to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for big_endian in [true, false] {
+            let exif = testing_exif(big_endian);
+            let bytes = exif.to_bytes().unwrap();
+
+            assert_eq!(ExifData::parse(&bytes).unwrap(), exif);
+        }
+    }
+
+    #[test]
+    fn test_entries_are_written_in_ascending_tag_order() {
+        let mut exif = testing_exif(false);
+
+        exif.primary.reverse();
+        exif.exif.reverse();
+
+        let bytes = exif.to_bytes().unwrap();
+        let reader = Reader {
+            bytes: &bytes,
+            big_endian: false,
+        };
+        let primary = reader.ifd(8).unwrap();
+        let tags: Vec<u16> = primary.iter().map(|e| e.tag).collect();
+
+        assert_eq!(tags, [0x010f, 0x0112, EXIF_POINTER, GPS_POINTER]);
+
+        let parsed = ExifData::parse(&bytes).unwrap();
+        let tags: Vec<u16> = parsed.exif.iter().map(|e| e.tag).collect();
+
+        assert_eq!(tags, [0x829a, 0x9000]);
+    }
+
+    #[test]
+    fn test_refuse_to_move_maker_note() {
+        let mut exif = testing_exif(true);
+
+        exif.set(
+            Ifd::Exif,
+            0x927c,
+            Value::Undefined(b"Maker\0\0\0\x10".to_vec()),
+        );
+        assert!(exif.to_bytes().is_err());
+        assert!(exif.remove(Ifd::Exif, 0x927c));
+        assert!(exif.to_bytes().is_ok());
+    }
+
+    #[test]
+    fn test_set_and_remove() {
+        let mut exif = testing_exif(true);
+        let (ifd, tag) = find_tag("datetimeoriginal").unwrap();
+        let value = Value::parse_for(ifd, tag, None, "2024:01:02 03:04:05").unwrap();
+
+        exif.set(ifd, tag, value.clone());
+        assert_eq!(exif.get(Ifd::Exif, 0x9003).unwrap().value, value);
+        assert!(exif.remove(Ifd::Primary, 0x010f));
+        assert!(!exif.remove(Ifd::Primary, 0x010f));
+        assert_eq!(exif.remove_gps(), 2);
+
+        let parsed = ExifData::parse(&exif.to_bytes().unwrap()).unwrap();
+
+        assert_eq!(parsed, exif);
+        assert!(parsed.gps.is_empty());
+    }
+
+    #[test]
+    fn test_parse_values() {
+        assert_eq!(
+            Value::parse(5, "1/250, 2.5, 40").unwrap(),
+            Value::Rational(vec![(1, 250), (25000, 10000), (40, 1)])
+        );
+        assert_eq!(Value::parse(3, "1,2").unwrap(), Value::Short(vec![1, 2]));
+        assert!(Value::parse(3, "one").is_err());
+        assert_eq!(Value::parse(5, "1/250").unwrap().to_string(), "1/250");
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(ExifData::parse(b"JPEG").is_err());
+        assert!(ExifData::parse(b"MM\0*\0\0\0\x08\0\x01").is_err());
+    }
+}
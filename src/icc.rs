@@ -0,0 +1,318 @@
+use std::convert::{TryFrom, TryInto};
+use std::fmt::{self, Display};
+
+use crate::ancillary::Iccp;
+use crate::error::PngMeError;
+use crate::png::Png;
+use crate::text::encode_keyword;
+use crate::Result;
+
+/// Length of the profile header, followed by the tag count
+const HEADER_LENGTH: usize = 128;
+/// Signature every ICC profile holds at offset 36
+const PROFILE_SIGNATURE: &[u8; 4] = b"acsp";
+/// Profile name used when the profile has no description fitting a keyword
+pub const DEFAULT_PROFILE_NAME: &str = "ICC profile";
+
+/// Details read from the header and the `desc` tag of an ICC profile
+///
+/// Reference: https://www.color.org/specification/ICC.1-2022-05.pdf
+#[derive(Debug, Clone, PartialEq)]
+pub struct IccProfile {
+    /// Major, minor and bug fix version
+    pub version: (u8, u8, u8),
+    pub device_class: String,
+    pub color_space: String,
+    pub connection_space: String,
+    pub description: Option<String>,
+}
+
+impl IccProfile {
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < HEADER_LENGTH + 4 {
+            return Err(invalid(&format!(
+                "{} bytes are too short for a profile",
+                bytes.len()
+            )));
+        }
+
+        if &bytes[36..40] != PROFILE_SIGNATURE {
+            return Err(invalid("the acsp signature is missing"));
+        }
+
+        if read_u32(bytes, 0)? as usize != bytes.len() {
+            return Err(invalid(&format!(
+                "the header declares {} bytes but the profile has {}",
+                read_u32(bytes, 0)?,
+                bytes.len()
+            )));
+        }
+
+        let tag_count = read_u32(bytes, HEADER_LENGTH)? as usize;
+        let mut description = None;
+
+        for index in 0..tag_count {
+            let entry = HEADER_LENGTH + 4 + index * 12;
+            let signature = bytes
+                .get(entry..entry + 4)
+                .ok_or_else(|| invalid("the tag table is truncated"))?;
+            let offset = read_u32(bytes, entry + 4)? as usize;
+            let length = read_u32(bytes, entry + 8)? as usize;
+            let data = offset
+                .checked_add(length)
+                .and_then(|end| bytes.get(offset..end))
+                .ok_or_else(|| invalid("a tag points outside of the profile"))?;
+
+            if signature == b"desc" {
+                description = Some(parse_description(data)?);
+            }
+        }
+
+        Ok(IccProfile {
+            version: (bytes[8], bytes[9] >> 4, bytes[9] & 0x0f),
+            device_class: signature(&bytes[12..16]),
+            color_space: signature(&bytes[16..20]),
+            connection_space: signature(&bytes[20..24]),
+            description,
+        })
+    }
+
+    /// Human readable name of the device class
+    pub fn device_class_name(&self) -> &str {
+        match self.device_class.as_str() {
+            "scnr" => "input",
+            "mntr" => "display",
+            "prtr" => "output",
+            "link" => "device link",
+            "spac" => "color space",
+            "abst" => "abstract",
+            "nmcl" => "named color",
+            other => other,
+        }
+    }
+
+    /// Profile name to use in an `iCCP` chunk, the description when it is a
+    /// valid keyword
+    pub fn profile_name(&self) -> String {
+        match &self.description {
+            Some(description)
+                if encode_keyword(description).is_ok() && description.trim() == description =>
+            {
+                description.clone()
+            }
+            _ => DEFAULT_PROFILE_NAME.to_string(),
+        }
+    }
+}
+
+impl Display for IccProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Description: {}",
+            self.description.as_deref().unwrap_or("-")
+        )?;
+        writeln!(f, "Color space: {}", self.color_space)?;
+        writeln!(f, "Connection space: {}", self.connection_space)?;
+        writeln!(f, "Device class: {}", self.device_class_name())?;
+        write!(
+            f,
+            "Version: {}.{}.{}",
+            self.version.0, self.version.1, self.version.2
+        )
+    }
+}
+
+/// Retrieves the ICC profile embedded in the provided PNG file, if any
+pub fn extract(png: &Png) -> Result<Option<Iccp>> {
+    png.chunk_by_type("iCCP").map(Iccp::try_from).transpose()
+}
+
+/// Embeds the profile in an `iCCP` chunk, replacing the current one. The
+/// `sRGB` chunk is removed as a file must not hold both.
+///
+/// Reference: http://www.libpng.org/pub/png/spec/1.2/PNG-Chunks.html#C.iCCP
+pub fn embed(png: &mut Png, iccp: &Iccp) -> Result<()> {
+    IccProfile::parse(&iccp.profile)?;
+
+    let chunk = iccp.to_chunk()?;
+
+    while png.remove_chunk("iCCP").is_ok() {}
+    while png.remove_chunk("sRGB").is_ok() {}
+
+    png.insert_chunk_ordered(chunk);
+
+    Ok(())
+}
+
+/// Reads the text of a `desc` tag, either a `textDescriptionType` (version 2)
+/// or a `multiLocalizedUnicodeType` (version 4) holding the first record
+fn parse_description(data: &[u8]) -> Result<String> {
+    match data.get(0..4) {
+        Some(b"desc") => {
+            let length = read_u32(data, 8)? as usize;
+            let text = data
+                .get(12..12 + length)
+                .ok_or_else(|| invalid("the description is truncated"))?;
+            let text = text.split(|b| *b == 0).next().unwrap_or_default();
+
+            Ok(String::from_utf8_lossy(text).to_string())
+        }
+        Some(b"mluc") => {
+            if read_u32(data, 8)? == 0 {
+                return Ok(String::new());
+            }
+
+            let length = read_u32(data, 20)? as usize;
+            let offset = read_u32(data, 24)? as usize;
+            let text = offset
+                .checked_add(length)
+                .and_then(|end| data.get(offset..end))
+                .ok_or_else(|| invalid("the description is truncated"))?;
+            let units: Vec<u16> = text
+                .chunks_exact(2)
+                .map(|unit| u16::from_be_bytes([unit[0], unit[1]]))
+                .collect();
+
+            Ok(String::from_utf16_lossy(&units))
+        }
+        _ => Err(invalid("the description tag has an unknown type")),
+    }
+}
+
+fn signature(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).trim_end().to_string()
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32> {
+    bytes
+        .get(offset..offset + 4)
+        .map(|b| u32::from_be_bytes(b.try_into().unwrap()))
+        .ok_or_else(|| invalid("the profile is truncated"))
+}
+
+fn invalid(reason: &str) -> crate::Error {
+    Box::new(PngMeError::InvalidIccProfile(reason.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::Chunk;
+    use crate::chunk_type::ChunkType;
+    use std::str::FromStr;
+
+    fn testing_profile(description_tag: &[u8]) -> Vec<u8> {
+        let mut header = vec![0; HEADER_LENGTH];
+
+        header[8] = 2;
+        header[9] = 0x10;
+        header[12..16].copy_from_slice(b"mntr");
+        header[16..20].copy_from_slice(b"RGB ");
+        header[20..24].copy_from_slice(b"XYZ ");
+        header[36..40].copy_from_slice(PROFILE_SIGNATURE);
+
+        let offset = (HEADER_LENGTH + 4 + 12) as u32;
+        let mut profile = [
+            header,
+            1u32.to_be_bytes().to_vec(),
+            b"desc".to_vec(),
+            offset.to_be_bytes().to_vec(),
+            (description_tag.len() as u32).to_be_bytes().to_vec(),
+            description_tag.to_vec(),
+        ]
+        .concat();
+        let length = (profile.len() as u32).to_be_bytes();
+
+        profile[0..4].copy_from_slice(&length);
+        profile
+    }
+
+    fn chunk(chunk_type: &str) -> Chunk {
+        Chunk::new(ChunkType::from_str(chunk_type).unwrap(), vec![0])
+    }
+
+    #[test]
+    fn test_parse_text_description() {
+        let tag = [b"desc", &[0; 4][..], &5u32.to_be_bytes(), b"sRGB\0"].concat();
+        let profile = IccProfile::parse(&testing_profile(&tag)).unwrap();
+
+        assert_eq!(profile.version, (2, 1, 0));
+        assert_eq!(profile.device_class_name(), "display");
+        assert_eq!(profile.color_space, "RGB");
+        assert_eq!(profile.connection_space, "XYZ");
+        assert_eq!(profile.description.as_deref(), Some("sRGB"));
+        assert_eq!(profile.profile_name(), "sRGB");
+    }
+
+    #[test]
+    fn test_parse_localized_description() {
+        let text: Vec<u8> = "Display P3"
+            .encode_utf16()
+            .flat_map(|unit| unit.to_be_bytes())
+            .collect();
+        let tag = [
+            b"mluc",
+            &[0; 4][..],
+            &1u32.to_be_bytes(),
+            &12u32.to_be_bytes(),
+            b"enUS",
+            &(text.len() as u32).to_be_bytes(),
+            &28u32.to_be_bytes(),
+            &text,
+        ]
+        .concat();
+        let profile = IccProfile::parse(&testing_profile(&tag)).unwrap();
+
+        assert_eq!(profile.description.as_deref(), Some("Display P3"));
+    }
+
+    #[test]
+    fn test_parse_invalid_profile() {
+        let tag = [b"desc", &[0; 4][..], &5u32.to_be_bytes(), b"sRGB\0"].concat();
+        let mut profile = testing_profile(&tag);
+
+        assert!(IccProfile::parse(&profile).is_ok());
+        assert!(IccProfile::parse(&profile[..100]).is_err());
+        profile[0] = 1;
+        assert!(IccProfile::parse(&profile).is_err());
+        profile[0] = 0;
+        profile[36] = b'x';
+        assert!(IccProfile::parse(&profile).is_err());
+    }
+
+    #[test]
+    fn test_embed_replaces_srgb() {
+        let tag = [b"desc", &[0; 4][..], &5u32.to_be_bytes(), b"sRGB\0"].concat();
+        let iccp = Iccp {
+            name: String::from("sRGB"),
+            profile: testing_profile(&tag),
+        };
+        let mut png = Png::from_chunks(vec![
+            chunk("IHDR"),
+            chunk("sRGB"),
+            chunk("PLTE"),
+            chunk("IDAT"),
+            chunk("IEND"),
+        ]);
+
+        embed(&mut png, &iccp).unwrap();
+
+        let chunk_types: Vec<String> = png
+            .chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect();
+
+        assert_eq!(chunk_types, ["IHDR", "iCCP", "PLTE", "IDAT", "IEND"]);
+        assert_eq!(extract(&png).unwrap().unwrap(), iccp);
+        assert!(embed(
+            &mut png,
+            &Iccp {
+                name: String::from("broken"),
+                profile: vec![0; 10],
+            }
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,174 @@
+use alloc::{boxed::Box, format, string::String, vec::Vec};
+use core::convert::{TryFrom, TryInto};
+use core::fmt::{self, Display};
+use core::str::FromStr;
+
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::error::PngMeError;
+use crate::png::Png;
+use crate::Result;
+
+/// Image header stored in the `IHDR` chunk
+///
+/// Reference: http://www.libpng.org/pub/png/spec/1.2/PNG-Chunks.html#C.IHDR
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ihdr {
+    pub width: u32,
+    pub height: u32,
+    pub bit_depth: u8,
+    pub color_type: u8,
+    pub compression_method: u8,
+    pub filter_method: u8,
+    pub interlace_method: u8,
+}
+
+impl Ihdr {
+    /// Retrieves the header of the provided PNG file
+    pub fn from_png(png: &Png) -> Result<Self> {
+        match png.chunk_by_type("IHDR") {
+            Some(chunk) => Ihdr::try_from(chunk),
+            None => Err(Box::new(PngMeError::UnexistentChunkType)),
+        }
+    }
+
+    /// Number of samples per pixel for the image color type
+    pub fn channels(&self) -> usize {
+        match self.color_type {
+            2 => 3,
+            4 => 2,
+            6 => 4,
+            _ => 1,
+        }
+    }
+
+    /// Number of bits used by each pixel
+    pub fn bits_per_pixel(&self) -> usize {
+        self.channels() * self.bit_depth as usize
+    }
+
+    pub fn color_type_name(&self) -> &'static str {
+        match self.color_type {
+            0 => "Grayscale",
+            2 => "RGB",
+            3 => "Indexed",
+            4 => "Grayscale with alpha",
+            6 => "RGBA",
+            _ => "Unknown",
+        }
+    }
+
+    pub fn to_chunk(&self) -> Chunk {
+        let data: Vec<u8> = self
+            .width
+            .to_be_bytes()
+            .iter()
+            .chain(self.height.to_be_bytes().iter())
+            .chain(
+                [
+                    self.bit_depth,
+                    self.color_type,
+                    self.compression_method,
+                    self.filter_method,
+                    self.interlace_method,
+                ]
+                .iter(),
+            )
+            .copied()
+            .collect();
+
+        Chunk::new(ChunkType::from_str("IHDR").unwrap(), data)
+    }
+}
+
+impl TryFrom<&Chunk> for Ihdr {
+    type Error = crate::Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        let data = chunk.data();
+
+        if &chunk.chunk_type().bytes() != b"IHDR" || data.len() != 13 {
+            return Err(Box::new(PngMeError::InvalidChunkData(String::from(
+                "IHDR must be 13 bytes long",
+            ))));
+        }
+
+        let ihdr = Ihdr {
+            width: u32::from_be_bytes(data[0..4].try_into()?),
+            height: u32::from_be_bytes(data[4..8].try_into()?),
+            bit_depth: data[8],
+            color_type: data[9],
+            compression_method: data[10],
+            filter_method: data[11],
+            interlace_method: data[12],
+        };
+        let valid_bit_depths: &[u8] = match ihdr.color_type {
+            0 => &[1, 2, 4, 8, 16],
+            3 => &[1, 2, 4, 8],
+            2 | 4 | 6 => &[8, 16],
+            _ => &[],
+        };
+
+        if ihdr.width == 0 || ihdr.height == 0 || !valid_bit_depths.contains(&ihdr.bit_depth) {
+            return Err(Box::new(PngMeError::InvalidChunkData(format!(
+                "invalid IHDR {}x{} with color type {} and bit depth {}",
+                ihdr.width, ihdr.height, ihdr.color_type, ihdr.bit_depth
+            ))));
+        }
+
+        Ok(ihdr)
+    }
+}
+
+impl Display for Ihdr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}x{}, {}-bit {}{}",
+            self.width,
+            self.height,
+            self.bit_depth,
+            self.color_type_name(),
+            if self.interlace_method == 1 {
+                ", interlaced"
+            } else {
+                ""
+            }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn testing_ihdr() -> Ihdr {
+        Ihdr {
+            width: 640,
+            height: 480,
+            bit_depth: 8,
+            color_type: 6,
+            compression_method: 0,
+            filter_method: 0,
+            interlace_method: 0,
+        }
+    }
+
+    #[test]
+    fn test_ihdr_round_trip() {
+        let chunk = testing_ihdr().to_chunk();
+
+        assert_eq!(chunk.length(), 13);
+        assert_eq!(Ihdr::try_from(&chunk).unwrap(), testing_ihdr());
+        assert_eq!(testing_ihdr().bits_per_pixel(), 32);
+        assert_eq!(testing_ihdr().to_string(), "640x480, 8-bit RGBA");
+    }
+
+    #[test]
+    fn test_invalid_bit_depth() {
+        let mut ihdr = testing_ihdr();
+        ihdr.bit_depth = 4;
+
+        assert!(Ihdr::try_from(&ihdr.to_chunk()).is_err());
+    }
+}
@@ -0,0 +1,201 @@
+use std::convert::TryFrom;
+use std::fmt::{self, Display};
+
+use crate::ancillary::{Bkgd, Chrm, Cicp, Exif, Gama, Hist, Iccp, Phys, Sbit, Splt, Srgb, Time};
+use crate::apng::{AnimationControl, FrameControl};
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::error::PngMeError;
+use crate::ihdr::Ihdr;
+use crate::palette::{Plte, Trns};
+use crate::text::{CompressedText, InternationalText, Text};
+use crate::Result;
+
+/// Chunk types parsed by `KnownChunk::parse`
+const KNOWN_CHUNK_TYPES: [&str; 20] = [
+    "IHDR", "PLTE", "gAMA", "cHRM", "sRGB", "iCCP", "sBIT", "bKGD", "hIST", "pHYs", "sPLT", "tIME",
+    "tRNS", "eXIf", "cICP", "tEXt", "zTXt", "iTXt", "acTL", "fcTL",
+];
+
+/// A chunk with a type defined by the PNG or APNG specifications, parsed
+/// into its typed representation
+#[derive(Debug, Clone, PartialEq)]
+pub enum KnownChunk {
+    Ihdr(Ihdr),
+    Plte(Plte),
+    Gama(Gama),
+    Chrm(Chrm),
+    Srgb(Srgb),
+    Iccp(Iccp),
+    Sbit(Sbit),
+    Bkgd(Bkgd),
+    Hist(Hist),
+    Phys(Phys),
+    Splt(Splt),
+    Time(Time),
+    Trns(Trns),
+    Exif(Exif),
+    Cicp(Cicp),
+    Text(Text),
+    CompressedText(CompressedText),
+    InternationalText(InternationalText),
+    AnimationControl(AnimationControl),
+    FrameControl(FrameControl),
+}
+
+impl KnownChunk {
+    /// Parses the chunk, the image header is required to parse `tRNS`
+    /// chunks as their layout depends on the color type
+    pub fn parse(chunk: &Chunk, ihdr: Option<&Ihdr>) -> Result<Self> {
+        Ok(match &chunk.chunk_type().bytes() {
+            b"IHDR" => KnownChunk::Ihdr(Ihdr::try_from(chunk)?),
+            b"PLTE" => KnownChunk::Plte(Plte::try_from(chunk)?),
+            b"gAMA" => KnownChunk::Gama(Gama::try_from(chunk)?),
+            b"cHRM" => KnownChunk::Chrm(Chrm::try_from(chunk)?),
+            b"sRGB" => KnownChunk::Srgb(Srgb::try_from(chunk)?),
+            b"iCCP" => KnownChunk::Iccp(Iccp::try_from(chunk)?),
+            b"sBIT" => KnownChunk::Sbit(Sbit::try_from(chunk)?),
+            b"bKGD" => KnownChunk::Bkgd(Bkgd::try_from(chunk)?),
+            b"hIST" => KnownChunk::Hist(Hist::try_from(chunk)?),
+            b"pHYs" => KnownChunk::Phys(Phys::try_from(chunk)?),
+            b"sPLT" => KnownChunk::Splt(Splt::try_from(chunk)?),
+            b"tIME" => KnownChunk::Time(Time::try_from(chunk)?),
+            b"tRNS" => match ihdr {
+                Some(ihdr) => KnownChunk::Trns(Trns::from_chunk(chunk, ihdr)?),
+                None => {
+                    return Err(Box::new(PngMeError::InvalidChunkData(
+                        "tRNS can't be parsed without the image header".to_string(),
+                    )))
+                }
+            },
+            b"eXIf" => KnownChunk::Exif(Exif::try_from(chunk)?),
+            b"cICP" => KnownChunk::Cicp(Cicp::try_from(chunk)?),
+            b"tEXt" => KnownChunk::Text(Text::try_from(chunk)?),
+            b"zTXt" => KnownChunk::CompressedText(CompressedText::try_from(chunk)?),
+            b"iTXt" => KnownChunk::InternationalText(InternationalText::try_from(chunk)?),
+            b"acTL" => KnownChunk::AnimationControl(AnimationControl::try_from(chunk)?),
+            b"fcTL" => KnownChunk::FrameControl(FrameControl::try_from(chunk)?),
+            _ => return Err(Box::new(PngMeError::UnexistentChunkType)),
+        })
+    }
+
+    /// Returns true for the chunk types `KnownChunk::parse` understands
+    pub fn is_known(chunk_type: &ChunkType) -> bool {
+        KNOWN_CHUNK_TYPES
+            .iter()
+            .any(|known| known.as_bytes() == chunk_type.bytes())
+    }
+
+    pub fn to_chunk(&self) -> Result<Chunk> {
+        Ok(match self {
+            KnownChunk::Ihdr(ihdr) => ihdr.to_chunk(),
+            KnownChunk::Plte(plte) => plte.to_chunk(),
+            KnownChunk::Gama(gama) => gama.to_chunk(),
+            KnownChunk::Chrm(chrm) => chrm.to_chunk(),
+            KnownChunk::Srgb(srgb) => srgb.to_chunk(),
+            KnownChunk::Iccp(iccp) => iccp.to_chunk()?,
+            KnownChunk::Sbit(sbit) => sbit.to_chunk(),
+            KnownChunk::Bkgd(bkgd) => bkgd.to_chunk(),
+            KnownChunk::Hist(hist) => hist.to_chunk(),
+            KnownChunk::Phys(phys) => phys.to_chunk(),
+            KnownChunk::Splt(splt) => splt.to_chunk()?,
+            KnownChunk::Time(time) => time.to_chunk(),
+            KnownChunk::Trns(trns) => trns.to_chunk(),
+            KnownChunk::Exif(exif) => exif.to_chunk(),
+            KnownChunk::Cicp(cicp) => cicp.to_chunk(),
+            KnownChunk::Text(text) => text.to_chunk()?,
+            KnownChunk::CompressedText(text) => text.to_chunk()?,
+            KnownChunk::InternationalText(text) => text.to_chunk()?,
+            KnownChunk::AnimationControl(animation_control) => animation_control.to_chunk(),
+            KnownChunk::FrameControl(frame_control) => frame_control.to_chunk(),
+        })
+    }
+}
+
+/// Parses chunks without the image header, `tRNS` chunks can't be parsed
+/// this way, see `KnownChunk::parse`
+impl TryFrom<&Chunk> for KnownChunk {
+    type Error = crate::Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        KnownChunk::parse(chunk, None)
+    }
+}
+
+impl Display for KnownChunk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KnownChunk::Ihdr(ihdr) => ihdr.fmt(f),
+            KnownChunk::Plte(plte) => plte.fmt(f),
+            KnownChunk::Gama(gama) => gama.fmt(f),
+            KnownChunk::Chrm(chrm) => chrm.fmt(f),
+            KnownChunk::Srgb(srgb) => srgb.fmt(f),
+            KnownChunk::Iccp(iccp) => iccp.fmt(f),
+            KnownChunk::Sbit(sbit) => sbit.fmt(f),
+            KnownChunk::Bkgd(bkgd) => bkgd.fmt(f),
+            KnownChunk::Hist(hist) => hist.fmt(f),
+            KnownChunk::Phys(phys) => phys.fmt(f),
+            KnownChunk::Splt(splt) => splt.fmt(f),
+            KnownChunk::Time(time) => time.fmt(f),
+            KnownChunk::Trns(trns) => trns.fmt(f),
+            KnownChunk::Exif(exif) => exif.fmt(f),
+            KnownChunk::Cicp(cicp) => cicp.fmt(f),
+            KnownChunk::Text(text) => text.fmt(f),
+            KnownChunk::CompressedText(text) => text.fmt(f),
+            KnownChunk::InternationalText(text) => text.fmt(f),
+            KnownChunk::AnimationControl(animation_control) => animation_control.fmt(f),
+            KnownChunk::FrameControl(frame_control) => frame_control.fmt(f),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ancillary::PhysUnit;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_known_chunk_round_trip() {
+        let phys = Phys {
+            pixels_per_unit_x: 1,
+            pixels_per_unit_y: 2,
+            unit: PhysUnit::Unknown,
+        };
+        let chunk = phys.to_chunk();
+        let known = KnownChunk::try_from(&chunk).unwrap();
+
+        assert_eq!(known, KnownChunk::Phys(phys));
+        assert_eq!(known.to_chunk().unwrap().as_bytes(), chunk.as_bytes());
+        assert_eq!(known.to_string(), "pixel aspect ratio 1:2");
+    }
+
+    #[test]
+    fn test_trns_requires_header() {
+        let ihdr = Ihdr {
+            width: 1,
+            height: 1,
+            bit_depth: 8,
+            color_type: 0,
+            compression_method: 0,
+            filter_method: 0,
+            interlace_method: 0,
+        };
+        let chunk = Trns::Grayscale(7).to_chunk();
+
+        assert!(KnownChunk::try_from(&chunk).is_err());
+        assert_eq!(
+            KnownChunk::parse(&chunk, Some(&ihdr)).unwrap(),
+            KnownChunk::Trns(Trns::Grayscale(7))
+        );
+    }
+
+    #[test]
+    fn test_unknown_chunk_type() {
+        let chunk = Chunk::new(ChunkType::from_str("ruSt").unwrap(), b"message".to_vec());
+
+        assert!(KnownChunk::try_from(&chunk).is_err());
+        assert!(!KnownChunk::is_known(chunk.chunk_type()));
+        assert!(KnownChunk::is_known(&ChunkType::from_str("iTXt").unwrap()));
+    }
+}
@@ -0,0 +1,53 @@
+//! Without the default `std` feature only the chunk codec is available:
+//! `ChunkType`, `Chunk`, `ChunkRef`, `Png`, `PngView` and the chunk walker
+//! used to parse files, which only require `alloc`.
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod ancillary;
+pub mod apng;
+#[cfg(feature = "async")]
+pub mod async_io;
+#[cfg(feature = "std")]
+pub mod atomic;
+pub mod chunk;
+pub mod chunk_type;
+#[cfg(feature = "std")]
+pub mod copy;
+#[cfg(feature = "std")]
+pub mod diff;
+pub mod error;
+#[cfg(feature = "std")]
+pub mod exif;
+#[cfg(feature = "std")]
+pub mod icc;
+pub mod ihdr;
+#[cfg(feature = "std")]
+pub mod known_chunk;
+#[cfg(feature = "std")]
+pub mod palette;
+pub mod parse;
+pub mod png;
+#[cfg(feature = "std")]
+pub mod raster;
+#[cfg(feature = "std")]
+pub mod repair;
+#[cfg(feature = "std")]
+pub mod stego;
+#[cfg(feature = "std")]
+pub mod strip;
+#[cfg(feature = "std")]
+pub mod text;
+pub mod view;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "std")]
+pub mod xmp;
+
+#[cfg(feature = "std")]
+pub type Error = Box<dyn std::error::Error>;
+#[cfg(not(feature = "std"))]
+pub type Error = alloc::boxed::Box<dyn core::error::Error>;
+pub type Result<T> = core::result::Result<T, Error>;
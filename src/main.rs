@@ -1,20 +1,32 @@
 use structopt::StructOpt;
 
 mod args;
-mod chunk;
-mod chunk_type;
+mod batch;
 mod commands;
-mod error;
-mod png;
+mod manifest;
+mod serve;
+mod tui;
 
-pub type Error = Box<dyn std::error::Error>;
-pub type Result<T> = std::result::Result<T, Error>;
+pub use pngme::{Error, Result};
 
 fn main() -> Result<()> {
     match args::Args::from_args() {
         args::Args::Encode(args) => commands::encode(args),
         args::Args::Decode(args) => commands::decode(args),
+        args::Args::Update(args) => commands::update(args),
         args::Args::Remove(args) => commands::remove(args),
         args::Args::Print(args) => commands::print(args),
+        args::Args::Inspect(args) => commands::inspect(args),
+        args::Args::Apply(args) => commands::apply(args),
+        args::Args::Diff(args) => commands::diff(args),
+        args::Args::Copy(args) => commands::copy(args),
+        args::Args::Strip(args) => commands::strip(args),
+        args::Args::Repair(args) => commands::repair(args),
+        args::Args::Info(args) => commands::info(args),
+        args::Args::Exif(args) => commands::exif(args),
+        args::Args::Xmp(args) => commands::xmp(args),
+        args::Args::Icc(args) => commands::icc(args),
+        args::Args::Serve(args) => commands::serve(args),
+        args::Args::Tui(args) => commands::tui(args),
     }
 }
@@ -0,0 +1,432 @@
+use std::fmt::{self, Display};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+use pngme::chunk::Chunk;
+use pngme::chunk_type::ChunkType;
+use pngme::error::PngMeError;
+use pngme::png::Png;
+
+use crate::Result;
+
+/// Describes the changes to apply to a set of PNG files.
+///
+/// ```toml
+/// [[file]]
+/// path = "assets/*.png"
+///
+/// [[file.chunk]]
+/// action = "add"
+/// type = "ruSt"
+/// text = "release-42"
+/// position = "before-idat"
+///
+/// [[file.chunk]]
+/// action = "update"
+/// type = "tRAk"
+/// env = "TRACKING_ID"
+///
+/// [[file.chunk]]
+/// action = "remove"
+/// type = "tEXt"
+/// ```
+///
+/// Paths, globs and payload files are relative to the manifest directory.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    #[serde(rename = "file", default)]
+    pub files: Vec<FileEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FileEntry {
+    pub path: String,
+    #[serde(rename = "chunk", default)]
+    pub chunks: Vec<ChunkEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChunkEntry {
+    pub action: Action,
+    #[serde(rename = "type")]
+    pub chunk_type: String,
+    /// Inline payload
+    pub text: Option<String>,
+    /// Payload read from a file
+    pub file: Option<PathBuf>,
+    /// Payload read from an environment variable
+    pub env: Option<String>,
+    /// Where an added chunk is inserted
+    #[serde(default)]
+    pub position: Position,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    Add,
+    Update,
+    Remove,
+}
+
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Position {
+    /// Right after the `IHDR` chunk
+    AfterIhdr,
+    /// Right before the first `IDAT` chunk
+    BeforeIdat,
+    /// Right before the `IEND` chunk
+    #[default]
+    End,
+}
+
+/// A change made into a PNG file
+#[derive(Debug, PartialEq)]
+pub enum Change {
+    Added {
+        chunk_type: String,
+        length: u32,
+        index: usize,
+    },
+    Updated {
+        chunk_type: String,
+        old_length: u32,
+        new_length: u32,
+    },
+    Removed {
+        chunk_type: String,
+        length: u32,
+    },
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Added {
+                chunk_type,
+                length,
+                index,
+            } => write!(f, "+ {} ({} bytes) at {}", chunk_type, length, index),
+            Change::Updated {
+                chunk_type,
+                old_length,
+                new_length,
+            } => write!(
+                f,
+                "~ {} ({} -> {} bytes)",
+                chunk_type, old_length, new_length
+            ),
+            Change::Removed { chunk_type, length } => {
+                write!(f, "- {} ({} bytes)", chunk_type, length)
+            }
+        }
+    }
+}
+
+impl FromStr for Manifest {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(toml::from_str(s)?)
+    }
+}
+
+impl Manifest {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        Manifest::from_str(fs::read_to_string(path)?.as_str())
+    }
+}
+
+impl FileEntry {
+    /// Resolves the files this entry applies to
+    pub fn paths(&self, base_dir: &Path) -> Result<Vec<PathBuf>> {
+        let pattern = base_dir.join(self.path.as_str());
+
+        if pattern.is_file() {
+            return Ok(vec![pattern]);
+        }
+
+        let mut paths = Vec::new();
+
+        // Directories matching the pattern are skipped like batches do
+        for entry in glob::glob(pattern.to_string_lossy().as_ref())? {
+            let entry = entry?;
+
+            if entry.is_file() {
+                paths.push(entry);
+            }
+        }
+
+        if paths.is_empty() {
+            return Err(format!("No files matched {}", self.path).into());
+        }
+
+        Ok(paths)
+    }
+
+    /// Applies every chunk change of this entry into `png`, in the order
+    /// they are declared. `env` looks up the environment variables payloads
+    /// are read from.
+    pub fn apply(
+        &self,
+        png: &mut Png,
+        base_dir: &Path,
+        env: &dyn Fn(&str) -> Option<String>,
+    ) -> Result<Vec<Change>> {
+        let mut changes = Vec::new();
+
+        for entry in self.chunks.iter() {
+            changes.push(entry.apply(png, base_dir, env)?);
+        }
+
+        Ok(changes)
+    }
+}
+
+impl ChunkEntry {
+    fn apply(
+        &self,
+        png: &mut Png,
+        base_dir: &Path,
+        env: &dyn Fn(&str) -> Option<String>,
+    ) -> Result<Change> {
+        let chunk_type = self.chunk_type.clone();
+
+        match self.action {
+            Action::Add => {
+                let chunk = self.chunk(base_dir, env)?;
+                let length = chunk.length();
+                let index = self.position.index(png);
+
+                png.insert_message_at(index, chunk)?;
+
+                Ok(Change::Added {
+                    chunk_type,
+                    length,
+                    index,
+                })
+            }
+            Action::Update => {
+                let chunk = self.chunk(base_dir, env)?;
+                let new_length = chunk.length();
+                let old_chunk = png.replace_message(chunk)?;
+
+                Ok(Change::Updated {
+                    chunk_type,
+                    old_length: old_chunk.length(),
+                    new_length,
+                })
+            }
+            Action::Remove => {
+                let chunk = png.remove_chunk(self.chunk_type.as_str())?;
+
+                Ok(Change::Removed {
+                    chunk_type,
+                    length: chunk.length(),
+                })
+            }
+        }
+    }
+
+    fn chunk(&self, base_dir: &Path, env: &dyn Fn(&str) -> Option<String>) -> Result<Chunk> {
+        let chunk_type = ChunkType::from_str(self.chunk_type.as_str())?;
+
+        Ok(Chunk::new(chunk_type, self.payload(base_dir, env)?))
+    }
+
+    fn payload(&self, base_dir: &Path, env: &dyn Fn(&str) -> Option<String>) -> Result<Vec<u8>> {
+        match (&self.text, &self.file, &self.env) {
+            (Some(text), None, None) => Ok(text.as_bytes().to_vec()),
+            (None, Some(file), None) => Ok(fs::read(base_dir.join(file))?),
+            (None, None, Some(name)) => env(name)
+                .map(|value| value.into_bytes())
+                .ok_or_else(|| format!("Environment variable {} is not set", name).into()),
+            _ => Err(Box::new(PngMeError::InvalidBytes(format!(
+                "{} (exactly one of `text`, `file` or `env` must be provided)",
+                self.chunk_type
+            )))),
+        }
+    }
+}
+
+impl Position {
+    fn index(&self, png: &Png) -> usize {
+        let len = png.chunks().len();
+
+        match self {
+            Position::AfterIhdr => png.chunk_position("IHDR").map(|i| i + 1).unwrap_or(0),
+            Position::BeforeIdat => png
+                .chunk_position("IDAT")
+                .or_else(|| png.chunk_position("IEND"))
+                .unwrap_or(len),
+            Position::End => png.chunk_position("IEND").unwrap_or(len),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"
+        [[file]]
+        path = "image.png"
+
+        [[file.chunk]]
+        action = "add"
+        type = "ruSt"
+        text = "Hello"
+        position = "after-ihdr"
+
+        [[file.chunk]]
+        action = "update"
+        type = "miDl"
+        env = "PNGME_MANIFEST_TEST"
+
+        [[file.chunk]]
+        action = "remove"
+        type = "LASt"
+    "#;
+
+    fn chunk_from_strings(chunk_type: &str, data: &str) -> Chunk {
+        Chunk::new(
+            ChunkType::from_str(chunk_type).unwrap(),
+            data.as_bytes().to_vec(),
+        )
+    }
+
+    fn testing_png() -> Png {
+        Png::from_chunks(vec![
+            chunk_from_strings("IHDR", "header"),
+            chunk_from_strings("miDl", "I am another chunk"),
+            chunk_from_strings("LASt", "I am the last chunk"),
+            chunk_from_strings("IEND", ""),
+        ])
+    }
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest = Manifest::from_str(MANIFEST).unwrap();
+        let chunks = &manifest.files[0].chunks;
+
+        assert_eq!(manifest.files.len(), 1);
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0].action, Action::Add);
+        assert_eq!(chunks[0].position, Position::AfterIhdr);
+        assert_eq!(chunks[1].position, Position::End);
+    }
+
+    #[test]
+    fn test_invalid_manifest() {
+        let manifest = Manifest::from_str("[[file]]\npath = 1\n");
+
+        assert!(manifest.is_err());
+    }
+
+    fn testing_env(name: &str) -> Option<String> {
+        match name {
+            "PNGME_MANIFEST_TEST" => Some(String::from("Updated")),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_apply_manifest() {
+        let manifest = Manifest::from_str(MANIFEST).unwrap();
+        let mut png = testing_png();
+        let changes = manifest.files[0]
+            .apply(&mut png, Path::new("."), &testing_env)
+            .unwrap();
+        let types: Vec<String> = png
+            .chunks()
+            .iter()
+            .map(|c| c.chunk_type().to_string())
+            .collect();
+
+        assert_eq!(types, vec!["IHDR", "ruSt", "miDl", "IEND"]);
+        assert_eq!(
+            &png.chunk_by_type("miDl").unwrap().data_as_string().unwrap(),
+            "Updated"
+        );
+        assert_eq!(
+            changes,
+            vec![
+                Change::Added {
+                    chunk_type: String::from("ruSt"),
+                    length: 5,
+                    index: 1
+                },
+                Change::Updated {
+                    chunk_type: String::from("miDl"),
+                    old_length: 18,
+                    new_length: 7
+                },
+                Change::Removed {
+                    chunk_type: String::from("LASt"),
+                    length: 19
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_payload_requires_a_single_source() {
+        let manifest = Manifest::from_str(
+            "[[file]]\npath = \"a.png\"\n[[file.chunk]]\naction = \"add\"\ntype = \"ruSt\"\n",
+        )
+        .unwrap();
+        let mut png = testing_png();
+
+        assert!(manifest.files[0]
+            .apply(&mut png, Path::new("."), &testing_env)
+            .is_err());
+    }
+
+    #[test]
+    fn test_add_critical_chunk() {
+        let manifest = Manifest::from_str(
+            "[[file]]\npath = \"a.png\"\n[[file.chunk]]\naction = \"add\"\ntype = \"IDAT\"\ntext = \"pixels\"\n",
+        )
+        .unwrap();
+        let mut png = testing_png();
+
+        assert!(manifest.files[0]
+            .apply(&mut png, Path::new("."), &testing_env)
+            .is_err());
+        assert!(png.chunk_by_type("IDAT").is_none());
+    }
+
+    #[test]
+    fn test_paths_skip_directories() {
+        let dir = std::env::temp_dir().join(format!("pngme-manifest-{}", std::process::id()));
+
+        fs::create_dir_all(dir.join("nested.png")).unwrap();
+        fs::write(dir.join("image.png"), b"").unwrap();
+
+        let entry = FileEntry {
+            path: String::from("*.png"),
+            chunks: Vec::new(),
+        };
+        let paths = entry.paths(&dir).unwrap();
+
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(paths, vec![dir.join("image.png")]);
+    }
+
+    #[test]
+    fn test_missing_environment_variable() {
+        let manifest = Manifest::from_str(MANIFEST).unwrap();
+        let mut png = testing_png();
+
+        assert!(manifest.files[0]
+            .apply(&mut png, Path::new("."), &|_: &str| None)
+            .is_err());
+    }
+}
@@ -0,0 +1,181 @@
+use std::convert::TryFrom;
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::error::PngMeError;
+use crate::ihdr::Ihdr;
+use crate::png::Png;
+use crate::Result;
+
+/// Palette stored in the `PLTE` chunk
+///
+/// Reference: http://www.libpng.org/pub/png/spec/1.2/PNG-Chunks.html#C.PLTE
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plte {
+    pub entries: Vec<[u8; 3]>,
+}
+
+/// Transparency stored in the `tRNS` chunk, its layout depends on the color
+/// type of the image
+///
+/// Reference: http://www.libpng.org/pub/png/spec/1.2/PNG-Chunks.html#C.tRNS
+#[derive(Debug, Clone, PartialEq)]
+pub enum Trns {
+    /// Alpha of the palette entries, missing entries are fully opaque
+    Indexed(Vec<u8>),
+    Grayscale(u16),
+    Rgb(u16, u16, u16),
+}
+
+impl Plte {
+    /// Retrieves the palette of the provided PNG file, if any
+    pub fn from_png(png: &Png) -> Result<Option<Self>> {
+        png.chunk_by_type("PLTE").map(Plte::try_from).transpose()
+    }
+
+    pub fn to_chunk(&self) -> Chunk {
+        Chunk::new(ChunkType::from_str("PLTE").unwrap(), self.entries.concat())
+    }
+}
+
+impl Trns {
+    /// Retrieves the transparency of the provided PNG file, if any
+    pub fn from_png(png: &Png) -> Result<Option<Self>> {
+        let ihdr = Ihdr::from_png(png)?;
+
+        png.chunk_by_type("tRNS")
+            .map(|chunk| Trns::from_chunk(chunk, &ihdr))
+            .transpose()
+    }
+
+    /// Parses a `tRNS` chunk for an image with the provided header
+    pub fn from_chunk(chunk: &Chunk, ihdr: &Ihdr) -> Result<Self> {
+        let data = chunk.data();
+        let sample = |index: usize| u16::from_be_bytes([data[index], data[index + 1]]);
+
+        if &chunk.chunk_type().bytes() != b"tRNS" {
+            return Err(invalid("expected a tRNS chunk"));
+        }
+
+        match (ihdr.color_type, data.len()) {
+            (3, length) if length <= 256 => Ok(Trns::Indexed(data.to_vec())),
+            (0, 2) => Ok(Trns::Grayscale(sample(0))),
+            (2, 6) => Ok(Trns::Rgb(sample(0), sample(2), sample(4))),
+            _ => Err(invalid(&format!(
+                "tRNS of {} bytes isn't valid for {} images",
+                data.len(),
+                ihdr.color_type_name()
+            ))),
+        }
+    }
+
+    pub fn to_chunk(&self) -> Chunk {
+        let data = match self {
+            Trns::Indexed(alpha) => alpha.clone(),
+            Trns::Grayscale(gray) => gray.to_be_bytes().to_vec(),
+            Trns::Rgb(red, green, blue) => {
+                [red.to_be_bytes(), green.to_be_bytes(), blue.to_be_bytes()].concat()
+            }
+        };
+
+        Chunk::new(ChunkType::from_str("tRNS").unwrap(), data)
+    }
+}
+
+impl TryFrom<&Chunk> for Plte {
+    type Error = crate::Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        let data = chunk.data();
+
+        if &chunk.chunk_type().bytes() != b"PLTE" {
+            return Err(invalid("expected a PLTE chunk"));
+        }
+
+        if data.is_empty() || !data.len().is_multiple_of(3) || data.len() > 256 * 3 {
+            return Err(invalid(&format!(
+                "PLTE of {} bytes must hold between 1 and 256 entries of 3 bytes",
+                data.len()
+            )));
+        }
+
+        Ok(Plte {
+            entries: data
+                .chunks(3)
+                .map(|entry| [entry[0], entry[1], entry[2]])
+                .collect(),
+        })
+    }
+}
+
+impl Display for Plte {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "palette of {} entries", self.entries.len())
+    }
+}
+
+impl Display for Trns {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Trns::Indexed(alpha) => write!(f, "alpha of {} palette entries", alpha.len()),
+            Trns::Grayscale(gray) => write!(f, "transparent gray {}", gray),
+            Trns::Rgb(red, green, blue) => {
+                write!(f, "transparent rgb({}, {}, {})", red, green, blue)
+            }
+        }
+    }
+}
+
+fn invalid(reason: &str) -> crate::Error {
+    Box::new(PngMeError::InvalidChunkData(reason.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ihdr(color_type: u8) -> Ihdr {
+        Ihdr {
+            width: 1,
+            height: 1,
+            bit_depth: 8,
+            color_type,
+            compression_method: 0,
+            filter_method: 0,
+            interlace_method: 0,
+        }
+    }
+
+    #[test]
+    fn test_plte_round_trip() {
+        let plte = Plte {
+            entries: vec![[255, 0, 0], [0, 255, 0]],
+        };
+        let chunk = plte.to_chunk();
+
+        assert_eq!(chunk.length(), 6);
+        assert_eq!(Plte::try_from(&chunk).unwrap(), plte);
+        assert!(Plte::try_from(&Chunk::new(
+            ChunkType::from_str("PLTE").unwrap(),
+            vec![0; 4]
+        ))
+        .is_err());
+    }
+
+    #[test]
+    fn test_trns_depends_on_color_type() {
+        let chunk = Trns::Rgb(1, 2, 3).to_chunk();
+
+        assert_eq!(
+            Trns::from_chunk(&chunk, &ihdr(2)).unwrap(),
+            Trns::Rgb(1, 2, 3)
+        );
+        assert_eq!(
+            Trns::from_chunk(&chunk, &ihdr(3)).unwrap(),
+            Trns::Indexed(vec![0, 1, 0, 2, 0, 3])
+        );
+        assert!(Trns::from_chunk(&chunk, &ihdr(0)).is_err());
+    }
+}
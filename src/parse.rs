@@ -0,0 +1,455 @@
+use alloc::{
+    boxed::Box,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::convert::{TryFrom, TryInto};
+use core::fmt::{self, Display};
+
+use crate::chunk::ChunkRef;
+use crate::chunk_type::ChunkType;
+use crate::error::PngMeError;
+use crate::png::{BEFORE_IDAT_CHUNK_TYPES, BEFORE_PLTE_CHUNK_TYPES, PNG_FILE_SIGNATURE};
+use crate::Result;
+
+/// Ancillary chunks which must appear after `PLTE` when the image has one
+const AFTER_PLTE_CHUNK_TYPES: [&str; 3] = ["bKGD", "hIST", "tRNS"];
+
+/// How strictly PNG files are parsed by `Png::parse`
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ParseMode {
+    /// Fails on any invalid chunk and on chunks out of the order defined by
+    /// the PNG specification
+    #[default]
+    Strict,
+    /// Keeps chunks with a wrong CRC or with the reserved bit set and
+    /// chunks out of order, reporting them as diagnostics. Parsing stops
+    /// on unreadable data keeping every chunk read so far.
+    Lenient,
+    /// Same as `Lenient` but the raw bytes of broken chunks are kept in
+    /// the diagnostics
+    Forensic,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ParseOptions {
+    pub mode: ParseMode,
+}
+
+impl ParseOptions {
+    pub fn strict() -> Self {
+        ParseOptions {
+            mode: ParseMode::Strict,
+        }
+    }
+
+    pub fn lenient() -> Self {
+        ParseOptions {
+            mode: ParseMode::Lenient,
+        }
+    }
+
+    pub fn forensic() -> Self {
+        ParseOptions {
+            mode: ParseMode::Forensic,
+        }
+    }
+}
+
+/// A problem found while parsing a PNG file, `offset` is relative to the
+/// start of the file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub offset: usize,
+    pub kind: DiagnosticKind,
+    /// Raw bytes of the broken chunk, only kept by `ParseMode::Forensic`
+    pub raw: Option<Vec<u8>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiagnosticKind {
+    CrcMismatch {
+        chunk_type: String,
+        stored: u32,
+        computed: u32,
+    },
+    ReservedBitSet {
+        chunk_type: String,
+    },
+    InvalidChunkType {
+        bytes: [u8; 4],
+    },
+    Truncated,
+    OutOfOrder {
+        chunk_type: String,
+        reason: String,
+    },
+}
+
+impl Diagnostic {
+    /// Returns true for diagnostics which stop the parsing
+    pub fn is_fatal(&self) -> bool {
+        matches!(
+            self.kind,
+            DiagnosticKind::InvalidChunkType { .. } | DiagnosticKind::Truncated
+        )
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "offset {}: ", self.offset)?;
+
+        match &self.kind {
+            DiagnosticKind::CrcMismatch {
+                chunk_type,
+                stored,
+                computed,
+            } => write!(
+                f,
+                "{} has a wrong CRC (stored {}, computed {})",
+                chunk_type, stored, computed
+            )?,
+            DiagnosticKind::ReservedBitSet { chunk_type } => {
+                write!(f, "{} has the reserved bit set", chunk_type)?
+            }
+            DiagnosticKind::InvalidChunkType { bytes } => {
+                write!(f, "invalid chunk type {:?}", bytes)?
+            }
+            DiagnosticKind::Truncated => write!(f, "truncated chunk")?,
+            DiagnosticKind::OutOfOrder { chunk_type, reason } => {
+                write!(f, "{} is out of order: {}", chunk_type, reason)?
+            }
+        }
+
+        if let Some(raw) = &self.raw {
+            write!(f, " (raw: {} bytes)", raw.len())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl From<&Diagnostic> for PngMeError {
+    fn from(diagnostic: &Diagnostic) -> Self {
+        match &diagnostic.kind {
+            DiagnosticKind::CrcMismatch { .. } => PngMeError::InvalidCRC,
+            DiagnosticKind::ReservedBitSet { .. } | DiagnosticKind::InvalidChunkType { .. } => {
+                PngMeError::InvalidChunkType
+            }
+            DiagnosticKind::Truncated => PngMeError::TruncatedChunk,
+            DiagnosticKind::OutOfOrder { .. } => {
+                PngMeError::InvalidChunkOrder(diagnostic.to_string())
+            }
+        }
+    }
+}
+
+/// Chunks, trailing data and diagnostics read from a PNG file
+pub(crate) struct Parsed<'a> {
+    pub chunks: Vec<ChunkRef<'a>>,
+    pub trailing_data: &'a [u8],
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+pub(crate) fn parse<'a>(bytes: &'a [u8], options: &ParseOptions) -> Result<Parsed<'a>> {
+    if !bytes.starts_with(&PNG_FILE_SIGNATURE) {
+        return Err(Box::new(PngMeError::InvalidPNGFileHeader));
+    }
+
+    let forensic = options.mode == ParseMode::Forensic;
+    let mut chunks = Vec::new();
+    let mut offsets = Vec::new();
+    let mut diagnostics = Vec::new();
+    let mut trailing_data: &[u8] = &[];
+    let mut cursor = PNG_FILE_SIGNATURE.len();
+    let mut seen_iend = false;
+
+    while cursor < bytes.len() {
+        let header = bytes.get(cursor..cursor + 8);
+        let length = header.map(|h| u32::from_be_bytes(h[..4].try_into().unwrap()) as usize);
+        // The length is untrusted, it can overflow `usize` on 32-bit targets
+        let end = length
+            .and_then(|l| l.checked_add(12))
+            .and_then(|l| cursor.checked_add(l))
+            .filter(|end| *end <= bytes.len());
+        let (header, end) = match (header, end) {
+            (Some(header), Some(end)) => (header, end),
+            _ if seen_iend => {
+                trailing_data = &bytes[cursor..];
+                break;
+            }
+            _ => {
+                diagnostics.push(broken(
+                    cursor,
+                    DiagnosticKind::Truncated,
+                    &bytes[cursor..],
+                    forensic,
+                ));
+                break;
+            }
+        };
+        let type_bytes: [u8; 4] = header[4..].try_into().unwrap();
+        let chunk_type = ChunkType::try_from(type_bytes)?;
+
+        if !type_bytes.iter().all(|b| b.is_ascii_alphabetic()) {
+            if seen_iend {
+                trailing_data = &bytes[cursor..];
+                break;
+            }
+
+            diagnostics.push(broken(
+                cursor,
+                DiagnosticKind::InvalidChunkType { bytes: type_bytes },
+                &bytes[cursor..],
+                forensic,
+            ));
+            break;
+        }
+
+        if !chunk_type.is_reserved_bit_valid() {
+            diagnostics.push(broken(
+                cursor,
+                DiagnosticKind::ReservedBitSet {
+                    chunk_type: chunk_type.to_string(),
+                },
+                &bytes[cursor..end],
+                forensic,
+            ));
+        }
+
+        let stored = u32::from_be_bytes(bytes[end - 4..end].try_into().unwrap());
+        let computed = crc32fast::hash(&bytes[cursor + 4..end - 4]);
+
+        if stored != computed {
+            diagnostics.push(broken(
+                cursor,
+                DiagnosticKind::CrcMismatch {
+                    chunk_type: chunk_type.to_string(),
+                    stored,
+                    computed,
+                },
+                &bytes[cursor..end],
+                forensic,
+            ));
+        }
+
+        seen_iend |= &chunk_type.bytes() == b"IEND";
+        chunks.push(ChunkRef::from_raw_parts(
+            chunk_type,
+            &bytes[cursor + 8..end - 4],
+            stored,
+        ));
+        offsets.push(cursor);
+        cursor = end;
+    }
+
+    diagnostics.extend(ordering_diagnostics(&chunks, &offsets, cursor));
+    diagnostics.sort_by_key(|d| d.offset);
+
+    if options.mode == ParseMode::Strict {
+        if let Some(diagnostic) = diagnostics.first() {
+            return Err(Box::new(PngMeError::from(diagnostic)));
+        }
+    }
+
+    Ok(Parsed {
+        chunks,
+        trailing_data,
+        diagnostics,
+    })
+}
+
+fn broken(offset: usize, kind: DiagnosticKind, raw: &[u8], forensic: bool) -> Diagnostic {
+    Diagnostic {
+        offset,
+        kind,
+        raw: if forensic { Some(raw.to_vec()) } else { None },
+    }
+}
+
+/// Checks the chunks follow the ordering rules of the PNG specification
+///
+/// Reference: http://www.libpng.org/pub/png/spec/1.2/PNG-Chunks.html#C.Summary-of-standard-chunks
+fn ordering_diagnostics(chunks: &[ChunkRef], offsets: &[usize], end: usize) -> Vec<Diagnostic> {
+    let types: Vec<String> = chunks.iter().map(|c| c.chunk_type().to_string()).collect();
+    let first = |chunk_type: &str| types.iter().position(|t| t == chunk_type);
+    let plte = first("PLTE");
+    let idat = first("IDAT");
+    let iend = first("IEND");
+    let mut diagnostics = Vec::new();
+    let mut out_of_order = |index: usize, reason: &str| {
+        diagnostics.push(Diagnostic {
+            offset: offsets[index],
+            kind: DiagnosticKind::OutOfOrder {
+                chunk_type: types[index].clone(),
+                reason: reason.to_string(),
+            },
+            raw: None,
+        })
+    };
+
+    for (index, chunk_type) in types.iter().enumerate() {
+        let chunk_type = chunk_type.as_str();
+        let before = |other: Option<usize>| other.is_none_or(|other| index < other);
+
+        if index == 0 && chunk_type != "IHDR" {
+            out_of_order(index, "the first chunk must be IHDR");
+        } else if index > 0
+            && (chunk_type == "IHDR" || (chunk_type == "PLTE" && plte != Some(index)))
+        {
+            out_of_order(index, "must appear only once");
+        } else if chunk_type == "PLTE" && !before(idat) {
+            out_of_order(index, "must appear before IDAT");
+        } else if chunk_type == "IDAT"
+            && index > 0
+            && types[index - 1] != "IDAT"
+            && idat != Some(index)
+        {
+            out_of_order(index, "IDAT chunks must be consecutive");
+        } else if BEFORE_PLTE_CHUNK_TYPES.contains(&chunk_type) && !(before(plte) && before(idat)) {
+            out_of_order(index, "must appear before PLTE and IDAT");
+        } else if AFTER_PLTE_CHUNK_TYPES.contains(&chunk_type) && plte.is_some_and(|p| index < p) {
+            out_of_order(index, "must appear after PLTE");
+        } else if BEFORE_IDAT_CHUNK_TYPES.contains(&chunk_type) && !before(idat) {
+            out_of_order(index, "must appear before IDAT");
+        } else if iend.is_some_and(|iend| index > iend) {
+            out_of_order(index, "must appear before IEND");
+        }
+    }
+
+    if iend.is_none() && !chunks.is_empty() {
+        diagnostics.push(Diagnostic {
+            offset: end,
+            kind: DiagnosticKind::OutOfOrder {
+                chunk_type: String::from("IEND"),
+                reason: String::from("the last chunk must be IEND"),
+            },
+            raw: None,
+        });
+    }
+
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use core::str::FromStr;
+
+    use super::*;
+    use crate::chunk::Chunk;
+    use crate::png::Png;
+    use crate::view::PngView;
+
+    fn chunk_from_strings(chunk_type: &str, data: &str) -> Chunk {
+        Chunk::new(
+            ChunkType::from_str(chunk_type).unwrap(),
+            data.as_bytes().to_vec(),
+        )
+    }
+
+    fn testing_bytes(types: &[&str]) -> Vec<u8> {
+        let chunks = types
+            .iter()
+            .map(|t| chunk_from_strings(t, "data"))
+            .collect();
+
+        Png::from_chunks(chunks).as_bytes()
+    }
+
+    #[test]
+    fn test_strict_valid_file() {
+        let bytes = testing_bytes(&["IHDR", "gAMA", "IDAT", "IDAT", "tEXt", "IEND"]);
+        let png = Png::parse(&bytes, &ParseOptions::strict()).unwrap();
+
+        assert_eq!(png.chunks().len(), 6);
+        assert!(png.diagnostics().is_empty());
+    }
+
+    #[test]
+    fn test_wrong_crc() {
+        let mut bytes = testing_bytes(&["IHDR", "tEXt", "IDAT", "IEND"]);
+        // Last CRC byte of the `tEXt` chunk
+        bytes[8 + 16 + 15] ^= 0xff;
+
+        assert!(Png::parse(&bytes, &ParseOptions::strict()).is_err());
+
+        let png = Png::parse(&bytes, &ParseOptions::lenient()).unwrap();
+
+        assert_eq!(png.chunks().len(), 4);
+        assert_eq!(png.as_bytes(), bytes);
+        assert_eq!(png.diagnostics().len(), 1);
+        assert_eq!(png.diagnostics()[0].offset, 24);
+        assert!(matches!(
+            png.diagnostics()[0].kind,
+            DiagnosticKind::CrcMismatch { .. }
+        ));
+        assert!(png.diagnostics()[0].raw.is_none());
+    }
+
+    #[test]
+    fn test_reserved_bit_set() {
+        let bytes = testing_bytes(&["IHDR", "IDAT", "sEcr", "IEND"]);
+
+        assert!(Png::parse(&bytes, &ParseOptions::strict()).is_err());
+
+        let png = Png::parse(&bytes, &ParseOptions::lenient()).unwrap();
+
+        assert_eq!(
+            png.diagnostics()[0].kind,
+            DiagnosticKind::ReservedBitSet {
+                chunk_type: String::from("sEcr")
+            }
+        );
+    }
+
+    #[test]
+    fn test_out_of_order() {
+        let bytes = testing_bytes(&["IHDR", "IDAT", "gAMA", "IEND", "ruSt"]);
+
+        assert!(Png::parse(&bytes, &ParseOptions::strict()).is_err());
+        assert!(Png::try_from(bytes.as_ref()).is_ok());
+
+        let png = Png::parse(&bytes, &ParseOptions::lenient()).unwrap();
+        let chunk_types: Vec<String> = png
+            .diagnostics()
+            .iter()
+            .map(|d| match &d.kind {
+                DiagnosticKind::OutOfOrder { chunk_type, .. } => chunk_type.clone(),
+                _ => String::new(),
+            })
+            .collect();
+
+        assert_eq!(chunk_types, vec!["gAMA", "ruSt"]);
+    }
+
+    #[test]
+    fn test_truncated_file() {
+        let bytes = testing_bytes(&["IHDR", "IDAT", "IEND"]);
+        let truncated = &bytes[..bytes.len() - 20];
+
+        assert!(Png::parse(truncated, &ParseOptions::lenient()).is_ok());
+
+        let png = Png::parse(truncated, &ParseOptions::forensic()).unwrap();
+        let diagnostic = &png.diagnostics()[0];
+
+        assert_eq!(png.chunks().len(), 1);
+        assert_eq!(diagnostic.kind, DiagnosticKind::Truncated);
+        assert!(diagnostic.is_fatal());
+        assert_eq!(diagnostic.raw.as_deref(), Some(&truncated[24..]));
+    }
+
+    #[test]
+    fn test_length_past_the_end() {
+        let mut bytes = testing_bytes(&["IHDR", "IDAT", "IEND"]);
+
+        // Largest length, overflows `usize` on 32-bit targets
+        bytes[8 + 16..8 + 20].copy_from_slice(&u32::MAX.to_be_bytes());
+
+        let png = Png::parse(&bytes, &ParseOptions::lenient()).unwrap();
+
+        assert_eq!(png.chunks().len(), 1);
+        assert_eq!(png.diagnostics()[0].kind, DiagnosticKind::Truncated);
+        assert!(PngView::try_from(bytes.as_slice()).is_err());
+    }
+}
@@ -1,27 +1,84 @@
-use std::convert::{TryFrom, TryInto};
-use std::fmt::{self, Display, Formatter};
-use std::fs::{read as read_file, File};
-use std::io::Write;
+use alloc::{boxed::Box, format, string::ToString, vec::Vec};
+use core::convert::TryFrom;
+use core::fmt::{self, Display, Formatter};
+#[cfg(feature = "std")]
+use std::fs::read as read_file;
+#[cfg(feature = "std")]
 use std::path::PathBuf;
 
+use crate::apng::{self, Frame};
+#[cfg(feature = "std")]
+use crate::atomic;
 use crate::chunk::Chunk;
 use crate::error::PngMeError;
+use crate::parse::{Diagnostic, ParseOptions};
+use crate::view::PngView;
 use crate::Error;
 use crate::Result;
 
 /// The first eight bytes of a PNG file always contain the following (decimal) values
 ///
 /// Reference: http://www.libpng.org/pub/png/spec/1.2/PNG-Structure.html
-const PNG_FILE_SIGNATURE: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+pub const PNG_FILE_SIGNATURE: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+
+/// Ancillary chunks which must appear before `PLTE` and `IDAT`
+pub(crate) const BEFORE_PLTE_CHUNK_TYPES: [&str; 6] =
+    ["cHRM", "cICP", "gAMA", "iCCP", "sBIT", "sRGB"];
+
+/// Ancillary chunks which must appear after `PLTE` (if any) and before `IDAT`
+pub(crate) const BEFORE_IDAT_CHUNK_TYPES: [&str; 7] =
+    ["acTL", "bKGD", "eXIf", "hIST", "pHYs", "sPLT", "tRNS"];
 
 /// A PNG file consists of a PNG signature followed by a series of chunks
 pub struct Png {
     chunks: Vec<Chunk>,
+    /// Bytes found after the `IEND` chunk which are not chunks
+    trailing_data: Vec<u8>,
+    /// Problems found while parsing the file, see `Png::parse`
+    diagnostics: Vec<Diagnostic>,
 }
 
 impl Png {
     pub fn from_chunks(chunks: Vec<Chunk>) -> Self {
-        Self { chunks }
+        Self {
+            chunks,
+            trailing_data: Vec::new(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn from_chunks_and_trailing_data(
+        chunks: Vec<Chunk>,
+        trailing_data: Vec<u8>,
+    ) -> Self {
+        Png::from_parts(chunks, trailing_data, Vec::new())
+    }
+
+    pub(crate) fn from_parts(
+        chunks: Vec<Chunk>,
+        trailing_data: Vec<u8>,
+        diagnostics: Vec<Diagnostic>,
+    ) -> Self {
+        Self {
+            chunks,
+            trailing_data,
+            diagnostics,
+        }
+    }
+
+    /// Parses a PNG file with the provided `ParseOptions`, problems found
+    /// in lenient and forensic modes are available in `Png::diagnostics`
+    pub fn parse(bytes: &[u8], options: &ParseOptions) -> Result<Self> {
+        PngView::parse(bytes, options)?.to_png()
+    }
+
+    /// Reads and parses a PNG file with the provided `ParseOptions`
+    #[cfg(feature = "std")]
+    pub fn from_file_with_options(path: PathBuf, options: &ParseOptions) -> Result<Self> {
+        let file = read_file(path)?;
+
+        Png::parse(file.as_slice(), options)
     }
 
     pub fn append_chunk(&mut self, chunk: Chunk) {
@@ -40,22 +97,212 @@ impl Png {
         Err(Box::new(PngMeError::UnexistentChunkType))
     }
 
+    /// Removes the chunk at position `index` shifting every chunk after it,
+    /// the removed chunk is returned
+    pub fn remove_chunk_at(&mut self, index: usize) -> Result<Chunk> {
+        if index >= self.chunks.len() {
+            return Err(format!(
+                "Unable to remove chunk at {}, the PNG file has {} chunks",
+                index,
+                self.chunks.len()
+            )
+            .into());
+        }
+
+        Ok(self.chunks.remove(index))
+    }
+
+    /// Inserts the chunk at position `index` shifting every chunk after it
+    pub fn insert_chunk(&mut self, index: usize, chunk: Chunk) -> Result<()> {
+        if index > self.chunks.len() {
+            return Err(format!(
+                "Unable to insert chunk at {}, the PNG file has {} chunks",
+                index,
+                self.chunks.len()
+            )
+            .into());
+        }
+
+        self.chunks.insert(index, chunk);
+
+        Ok(())
+    }
+
+    /// Inserts the chunk in the position the PNG specification requires for
+    /// its type, chunks without ordering constraints are inserted right
+    /// before `IEND`. The index where the chunk was inserted is returned.
+    ///
+    /// Reference: http://www.libpng.org/pub/png/spec/1.2/PNG-Chunks.html#C.Summary-of-standard-chunks
+    pub fn insert_chunk_ordered(&mut self, chunk: Chunk) -> usize {
+        let chunk_type = chunk.chunk_type().to_string();
+        let end = self.chunk_position("IEND").unwrap_or(self.chunks.len());
+        let index = if BEFORE_PLTE_CHUNK_TYPES.contains(&chunk_type.as_str()) {
+            self.chunk_position("PLTE")
+                .or_else(|| self.chunk_position("IDAT"))
+                .unwrap_or(end)
+        } else if BEFORE_IDAT_CHUNK_TYPES.contains(&chunk_type.as_str()) {
+            self.chunk_position("IDAT").unwrap_or(end)
+        } else {
+            end
+        };
+
+        self.chunks.insert(index, chunk);
+
+        index
+    }
+
+    /// Replaces the chunk at position `index`, the replaced chunk is returned
+    pub fn replace_chunk_at(&mut self, index: usize, chunk: Chunk) -> Result<Chunk> {
+        if index >= self.chunks.len() {
+            return Err(format!(
+                "Unable to replace chunk at {}, the PNG file has {} chunks",
+                index,
+                self.chunks.len()
+            )
+            .into());
+        }
+
+        Ok(core::mem::replace(&mut self.chunks[index], chunk))
+    }
+
+    /// Inserts a chunk holding a message in the position its type requires,
+    /// critical and APNG chunk types are rejected as the message would
+    /// break the image. The file is left unchanged if the message would
+    /// break the APNG rules.
+    pub fn insert_message(&mut self, chunk: Chunk) -> Result<()> {
+        Png::check_message_type(&chunk)?;
+
+        let index = self.insert_chunk_ordered(chunk);
+
+        self.validate_inserted(index)
+    }
+
+    /// Inserts a chunk holding a message at position `index`, with the same
+    /// checks as `insert_message`
+    pub fn insert_message_at(&mut self, index: usize, chunk: Chunk) -> Result<()> {
+        Png::check_message_type(&chunk)?;
+        self.insert_chunk(index, chunk)?;
+
+        self.validate_inserted(index)
+    }
+
+    /// Removes the chunk inserted at `index` if the file breaks the APNG
+    /// rules
+    fn validate_inserted(&mut self, index: usize) -> Result<()> {
+        match apng::validate(self) {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                self.chunks.remove(index);
+                Err(e)
+            }
+        }
+    }
+
+    /// Replaces the first chunk with the same type as the message chunk like
+    /// `replace_chunk` does, critical and APNG chunk types are rejected like
+    /// `insert_message` does. The file is left unchanged if the message
+    /// would break the APNG rules.
+    pub fn replace_message(&mut self, chunk: Chunk) -> Result<Chunk> {
+        Png::check_message_type(&chunk)?;
+
+        let index = self
+            .chunk_position(&chunk.chunk_type().to_string())
+            .ok_or(PngMeError::UnexistentChunkType)?;
+        let replaced = core::mem::replace(&mut self.chunks[index], chunk);
+
+        match apng::validate(self) {
+            Ok(()) => Ok(replaced),
+            Err(e) => {
+                self.chunks[index] = replaced;
+                Err(e)
+            }
+        }
+    }
+
+    fn check_message_type(chunk: &Chunk) -> Result<()> {
+        let chunk_type = chunk.chunk_type();
+
+        if chunk_type.is_critical() {
+            return Err(Box::new(PngMeError::CriticalChunk(chunk_type.to_string())));
+        }
+
+        if apng::is_animation_chunk_type(chunk_type) {
+            return Err(Box::new(PngMeError::InvalidAnimation(format!(
+                "{} can't be used to store messages",
+                chunk_type
+            ))));
+        }
+
+        Ok(())
+    }
+
+    /// Retrieves the index of the first chunk with the provided type
+    pub fn chunk_position(&self, chunk_type: &str) -> Option<usize> {
+        self.chunks
+            .iter()
+            .position(|c| c.chunk_type().bytes() == chunk_type.as_bytes())
+    }
+
+    /// Replaces the first chunk with the same type as the provided `chunk`,
+    /// keeping its position in the file. The replaced chunk is returned.
+    pub fn replace_chunk(&mut self, chunk: Chunk) -> Result<Chunk> {
+        if let Some(index) = self
+            .chunks()
+            .iter()
+            .position(|c| c.chunk_type() == chunk.chunk_type())
+        {
+            return Ok(core::mem::replace(&mut self.chunks[index], chunk));
+        }
+
+        Err(Box::new(PngMeError::UnexistentChunkType))
+    }
+
     pub fn header(&self) -> &[u8; 8] {
         &PNG_FILE_SIGNATURE
     }
 
+    /// Retrieves the frames of an APNG file, see `apng::frames`
+    pub fn frames(&self) -> Result<Vec<Frame<'_>>> {
+        apng::frames(self)
+    }
+
+    /// Retrieves the problems found while parsing the file
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        self.diagnostics.as_slice()
+    }
+
     pub fn chunks(&self) -> &[Chunk] {
         self.chunks.as_slice()
     }
 
     pub fn chunk_by_type(&self, chunk_type: &str) -> Option<&Chunk> {
-        for chunk in self.chunks.iter() {
-            if chunk.chunk_type().bytes() == chunk_type.as_bytes() {
-                return Some(chunk);
-            }
+        self.chunks
+            .iter()
+            .find(|c| c.chunk_type().bytes() == chunk_type.as_bytes())
+    }
+
+    /// Retrieves the bytes found after the `IEND` chunk which couldn't be
+    /// parsed as chunks
+    pub fn trailing_data(&self) -> &[u8] {
+        self.trailing_data.as_slice()
+    }
+
+    /// Removes every chunk after the `IEND` chunk along with any trailing
+    /// data, returns the number of bytes removed
+    pub fn remove_data_after_iend(&mut self) -> usize {
+        let mut removed = self.trailing_data.len();
+
+        if let Some(index) = self.chunk_position("IEND") {
+            removed += self
+                .chunks
+                .drain(index + 1..)
+                .map(|c| c.as_bytes().len())
+                .sum::<usize>();
         }
 
-        None
+        self.trailing_data.clear();
+
+        removed
     }
 
     pub fn as_bytes(&self) -> Vec<u8> {
@@ -63,22 +310,28 @@ impl Png {
             .iter()
             .copied()
             .chain(self.chunks().iter().flat_map(|c| c.as_bytes()))
+            .chain(self.trailing_data.iter().copied())
             .collect()
     }
 
+    #[cfg(feature = "std")]
     pub fn from_file(path: PathBuf) -> Result<Self> {
         let file = read_file(path)?;
 
         Png::try_from(file.as_slice())
     }
 
+    /// Atomically writes the PNG file into `path`, see `atomic::write`
+    #[cfg(feature = "std")]
     pub fn write_file(&self, path: PathBuf) -> Result<()> {
-        let mut file =
-            File::create(path).map_err(|e| PngMeError::UnableToCreateFile(e.to_string()))?;
-        file.write(self.as_bytes().as_slice())
-            .map_err(|e| PngMeError::UnableToWriteOutputFile(e.to_string()))?;
+        atomic::write(&path, self.as_bytes().as_slice(), None)
+    }
 
-        Ok(())
+    /// Atomically writes the PNG file into `path` keeping a copy of the
+    /// file being replaced with the provided `backup_suffix`
+    #[cfg(feature = "std")]
+    pub fn write_file_with_backup(&self, path: PathBuf, backup_suffix: &str) -> Result<()> {
+        atomic::write(&path, self.as_bytes().as_slice(), Some(backup_suffix))
     }
 }
 
@@ -86,38 +339,7 @@ impl TryFrom<&[u8]> for Png {
     type Error = Error;
 
     fn try_from(value: &[u8]) -> Result<Self> {
-        // First 8 bytes from PNG file must be exact
-        // the same as `PNG_FILE_SIGNATURE`
-        let header: [u8; 8] = value[..8].try_into()?;
-
-        if !header.eq(&PNG_FILE_SIGNATURE) {
-            return Err(Box::new(PngMeError::InvalidPNGFileHeader));
-        }
-
-        let mut chunks: Vec<Chunk> = Vec::new();
-        let mut cursor: usize = 8;
-
-        // We skip the first 8 bytes which represents
-        // the PNG file header and extract chunks
-        while cursor < value.len() {
-            // The first 4 bytes from a Chunk represents
-            // the length of the chunk
-            let length = (u32::from_be_bytes(value[cursor..cursor + 4].try_into()?) + 12) as usize;
-
-            match Chunk::try_from(&value[cursor..cursor + length]) {
-                Ok(chunk) => {
-                    // Pushes the chunk to the `chunks` Vectors
-                    chunks.push(chunk);
-                    // "Moves the cursor" the number of bytes ahead
-                    cursor += length;
-                }
-                Err(_) => {
-                    panic!("Invalid chunk provided.");
-                }
-            }
-        }
-
-        Ok(Png { chunks })
+        PngView::try_from(value)?.to_png()
     }
 }
 
@@ -133,7 +355,13 @@ impl Display for Png {
     }
 }
 
+// These tests are kept as they were written with the chunk codec
 #[cfg(test)]
+#[allow(
+    unused_imports,
+    clippy::iter_cloned_collect,
+    clippy::vec_init_then_push
+)]
 mod tests {
     use super::*;
     use crate::chunk::Chunk;
@@ -231,6 +459,15 @@ mod tests {
         assert!(png.is_err());
     }
 
+    #[test]
+    fn test_truncated_file() {
+        let png = Png::try_from(&PNG_FILE[..PNG_FILE.len() - 20]);
+        let header = Png::try_from(&PNG_FILE[..4]);
+
+        assert!(png.is_err());
+        assert!(header.is_err());
+    }
+
     #[test]
     fn test_list_chunks() {
         let png = testing_png();
@@ -264,6 +501,145 @@ mod tests {
         assert!(chunk.is_none());
     }
 
+    #[test]
+    fn test_insert_chunk() {
+        let mut png = testing_png();
+        png.insert_chunk(1, chunk_from_strings("TeSt", "Message").unwrap())
+            .unwrap();
+
+        assert_eq!(png.chunk_position("TeSt"), Some(1));
+        assert_eq!(png.chunk_position("miDl"), Some(2));
+        assert!(png
+            .insert_chunk(10, chunk_from_strings("TeSt", "Message").unwrap())
+            .is_err());
+    }
+
+    #[test]
+    fn test_remove_chunk_at() {
+        let mut png = testing_png();
+        let chunk = png.remove_chunk_at(1).unwrap();
+
+        assert_eq!(chunk.chunk_type().to_string(), "miDl");
+        assert_eq!(png.chunk_position("LASt"), Some(1));
+        assert!(png.remove_chunk_at(10).is_err());
+    }
+
+    #[test]
+    fn test_insert_chunk_ordered() {
+        let mut png = Png::from_chunks(vec![
+            chunk_from_strings("IHDR", "header").unwrap(),
+            chunk_from_strings("PLTE", "palette").unwrap(),
+            chunk_from_strings("IDAT", "data").unwrap(),
+            chunk_from_strings("IEND", "").unwrap(),
+        ]);
+
+        assert_eq!(
+            png.insert_chunk_ordered(chunk_from_strings("tEXt", "text").unwrap()),
+            3
+        );
+        assert_eq!(
+            png.insert_chunk_ordered(chunk_from_strings("tRNS", "alpha").unwrap()),
+            2
+        );
+        assert_eq!(
+            png.insert_chunk_ordered(chunk_from_strings("iCCP", "profile").unwrap()),
+            1
+        );
+
+        let types: Vec<String> = png
+            .chunks()
+            .iter()
+            .map(|c| c.chunk_type().to_string())
+            .collect();
+
+        assert_eq!(
+            types,
+            vec!["IHDR", "iCCP", "PLTE", "tRNS", "IDAT", "tEXt", "IEND"]
+        );
+    }
+
+    #[test]
+    fn test_insert_message() {
+        let mut png = Png::from_chunks(vec![
+            chunk_from_strings("IHDR", "header").unwrap(),
+            chunk_from_strings("IDAT", "data").unwrap(),
+            chunk_from_strings("IEND", "").unwrap(),
+        ]);
+
+        png.insert_message(chunk_from_strings("ruSt", "message").unwrap())
+            .unwrap();
+
+        assert_eq!(png.chunk_position("ruSt"), Some(2));
+        assert!(png
+            .insert_message(chunk_from_strings("IDAT", "message").unwrap())
+            .is_err());
+        assert!(png
+            .insert_message(chunk_from_strings("fcTL", "message").unwrap())
+            .is_err());
+    }
+
+    #[test]
+    fn test_replace_message() {
+        let mut png = Png::from_chunks(vec![
+            chunk_from_strings("IHDR", "header").unwrap(),
+            chunk_from_strings("IDAT", "data").unwrap(),
+            chunk_from_strings("ruSt", "message").unwrap(),
+            chunk_from_strings("IEND", "").unwrap(),
+        ]);
+        let before = png.as_bytes();
+
+        assert!(png
+            .replace_message(chunk_from_strings("IDAT", "message").unwrap())
+            .is_err());
+        assert_eq!(png.as_bytes(), before);
+
+        let replaced = png
+            .replace_message(chunk_from_strings("ruSt", "new message").unwrap())
+            .unwrap();
+
+        assert_eq!(replaced.data(), b"message");
+        assert_eq!(png.chunk_position("ruSt"), Some(2));
+    }
+
+    #[test]
+    fn test_replace_chunk() {
+        let mut png = testing_png();
+        let replaced = png
+            .replace_chunk(chunk_from_strings("miDl", "I am the new chunk").unwrap())
+            .unwrap();
+        let chunk = &png.chunks()[1];
+        let expected = chunk_from_strings("miDl", "I am the new chunk").unwrap();
+
+        assert_eq!(&replaced.data_as_string().unwrap(), "I am another chunk");
+        assert_eq!(png.chunks().len(), 3);
+        assert_eq!(&chunk.data_as_string().unwrap(), "I am the new chunk");
+        assert_eq!(chunk.crc(), expected.crc());
+    }
+
+    #[test]
+    fn test_replace_unexistent_chunk() {
+        let mut png = testing_png();
+        let result = png.replace_chunk(chunk_from_strings("TeSt", "Message").unwrap());
+
+        assert!(result.is_err());
+        assert_eq!(png.chunks().len(), 3);
+    }
+
+    #[test]
+    fn test_trailing_data() {
+        let mut bytes = PNG_FILE.to_vec();
+
+        bytes.extend(chunk_from_strings("ruSt", "After IEND").unwrap().as_bytes());
+        bytes.extend(b"trailing garbage");
+
+        let mut png = Png::try_from(bytes.as_ref()).unwrap();
+
+        assert_eq!(png.trailing_data(), b"trailing garbage");
+        assert_eq!(png.as_bytes(), bytes);
+        assert_eq!(png.remove_data_after_iend(), 22 + 16);
+        assert_eq!(png.as_bytes(), PNG_FILE.to_vec());
+    }
+
     #[test]
     fn test_png_from_image_file() {
         let png = Png::try_from(&PNG_FILE[..]);
@@ -0,0 +1,228 @@
+use crate::error::PngMeError;
+use crate::ihdr::Ihdr;
+use crate::Result;
+
+/// Uncompressed and unfiltered scanlines of an image, along with the filter
+/// type of every scanline so it can be filtered back the same way
+///
+/// Reference: http://www.libpng.org/pub/png/spec/1.2/PNG-Filters.html
+#[derive(Debug, Clone, PartialEq)]
+pub struct Raster {
+    pub width: u32,
+    pub height: u32,
+    /// Bytes per complete pixel, rounded up to one
+    pub bytes_per_pixel: usize,
+    pub filter_types: Vec<u8>,
+    pub pixels: Vec<u8>,
+}
+
+impl Raster {
+    /// Inflates and unfilters the image data of a `width` x `height` image,
+    /// interlaced images are not supported
+    pub fn decode(ihdr: &Ihdr, width: u32, height: u32, data: &[u8]) -> Result<Self> {
+        if ihdr.interlace_method != 0 {
+            return Err(invalid("interlaced images are not supported"));
+        }
+
+        let bytes_per_pixel = ihdr.bits_per_pixel().div_ceil(8).max(1);
+        let row_length = Raster::row_length(ihdr, width)?;
+        // Dimensions come from the file, the sizes are checked so huge
+        // frames are rejected instead of overflowing
+        let filtered_length = (row_length + 1)
+            .checked_mul(height as usize)
+            .ok_or_else(|| invalid("the image is too large"))?;
+        let pixels_length = row_length
+            .checked_mul(height as usize)
+            .ok_or_else(|| invalid("the image is too large"))?;
+        let filtered = crate::text::decompress_limited(data, filtered_length)?;
+
+        if filtered.len() < filtered_length {
+            return Err(invalid("the image data is shorter than expected"));
+        }
+
+        let mut filter_types = Vec::with_capacity(height as usize);
+        let mut pixels = vec![0; pixels_length];
+
+        for row in 0..height as usize {
+            let start = row * (row_length + 1);
+            let filter_type = filtered[start];
+            let (previous, current) = pixels.split_at_mut(row * row_length);
+            let prior = if row == 0 {
+                None
+            } else {
+                Some(&previous[(row - 1) * row_length..])
+            };
+
+            current[..row_length].copy_from_slice(&filtered[start + 1..start + 1 + row_length]);
+            unfilter(
+                filter_type,
+                bytes_per_pixel,
+                prior,
+                &mut current[..row_length],
+            )?;
+            filter_types.push(filter_type);
+        }
+
+        Ok(Raster {
+            width,
+            height,
+            bytes_per_pixel,
+            filter_types,
+            pixels,
+        })
+    }
+
+    /// Filters every scanline with its original filter type and deflates
+    /// the result
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        let row_length = self.pixels.len() / self.height.max(1) as usize;
+        let mut filtered = Vec::with_capacity((row_length + 1) * self.height as usize);
+
+        for (row, filter_type) in self.filter_types.iter().enumerate() {
+            let current = &self.pixels[row * row_length..(row + 1) * row_length];
+            let prior = if row == 0 {
+                None
+            } else {
+                Some(&self.pixels[(row - 1) * row_length..row * row_length])
+            };
+
+            filtered.push(*filter_type);
+            filtered.extend(filter(*filter_type, self.bytes_per_pixel, prior, current));
+        }
+
+        crate::text::compress(&filtered)
+    }
+
+    /// Number of bytes of an unfiltered scanline
+    pub fn row_length(ihdr: &Ihdr, width: u32) -> Result<usize> {
+        ihdr.bits_per_pixel()
+            .checked_mul(width as usize)
+            .map(|bits| bits.div_ceil(8))
+            .ok_or_else(|| invalid("the image is too large"))
+    }
+}
+
+fn unfilter(filter_type: u8, bpp: usize, prior: Option<&[u8]>, row: &mut [u8]) -> Result<()> {
+    for i in 0..row.len() {
+        let a = if i >= bpp { row[i - bpp] } else { 0 };
+        let b = prior.map_or(0, |p| p[i]);
+        let c = if i >= bpp {
+            prior.map_or(0, |p| p[i - bpp])
+        } else {
+            0
+        };
+
+        row[i] = row[i].wrapping_add(predict(filter_type, a, b, c)?);
+    }
+
+    Ok(())
+}
+
+fn filter(filter_type: u8, bpp: usize, prior: Option<&[u8]>, row: &[u8]) -> Vec<u8> {
+    (0..row.len())
+        .map(|i| {
+            let a = if i >= bpp { row[i - bpp] } else { 0 };
+            let b = prior.map_or(0, |p| p[i]);
+            let c = if i >= bpp {
+                prior.map_or(0, |p| p[i - bpp])
+            } else {
+                0
+            };
+
+            // Filter types are checked while decoding
+            row[i].wrapping_sub(predict(filter_type, a, b, c).unwrap_or(0))
+        })
+        .collect()
+}
+
+fn predict(filter_type: u8, a: u8, b: u8, c: u8) -> Result<u8> {
+    match filter_type {
+        0 => Ok(0),
+        1 => Ok(a),
+        2 => Ok(b),
+        3 => Ok(((a as u16 + b as u16) / 2) as u8),
+        4 => Ok(paeth(a, b, c)),
+        _ => Err(invalid(&format!("unknown filter type {}", filter_type))),
+    }
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+fn invalid(reason: &str) -> crate::Error {
+    Box::new(PngMeError::InvalidImageData(reason.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ihdr() -> Ihdr {
+        Ihdr {
+            width: 3,
+            height: 3,
+            bit_depth: 8,
+            color_type: 2,
+            compression_method: 0,
+            filter_method: 0,
+            interlace_method: 0,
+        }
+    }
+
+    #[test]
+    fn test_round_trip_every_filter_type() {
+        let pixels: Vec<u8> = (0..27u8).map(|b| b.wrapping_mul(37)).collect();
+        let raster = Raster {
+            width: 3,
+            height: 3,
+            bytes_per_pixel: 3,
+            filter_types: vec![1, 3, 4],
+            pixels: pixels.clone(),
+        };
+        let encoded = raster.encode().unwrap();
+        let decoded = Raster::decode(&ihdr(), 3, 3, &encoded).unwrap();
+
+        assert_eq!(decoded, raster);
+
+        for filter_type in 0..5 {
+            let raster = Raster {
+                filter_types: vec![filter_type; 3],
+                ..raster.clone()
+            };
+            let decoded = Raster::decode(&ihdr(), 3, 3, &raster.encode().unwrap()).unwrap();
+
+            assert_eq!(decoded.pixels, pixels);
+        }
+    }
+
+    #[test]
+    fn test_decode_unknown_filter_type() {
+        let data = crate::text::compress(&[5; 30]).unwrap();
+
+        assert!(Raster::decode(&ihdr(), 3, 3, &data).is_err());
+    }
+
+    #[test]
+    fn test_decode_huge_frame() {
+        let ihdr = Ihdr {
+            bit_depth: 16,
+            color_type: 6,
+            ..ihdr()
+        };
+        let data = crate::text::compress(&[0; 30]).unwrap();
+
+        assert!(Raster::decode(&ihdr, u32::MAX, u32::MAX, &data).is_err());
+    }
+}
@@ -0,0 +1,449 @@
+use std::convert::{TryFrom, TryInto};
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use crate::chunk::{Chunk, ChunkRef};
+use crate::chunk_type::ChunkType;
+use crate::error::PngMeError;
+use crate::png::{Png, PNG_FILE_SIGNATURE};
+use crate::Result;
+
+#[derive(Debug, Default)]
+pub struct RepairOptions {
+    /// Keeps chunks with a wrong CRC recomputing it, otherwise such chunks
+    /// are dropped except `IDAT` chunks which are kept with the wrong CRC
+    pub fix_crc: bool,
+}
+
+/// A repair made while parsing a damaged PNG file, offsets are relative to
+/// the start of the file
+#[derive(Debug, PartialEq)]
+pub enum Repair {
+    FixedSignature,
+    FixedCrc {
+        chunk_type: String,
+        offset: usize,
+    },
+    DroppedChunk {
+        chunk_type: String,
+        offset: usize,
+        reason: String,
+    },
+    SalvagedImageData {
+        offset: usize,
+        length: usize,
+    },
+    /// An `IDAT` chunk with a wrong CRC kept without `fix_crc`, its data
+    /// may be damaged so the stored CRC is kept too
+    KeptImageDataWithWrongCrc {
+        offset: usize,
+        length: usize,
+    },
+    Resynced {
+        offset: usize,
+        skipped: usize,
+    },
+    DroppedTrailingBytes {
+        offset: usize,
+        length: usize,
+    },
+    AppendedIend,
+}
+
+impl Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Repair::FixedSignature => write!(f, "Fixed the PNG file signature"),
+            Repair::FixedCrc { chunk_type, offset } => {
+                write!(
+                    f,
+                    "Recomputed the CRC of {} at offset {}",
+                    chunk_type, offset
+                )
+            }
+            Repair::DroppedChunk {
+                chunk_type,
+                offset,
+                reason,
+            } => write!(f, "Dropped {} at offset {}: {}", chunk_type, offset, reason),
+            Repair::SalvagedImageData { offset, length } => write!(
+                f,
+                "Salvaged {} bytes of image data from the IDAT at offset {}",
+                length, offset
+            ),
+            Repair::KeptImageDataWithWrongCrc { offset, length } => write!(
+                f,
+                "Kept {} bytes of image data from the IDAT at offset {} despite a CRC mismatch, \
+                 the data may be damaged",
+                length, offset
+            ),
+            Repair::Resynced { offset, skipped } => write!(
+                f,
+                "Skipped {} invalid bytes at offset {} up to the next valid chunk",
+                skipped, offset
+            ),
+            Repair::DroppedTrailingBytes { offset, length } => write!(
+                f,
+                "Dropped {} unreadable bytes at offset {}",
+                length, offset
+            ),
+            Repair::AppendedIend => write!(f, "Appended the missing IEND chunk"),
+        }
+    }
+}
+
+/// Lenient parser for PNG files rejected by `Png::try_from`.
+///
+/// Chunks are read the same way `Png::try_from` does, but instead of
+/// failing on damaged data the parser:
+///
+/// - Recomputes wrong CRCs when `fix_crc` is set, otherwise the chunk is
+///   dropped unless it is an `IDAT` chunk, which is kept with its stored
+///   CRC
+/// - Scans for the next valid chunk when a chunk type or length is corrupted
+/// - Keeps as many bytes as possible from damaged `IDAT` chunks
+/// - Appends the `IEND` chunk if missing
+///
+/// Every repair made is returned along with the resulting `Png`.
+pub fn repair(bytes: &[u8], options: &RepairOptions) -> Result<(Png, Vec<Repair>)> {
+    let mut repairs = Vec::new();
+    let mut chunks: Vec<Chunk> = Vec::new();
+    let mut trailing_data = Vec::new();
+    let mut cursor = PNG_FILE_SIGNATURE.len();
+    let mut seen_iend = false;
+
+    if !bytes.starts_with(&PNG_FILE_SIGNATURE) {
+        // A damaged signature is only fixed if the file starts with an
+        // `IHDR` chunk right after it
+        if bytes.get(12..16) != Some(b"IHDR") {
+            return Err(Box::new(PngMeError::InvalidPNGFileHeader));
+        }
+
+        repairs.push(Repair::FixedSignature);
+    }
+
+    while cursor < bytes.len() {
+        if seen_iend {
+            trailing_data = bytes[cursor..].to_vec();
+            break;
+        }
+
+        let (length, chunk_type) = match header_at(bytes, cursor) {
+            Some(header) => header,
+            None => {
+                repairs.push(Repair::DroppedTrailingBytes {
+                    offset: cursor,
+                    length: bytes.len() - cursor,
+                });
+                break;
+            }
+        };
+
+        if !chunk_type.is_valid() {
+            match find_next_chunk(bytes, cursor + 1) {
+                Some(next) => {
+                    repairs.push(Repair::Resynced {
+                        offset: cursor,
+                        skipped: next - cursor,
+                    });
+                    cursor = next;
+                    continue;
+                }
+                None => {
+                    repairs.push(Repair::DroppedTrailingBytes {
+                        offset: cursor,
+                        length: bytes.len() - cursor,
+                    });
+                    break;
+                }
+            }
+        }
+
+        // A length overflowing `usize` is handled as any other wrong length
+        let end = length
+            .checked_add(12)
+            .and_then(|length| cursor.checked_add(length))
+            .unwrap_or(usize::MAX);
+
+        if is_chunk_at(bytes, cursor) {
+            seen_iend = &chunk_type.bytes() == b"IEND";
+            chunks.push(Chunk::try_from(&bytes[cursor..end])?);
+            cursor = end;
+            continue;
+        }
+
+        // The chunk fits in the file and the next chunk header is valid, so
+        // only the CRC is wrong
+        if end <= bytes.len() && (end == bytes.len() || has_valid_header_at(bytes, end)) {
+            if options.fix_crc {
+                seen_iend = &chunk_type.bytes() == b"IEND";
+                chunks.push(Chunk::new(
+                    chunk_type.clone(),
+                    bytes[cursor + 8..end - 4].to_vec(),
+                ));
+                repairs.push(Repair::FixedCrc {
+                    chunk_type: chunk_type.to_string(),
+                    offset: cursor,
+                });
+            } else if &chunk_type.bytes() == b"IDAT" {
+                chunks.push(ChunkRef::read(&bytes[cursor..end], false)?.to_chunk());
+                repairs.push(Repair::KeptImageDataWithWrongCrc {
+                    offset: cursor,
+                    length,
+                });
+            } else {
+                repairs.push(Repair::DroppedChunk {
+                    chunk_type: chunk_type.to_string(),
+                    offset: cursor,
+                    reason: String::from("CRC mismatch"),
+                });
+            }
+
+            cursor = end;
+            continue;
+        }
+
+        // Otherwise the length is wrong or the file is truncated, the
+        // chunk data ends right before the CRC of the next valid chunk
+        let next = find_next_chunk(bytes, cursor + 8);
+        let data_end = next
+            .map(|n| n.saturating_sub(4).max(cursor + 8))
+            .unwrap_or(bytes.len());
+
+        if &chunk_type.bytes() == b"IDAT" && data_end > cursor + 8 {
+            chunks.push(Chunk::new(chunk_type, bytes[cursor + 8..data_end].to_vec()));
+            repairs.push(Repair::SalvagedImageData {
+                offset: cursor,
+                length: data_end - cursor - 8,
+            });
+        } else {
+            repairs.push(Repair::DroppedChunk {
+                chunk_type: chunk_type.to_string(),
+                offset: cursor,
+                reason: String::from("invalid length or truncated data"),
+            });
+        }
+
+        match next {
+            Some(next) => {
+                repairs.push(Repair::Resynced {
+                    offset: data_end,
+                    skipped: next - data_end,
+                });
+                cursor = next;
+            }
+            None => break,
+        }
+    }
+
+    if !seen_iend {
+        chunks.push(Chunk::new(ChunkType::from_str("IEND")?, Vec::new()));
+        repairs.push(Repair::AppendedIend);
+    }
+
+    Ok((
+        Png::from_chunks_and_trailing_data(chunks, trailing_data),
+        repairs,
+    ))
+}
+
+/// Reads the length and chunk type at `offset`
+fn header_at(bytes: &[u8], offset: usize) -> Option<(usize, ChunkType)> {
+    let header = bytes.get(offset..offset + 8)?;
+    let length = u32::from_be_bytes(header[..4].try_into().unwrap()) as usize;
+    let chunk_type: [u8; 4] = header[4..].try_into().unwrap();
+
+    Some((length, ChunkType::try_from(chunk_type).ok()?))
+}
+
+fn has_valid_header_at(bytes: &[u8], offset: usize) -> bool {
+    matches!(header_at(bytes, offset), Some((_, chunk_type)) if chunk_type.is_valid())
+}
+
+/// Checks wether a chunk with a valid type, length and CRC starts at `offset`
+fn is_chunk_at(bytes: &[u8], offset: usize) -> bool {
+    let (length, chunk_type) = match header_at(bytes, offset) {
+        Some(header) => header,
+        None => return false,
+    };
+    let end = match length
+        .checked_add(12)
+        .and_then(|length| offset.checked_add(length))
+    {
+        Some(end) if end <= bytes.len() => end,
+        _ => return false,
+    };
+    let crc = u32::from_be_bytes(bytes[end - 4..end].try_into().unwrap());
+
+    chunk_type.is_valid() && crc == crc32fast::hash(&bytes[offset + 4..end - 4])
+}
+
+fn find_next_chunk(bytes: &[u8], from: usize) -> Option<usize> {
+    (from..bytes.len()).find(|offset| is_chunk_at(bytes, *offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk_from_strings(chunk_type: &str, data: &str) -> Chunk {
+        Chunk::new(
+            ChunkType::from_str(chunk_type).unwrap(),
+            data.as_bytes().to_vec(),
+        )
+    }
+
+    fn testing_bytes() -> Vec<u8> {
+        let png = Png::from_chunks(vec![
+            chunk_from_strings("IHDR", "header data!!"),
+            chunk_from_strings("tEXt", "Comment\0Hello"),
+            chunk_from_strings("IDAT", "image data"),
+            chunk_from_strings("IEND", ""),
+        ]);
+
+        png.as_bytes()
+    }
+
+    fn types(png: &Png) -> Vec<String> {
+        png.chunks()
+            .iter()
+            .map(|c| c.chunk_type().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_file() {
+        let bytes = testing_bytes();
+        let (png, repairs) = repair(&bytes, &RepairOptions::default()).unwrap();
+
+        assert!(repairs.is_empty());
+        assert_eq!(png.as_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_wrong_crc() {
+        let mut bytes = testing_bytes();
+        // Last CRC byte of the `tEXt` chunk
+        bytes[8 + 25 + 25 - 1] ^= 0xff;
+
+        let (png, repairs) = repair(&bytes, &RepairOptions::default()).unwrap();
+
+        assert_eq!(types(&png), vec!["IHDR", "IDAT", "IEND"]);
+        assert!(matches!(
+            repairs[0],
+            Repair::DroppedChunk { offset: 33, .. }
+        ));
+
+        let (png, repairs) = repair(&bytes, &RepairOptions { fix_crc: true }).unwrap();
+
+        assert_eq!(png.as_bytes(), testing_bytes());
+        assert_eq!(
+            repairs,
+            vec![Repair::FixedCrc {
+                chunk_type: String::from("tEXt"),
+                offset: 33
+            }]
+        );
+    }
+
+    #[test]
+    fn test_wrong_idat_crc() {
+        let mut bytes = testing_bytes();
+        // Last CRC byte of the `IDAT` chunk
+        bytes[58 + 22 - 1] ^= 0xff;
+
+        let (png, repairs) = repair(&bytes, &RepairOptions::default()).unwrap();
+
+        // The damage stays visible in the stored CRC
+        assert_eq!(png.as_bytes(), bytes);
+        assert_eq!(
+            repairs,
+            vec![Repair::KeptImageDataWithWrongCrc {
+                offset: 58,
+                length: 10
+            }]
+        );
+
+        let (png, _) = repair(&bytes, &RepairOptions { fix_crc: true }).unwrap();
+
+        assert_eq!(png.as_bytes(), testing_bytes());
+    }
+
+    #[test]
+    fn test_wrong_idat_length() {
+        let mut bytes = testing_bytes();
+        // Length of the `IDAT` chunk
+        bytes[58..62].copy_from_slice(&1000u32.to_be_bytes());
+
+        let (png, repairs) = repair(&bytes, &RepairOptions::default()).unwrap();
+
+        assert_eq!(types(&png), vec!["IHDR", "tEXt", "IDAT", "IEND"]);
+        assert_eq!(png.chunk_by_type("IDAT").unwrap().data(), b"image data");
+        assert_eq!(
+            repairs,
+            vec![
+                Repair::SalvagedImageData {
+                    offset: 58,
+                    length: 10
+                },
+                Repair::Resynced {
+                    offset: 76,
+                    skipped: 4
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_largest_idat_length() {
+        let mut bytes = testing_bytes();
+        // Length of the `IDAT` chunk, overflows `usize` on 32-bit targets
+        bytes[58..62].copy_from_slice(&u32::MAX.to_be_bytes());
+
+        let (png, _) = repair(&bytes, &RepairOptions::default()).unwrap();
+
+        assert_eq!(png.chunk_by_type("IDAT").unwrap().data(), b"image data");
+    }
+
+    #[test]
+    fn test_garbage_between_chunks() {
+        let mut bytes = testing_bytes();
+
+        bytes.splice(58..58, b"garbage".iter().copied());
+
+        let (png, repairs) = repair(&bytes, &RepairOptions::default()).unwrap();
+
+        assert_eq!(png.as_bytes(), testing_bytes());
+        assert_eq!(
+            repairs,
+            vec![Repair::Resynced {
+                offset: 58,
+                skipped: 7
+            }]
+        );
+    }
+
+    #[test]
+    fn test_truncated_file() {
+        let bytes = testing_bytes();
+        let (png, repairs) = repair(&bytes[..70], &RepairOptions::default()).unwrap();
+
+        assert_eq!(types(&png), vec!["IHDR", "tEXt", "IDAT", "IEND"]);
+        assert_eq!(png.chunk_by_type("IDAT").unwrap().data(), b"imag");
+        assert_eq!(
+            repairs,
+            vec![
+                Repair::SalvagedImageData {
+                    offset: 58,
+                    length: 4
+                },
+                Repair::AppendedIend,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_not_a_png_file() {
+        assert!(repair(b"definitely not a PNG file", &RepairOptions::default()).is_err());
+    }
+}
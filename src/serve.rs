@@ -0,0 +1,440 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::io::{Cursor, Read, Write};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::thread;
+
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use pngme::chunk::Chunk;
+use pngme::chunk_type::ChunkType;
+use pngme::png::Png;
+use pngme::stego;
+use pngme::strip::{self, StripOptions};
+use pngme::view::PngView;
+
+use crate::commands::embed_message;
+use crate::Result;
+
+/// Limits applied to the requests handled by the server
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    /// Largest request body accepted, larger bodies are answered with
+    /// `413 Payload Too Large`
+    pub max_body_size: usize,
+    /// Number of requests handled at the same time, other requests wait
+    /// until one of them is answered
+    pub max_concurrency: usize,
+}
+
+/// An error answered to the client as `{ "error": message }`
+#[derive(Debug)]
+struct HttpError {
+    status: u16,
+    message: String,
+}
+
+impl HttpError {
+    fn new(status: u16, message: &str) -> Self {
+        HttpError {
+            status,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl From<pngme::Error> for HttpError {
+    fn from(error: pngme::Error) -> Self {
+        HttpError::new(400, &error.to_string())
+    }
+}
+
+/// The response to a request or the error answered instead
+type Reply = std::result::Result<Response<Cursor<Vec<u8>>>, HttpError>;
+
+/// The PNG file and parameters of a request. Parameters are read from the
+/// query string and, for `multipart/form-data` bodies, from the fields
+/// which are not files.
+#[derive(Debug)]
+struct Input {
+    png: Vec<u8>,
+    params: HashMap<String, String>,
+}
+
+impl Input {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params.get(name).map(String::as_str)
+    }
+
+    fn required_param(&self, name: &str) -> std::result::Result<&str, HttpError> {
+        self.param(name)
+            .ok_or_else(|| HttpError::new(400, &format!("Missing parameter {}", name)))
+    }
+}
+
+/// A part of a `multipart/form-data` body
+#[derive(Debug)]
+struct Part<'a> {
+    name: String,
+    filename: Option<String>,
+    data: &'a [u8],
+}
+
+/// Serves `POST /encode`, `/decode`, `/inspect` and `/strip` on the
+/// provided address until the process is stopped. Each endpoint takes the
+/// PNG file as the raw request body or as the file of a
+/// `multipart/form-data` body.
+pub fn run(bind: &str, limits: Limits) -> Result<()> {
+    if limits.max_concurrency == 0 {
+        return Err("The server needs to handle at least one request at a time".into());
+    }
+
+    let server = Arc::new(Server::http(bind).map_err(|e| e.to_string())?);
+
+    match server.server_addr().to_ip() {
+        Some(address) => println!("Listening on http://{}", address),
+        None => println!("Listening on {}", bind),
+    }
+
+    // The address is read by the clients starting the server
+    std::io::stdout().flush()?;
+
+    let workers: Vec<_> = (0..limits.max_concurrency)
+        .map(|_| {
+            let server = Arc::clone(&server);
+
+            thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    handle(request, &limits);
+                }
+            })
+        })
+        .collect();
+
+    for worker in workers {
+        worker
+            .join()
+            .map_err(|_| "A server worker stopped unexpectedly")?;
+    }
+
+    Ok(())
+}
+
+fn handle(mut request: Request, limits: &Limits) {
+    let response = match respond(&mut request, limits) {
+        Ok(response) => response,
+        Err(error) => json_response(error.status, &json!({ "error": error.message })),
+    };
+
+    // The client may have gone away, there is nobody left to answer
+    let _ = request.respond(response);
+}
+
+fn respond(request: &mut Request, limits: &Limits) -> Reply {
+    let url = request.url().to_string();
+    let (path, query) = match url.split_once('?') {
+        Some((path, query)) => (path, query),
+        None => (url.as_str(), ""),
+    };
+
+    if !["/encode", "/decode", "/inspect", "/strip"].contains(&path) {
+        return Err(HttpError::new(404, &format!("Unknown endpoint {}", path)));
+    }
+
+    if request.method() != &Method::Post {
+        return Err(HttpError::new(405, "Only POST requests are supported"));
+    }
+
+    let input = read_input(request, query, limits)?;
+
+    match path {
+        "/encode" => encode(&input),
+        "/decode" => decode(&input),
+        "/inspect" => inspect(&input),
+        _ => strip(&input),
+    }
+}
+
+fn encode(input: &Input) -> Reply {
+    let mut png = Png::try_from(input.png.as_slice())?;
+    let chunk_type = ChunkType::from_str(input.required_param("chunk_type")?)
+        .map_err(|e| HttpError::new(400, &e.to_string()))?;
+    let chunk = Chunk::new(
+        chunk_type,
+        input.required_param("message")?.as_bytes().to_vec(),
+    );
+
+    embed_message(&mut png, chunk, input.param("method").unwrap_or("chunk"))?;
+
+    Ok(png_response(png.as_bytes()))
+}
+
+fn decode(input: &Input) -> Reply {
+    let png = PngView::try_from(input.png.as_slice())?;
+    let chunk_type = input.required_param("chunk_type")?;
+    let message = match input.param("method").unwrap_or("chunk") {
+        "chunk" => match png.chunk_by_type(chunk_type)? {
+            Some(chunk) => chunk.data().to_vec(),
+            None => {
+                return Err(HttpError::new(
+                    404,
+                    &format!("Chunk type: {} not found", chunk_type),
+                ))
+            }
+        },
+        method => {
+            let tag =
+                ChunkType::from_str(chunk_type).map_err(|e| HttpError::new(400, &e.to_string()))?;
+            let png = png.to_png()?;
+
+            match method {
+                "apng-frames" => stego::reveal_from_frames(&png, &tag)?,
+                "palette" => stego::reveal_from_palette(&png, &tag)?,
+                _ => return Err(HttpError::new(400, &format!("Unknown method {}", method))),
+            }
+        }
+    };
+
+    Ok(json_response(
+        200,
+        &json!({
+            "chunk_type": chunk_type,
+            "message": String::from_utf8_lossy(&message),
+        }),
+    ))
+}
+
+fn inspect(input: &Input) -> Reply {
+    let png = PngView::try_from(input.png.as_slice())?;
+    let chunks: Vec<Value> = png
+        .chunks()
+        .iter()
+        .map(|chunk| {
+            json!({
+                "type": chunk.chunk_type().to_string(),
+                "length": chunk.length(),
+                "crc": chunk.crc(),
+            })
+        })
+        .collect();
+
+    Ok(json_response(
+        200,
+        &json!({
+            "chunks": chunks,
+            "trailing_data": png.trailing_data().len(),
+        }),
+    ))
+}
+
+fn strip(input: &Input) -> Reply {
+    let mut png = Png::try_from(input.png.as_slice())?;
+    let mut options = StripOptions::default();
+
+    if let Some(keep) = input.param("keep") {
+        options.keep = keep
+            .split(',')
+            .filter(|t| !t.is_empty())
+            .map(String::from)
+            .collect();
+    }
+
+    strip::strip(&mut png, &options)?;
+
+    Ok(png_response(png.as_bytes()))
+}
+
+/// Reads the body of the request, up to `Limits::max_body_size` bytes, and
+/// the PNG file and parameters it holds
+fn read_input(
+    request: &mut Request,
+    query: &str,
+    limits: &Limits,
+) -> std::result::Result<Input, HttpError> {
+    let too_large = || {
+        HttpError::new(
+            413,
+            &format!(
+                "The request body is larger than {} bytes",
+                limits.max_body_size
+            ),
+        )
+    };
+
+    if request.body_length().unwrap_or(0) > limits.max_body_size {
+        return Err(too_large());
+    }
+
+    let mut body = Vec::new();
+
+    request
+        .as_reader()
+        .take(limits.max_body_size as u64 + 1)
+        .read_to_end(&mut body)
+        .map_err(|e| HttpError::new(400, &e.to_string()))?;
+
+    if body.len() > limits.max_body_size {
+        return Err(too_large());
+    }
+
+    let mut params: HashMap<String, String> = form_urlencoded::parse(query.as_bytes())
+        .into_owned()
+        .collect();
+    let content_type = request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv("Content-Type"))
+        .map(|h| h.value.to_string())
+        .unwrap_or_default();
+
+    if !content_type.starts_with("multipart/form-data") {
+        return Ok(Input { png: body, params });
+    }
+
+    let mut png = None;
+
+    for part in multipart_parts(&content_type, &body)? {
+        if part.filename.is_some() || part.name == "file" {
+            png = Some(part.data.to_vec());
+        } else {
+            params.insert(part.name, String::from_utf8_lossy(part.data).to_string());
+        }
+    }
+
+    match png {
+        Some(png) => Ok(Input { png, params }),
+        None => Err(HttpError::new(400, "The multipart body has no file")),
+    }
+}
+
+/// Splits a `multipart/form-data` body into its parts
+///
+/// Reference: https://www.rfc-editor.org/rfc/rfc7578
+fn multipart_parts<'a>(
+    content_type: &str,
+    body: &'a [u8],
+) -> std::result::Result<Vec<Part<'a>>, HttpError> {
+    let invalid =
+        |reason: &str| HttpError::new(400, &format!("Invalid multipart body: {}", reason));
+    let boundary = content_type
+        .split(';')
+        .map(str::trim)
+        .find_map(|param| param.strip_prefix("boundary="))
+        .map(|boundary| boundary.trim_matches('"'))
+        .ok_or_else(|| invalid("missing boundary"))?;
+    let delimiter = format!("--{}", boundary).into_bytes();
+    let mut delimiters = Vec::new();
+    let mut cursor = 0;
+
+    while let Some(position) = find(&body[cursor..], &delimiter) {
+        delimiters.push(cursor + position);
+        cursor += position + delimiter.len();
+    }
+
+    if delimiters.len() < 2 {
+        return Err(invalid("missing delimiters"));
+    }
+
+    delimiters
+        .windows(2)
+        .map(|window| {
+            // Every part sits between the line break following a delimiter
+            // and the line break preceding the next one
+            let part = &body[window[0] + delimiter.len()..window[1]];
+            let part = part
+                .strip_prefix(b"\r\n")
+                .and_then(|part| part.strip_suffix(b"\r\n"))
+                .ok_or_else(|| invalid("missing line breaks"))?;
+            let headers_end = find(part, b"\r\n\r\n").ok_or_else(|| invalid("missing headers"))?;
+            let headers = String::from_utf8_lossy(&part[..headers_end]);
+            let disposition = headers
+                .lines()
+                .find_map(|line| {
+                    let (name, value) = line.split_once(':')?;
+
+                    if name.trim().eq_ignore_ascii_case("Content-Disposition") {
+                        Some(value.to_string())
+                    } else {
+                        None
+                    }
+                })
+                .ok_or_else(|| invalid("missing Content-Disposition"))?;
+            let param = |name: &str| {
+                disposition
+                    .split(';')
+                    .map(str::trim)
+                    .find_map(|param| param.strip_prefix(name)?.strip_prefix('='))
+                    .map(|value| value.trim_matches('"').to_string())
+            };
+
+            Ok(Part {
+                name: param("name").ok_or_else(|| invalid("missing field name"))?,
+                filename: param("filename"),
+                data: &part[headers_end + 4..],
+            })
+        })
+        .collect()
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+fn png_response(bytes: Vec<u8>) -> Response<Cursor<Vec<u8>>> {
+    // The file is already in memory, sending it with its length instead of
+    // in chunks keeps clients simple
+    Response::from_data(bytes)
+        .with_chunked_threshold(usize::MAX)
+        .with_header(header("Content-Type", "image/png"))
+}
+
+fn json_response(status: u16, value: &Value) -> Response<Cursor<Vec<u8>>> {
+    Response::from_data(value.to_string().into_bytes())
+        .with_status_code(status)
+        .with_header(header("Content-Type", "application/json"))
+}
+
+fn header(field: &str, value: &str) -> Header {
+    Header::from_bytes(field.as_bytes(), value.as_bytes()).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multipart_parts() {
+        let body = b"--XyZ\r\n\
+            Content-Disposition: form-data; name=\"chunk_type\"\r\n\r\n\
+            ruSt\r\n\
+            --XyZ\r\n\
+            Content-Disposition: form-data; name=\"file\"; filename=\"dice.png\"\r\n\
+            Content-Type: image/png\r\n\r\n\
+            \x89PNG\r\n\r\n\
+            --XyZ--\r\n";
+        let parts = multipart_parts("multipart/form-data; boundary=XyZ", body).unwrap();
+
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].name, "chunk_type");
+        assert_eq!(parts[0].filename, None);
+        assert_eq!(parts[0].data, b"ruSt");
+        assert_eq!(parts[1].name, "file");
+        assert_eq!(parts[1].filename.as_deref(), Some("dice.png"));
+        assert_eq!(parts[1].data, b"\x89PNG\r\n");
+    }
+
+    #[test]
+    fn test_invalid_multipart_parts() {
+        assert!(multipart_parts("multipart/form-data", b"").is_err());
+        assert!(multipart_parts("multipart/form-data; boundary=XyZ", b"--XyZ\r\n").is_err());
+        assert!(multipart_parts(
+            "multipart/form-data; boundary=XyZ",
+            b"--XyZ\r\nContent-Type: text/plain\r\n\r\nruSt\r\n--XyZ--"
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,652 @@
+use std::convert::TryFrom;
+
+use crate::apng::{self, AnimationControl, FrameControl, FrameData};
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::error::PngMeError;
+use crate::ihdr::Ihdr;
+use crate::palette::{Plte, Trns};
+use crate::png::Png;
+use crate::raster::Raster;
+use crate::Result;
+
+/// Every payload is preceded by the 4 bytes of its tag and its length as a
+/// 4 bytes big endian integer
+const HEADER_LENGTH: usize = 8;
+
+/// Number of payload bytes that can be hidden in the frames of an APNG file
+pub fn frames_capacity(png: &Png) -> Result<usize> {
+    let (ihdr, rasters) = frame_rasters(png)?;
+    let bits: usize = rasters.iter().map(|r| carriers(&ihdr, r).count()).sum();
+
+    Ok((bits / 8).saturating_sub(HEADER_LENGTH))
+}
+
+/// Hides the payload in the least significant bit of the samples of the
+/// animation frames, filling the frames in order. Only the frames holding
+/// the payload are encoded again, the number of frames, their delays and
+/// their operations are kept as they are.
+pub fn hide_in_frames(png: &Png, tag: &ChunkType, payload: &[u8]) -> Result<Png> {
+    let (ihdr, mut rasters) = frame_rasters(png)?;
+    let available = rasters
+        .iter()
+        .map(|r| carriers(&ihdr, r).count() / 8)
+        .sum::<usize>()
+        .saturating_sub(HEADER_LENGTH);
+
+    if payload.len() > available || payload.len() > u32::MAX as usize {
+        return Err(Box::new(PngMeError::PayloadTooLarge(
+            payload.len(),
+            available,
+        )));
+    }
+
+    let header = [tag.bytes(), (payload.len() as u32).to_be_bytes()].concat();
+    let mut bits = header
+        .iter()
+        .chain(payload.iter())
+        .flat_map(|byte| (0..8).rev().map(move |i| (byte >> i) & 1))
+        .peekable();
+    let mut frame_data = Vec::new();
+
+    for raster in rasters.iter_mut() {
+        if bits.peek().is_none() {
+            break;
+        }
+
+        for index in carriers(&ihdr, raster).collect::<Vec<usize>>() {
+            match bits.next() {
+                Some(bit) => raster.pixels[index] = (raster.pixels[index] & !1) | bit,
+                None => break,
+            }
+        }
+
+        frame_data.push(raster.encode()?);
+    }
+
+    replace_frame_data(png, frame_data)
+}
+
+/// Reveals a payload hidden with `hide_in_frames`
+pub fn reveal_from_frames(png: &Png, tag: &ChunkType) -> Result<Vec<u8>> {
+    let (ihdr, rasters) = frame_rasters(png)?;
+    let mut bits = rasters
+        .iter()
+        .flat_map(|r| carriers(&ihdr, r).map(move |index| r.pixels[index] & 1));
+    let not_found = || -> crate::Error { Box::new(PngMeError::PayloadNotFound(tag.to_string())) };
+    let header = read_bytes(&mut bits, HEADER_LENGTH).ok_or_else(not_found)?;
+
+    if header[0..4] != tag.bytes() {
+        return Err(not_found());
+    }
+
+    let length = u32::from_be_bytes([header[4], header[5], header[6], header[7]]) as usize;
+
+    read_bytes(&mut bits, length).ok_or_else(not_found)
+}
+
+/// Decodes the image data of every frame of an APNG file
+fn frame_rasters(png: &Png) -> Result<(Ihdr, Vec<Raster>)> {
+    if AnimationControl::from_png(png)?.is_none() {
+        return Err(Box::new(PngMeError::InvalidAnimation(
+            "the file isn't an animated PNG".to_string(),
+        )));
+    }
+
+    let ihdr = Ihdr::from_png(png)?;
+
+    if ihdr.color_type == 3 || ihdr.bit_depth < 8 {
+        return Err(Box::new(PngMeError::InvalidImageData(format!(
+            "{}-bit {} images can't hold a payload in their frames",
+            ihdr.bit_depth,
+            ihdr.color_type_name()
+        ))));
+    }
+
+    let rasters = apng::frames(png)?
+        .iter()
+        .map(|frame| {
+            Raster::decode(
+                &ihdr,
+                frame.control.width,
+                frame.control.height,
+                &frame.data(),
+            )
+        })
+        .collect::<Result<Vec<Raster>>>()?;
+
+    Ok((ihdr, rasters))
+}
+
+/// Indexes of the bytes holding the least significant bit of every sample
+fn carriers<'a>(ihdr: &Ihdr, raster: &'a Raster) -> impl Iterator<Item = usize> + 'a {
+    let bytes_per_sample = ihdr.bit_depth as usize / 8;
+
+    (bytes_per_sample - 1..raster.pixels.len()).step_by(bytes_per_sample)
+}
+
+fn read_bytes(bits: &mut impl Iterator<Item = u8>, length: usize) -> Option<Vec<u8>> {
+    (0..length)
+        .map(|_| (0..8).try_fold(0u8, |byte, _| bits.next().map(|bit| (byte << 1) | bit)))
+        .collect()
+}
+
+/// Replaces the image data of the first frames with `frame_data`, every
+/// frame is stored in a single chunk and the sequence numbers are updated
+fn replace_frame_data(png: &Png, frame_data: Vec<Vec<u8>>) -> Result<Png> {
+    let mut chunks = Vec::with_capacity(png.chunks().len());
+    let mut frame: Option<usize> = None;
+    let mut replaced = false;
+    let mut sequence_number = 0;
+
+    for chunk in png.chunks() {
+        match &chunk.chunk_type().bytes() {
+            b"fcTL" => {
+                let mut control = FrameControl::try_from(chunk)?;
+
+                frame = Some(frame.map_or(0, |f| f + 1));
+                replaced = false;
+                control.sequence_number = sequence_number;
+                sequence_number += 1;
+                chunks.push(control.to_chunk());
+            }
+            b"IDAT" | b"fdAT" => match frame.and_then(|f| frame_data.get(f)) {
+                Some(_) if replaced => {}
+                Some(data) => {
+                    replaced = true;
+                    chunks.push(data_chunk(chunk, data, &mut sequence_number));
+                }
+                None => chunks.push(data_chunk(chunk, data(chunk), &mut sequence_number)),
+            },
+            _ => chunks.push(chunk.clone()),
+        }
+    }
+
+    let png = Png::from_chunks_and_trailing_data(chunks, png.trailing_data().to_vec());
+
+    apng::validate(&png)?;
+
+    Ok(png)
+}
+
+fn data(chunk: &Chunk) -> &[u8] {
+    match &chunk.chunk_type().bytes() {
+        b"fdAT" => chunk.data().get(4..).unwrap_or_default(),
+        _ => chunk.data(),
+    }
+}
+
+fn data_chunk(chunk: &Chunk, data: &[u8], sequence_number: &mut u32) -> Chunk {
+    if &chunk.chunk_type().bytes() == b"IDAT" {
+        return Chunk::new(chunk.chunk_type().clone(), data.to_vec());
+    }
+
+    let frame_data = FrameData {
+        sequence_number: *sequence_number,
+        data: data.to_vec(),
+    };
+
+    *sequence_number += 1;
+    frame_data.to_chunk()
+}
+
+/// Palette payloads are preceded by the 4 bytes of their tag and their
+/// length as a 2 bytes big endian integer, as palettes hold few bytes
+const PALETTE_HEADER_LENGTH: usize = 6;
+
+/// Number of payload bytes that can be hidden in the order of the palette
+/// entries of an indexed image, `log2(n!)` bits for `n` distinct colors
+pub fn palette_capacity(png: &Png) -> Result<usize> {
+    let colors = palette_colors(png)?;
+
+    Ok(permutation_bytes(canonical_order(&colors).len()).saturating_sub(PALETTE_HEADER_LENGTH))
+}
+
+/// Hides the payload in the order of the palette entries of an indexed
+/// image. Duplicated colors are merged and the indices of the image data,
+/// `tRNS`, `bKGD` and `hIST` chunks are remapped so the image looks the same.
+pub fn hide_in_palette(png: &Png, tag: &ChunkType, payload: &[u8]) -> Result<Png> {
+    let colors = palette_colors(png)?;
+    let canonical = canonical_order(&colors);
+    let length = permutation_bytes(canonical.len());
+    let available = length.saturating_sub(PALETTE_HEADER_LENGTH);
+
+    if payload.len() > available {
+        return Err(Box::new(PngMeError::PayloadTooLarge(
+            payload.len(),
+            available,
+        )));
+    }
+
+    let mut number = [
+        &tag.bytes()[..],
+        &(payload.len() as u16).to_be_bytes(),
+        payload,
+    ]
+    .concat();
+    let mut remaining = canonical.clone();
+    let mut palette = Vec::with_capacity(canonical.len());
+
+    number.resize(length, 0);
+
+    for radix in (1..=canonical.len() as u32).rev() {
+        let digit = divide(&mut number, radix);
+
+        palette.push(remaining.remove(digit as usize));
+    }
+
+    let mapping: Vec<u8> = colors
+        .iter()
+        .map(|color| palette.iter().position(|c| c == color).unwrap() as u8)
+        .collect();
+
+    remap_palette(png, &palette, &mapping)
+}
+
+/// Reveals a payload hidden with `hide_in_palette`
+pub fn reveal_from_palette(png: &Png, tag: &ChunkType) -> Result<Vec<u8>> {
+    let colors = palette_colors(png)?;
+    let mut remaining = canonical_order(&colors);
+    let not_found = || -> crate::Error { Box::new(PngMeError::PayloadNotFound(tag.to_string())) };
+
+    if remaining.len() != colors.len() {
+        return Err(not_found());
+    }
+
+    let digits: Vec<u32> = colors
+        .iter()
+        .map(|color| {
+            let digit = remaining.iter().position(|c| c == color).unwrap();
+
+            remaining.remove(digit);
+            digit as u32
+        })
+        .collect();
+    let mut number = vec![0; permutation_bytes(colors.len())];
+
+    for (index, digit) in digits.iter().enumerate().rev() {
+        if !multiply_add(&mut number, (colors.len() - index) as u32, *digit) {
+            return Err(not_found());
+        }
+    }
+
+    let length = number
+        .get(4..PALETTE_HEADER_LENGTH)
+        .map(|l| u16::from_be_bytes([l[0], l[1]]) as usize)
+        .ok_or_else(not_found)?;
+
+    if number[0..4] != tag.bytes() || PALETTE_HEADER_LENGTH + length > number.len() {
+        return Err(not_found());
+    }
+
+    Ok(number[PALETTE_HEADER_LENGTH..PALETTE_HEADER_LENGTH + length].to_vec())
+}
+
+/// Colors of the palette of an indexed image along with their alpha
+fn palette_colors(png: &Png) -> Result<Vec<[u8; 4]>> {
+    let ihdr = Ihdr::from_png(png)?;
+
+    if ihdr.color_type != 3 {
+        return Err(Box::new(PngMeError::InvalidImageData(format!(
+            "{} images don't have a palette",
+            ihdr.color_type_name()
+        ))));
+    }
+
+    if AnimationControl::from_png(png)?.is_some() {
+        return Err(Box::new(PngMeError::InvalidImageData(
+            "the palette of animated PNG files can't hold a payload".to_string(),
+        )));
+    }
+
+    let plte = Plte::from_png(png)?.ok_or(PngMeError::UnexistentChunkType)?;
+    let alpha = match Trns::from_png(png)? {
+        Some(Trns::Indexed(alpha)) => alpha,
+        _ => Vec::new(),
+    };
+
+    Ok(plte
+        .entries
+        .iter()
+        .enumerate()
+        .map(|(index, [r, g, b])| [*r, *g, *b, *alpha.get(index).unwrap_or(&255)])
+        .collect())
+}
+
+/// Distinct colors sorted, the permutation of the palette is relative to
+/// this order
+fn canonical_order(colors: &[[u8; 4]]) -> Vec<[u8; 4]> {
+    let mut canonical = colors.to_vec();
+
+    canonical.sort_unstable();
+    canonical.dedup();
+    canonical
+}
+
+/// Whole number of bytes that fit in the permutations of `count` entries
+fn permutation_bytes(count: usize) -> usize {
+    let mut factorial = vec![0; count + 1];
+
+    factorial[count] = 1;
+
+    for factor in 2..=count as u32 {
+        multiply_add(&mut factorial, factor, 0);
+    }
+
+    let leading_zeros: usize = factorial
+        .iter()
+        .position(|b| *b != 0)
+        .map(|index| index * 8 + factorial[index].leading_zeros() as usize)
+        .unwrap_or(0);
+    let bits = factorial.len() * 8 - leading_zeros;
+
+    bits.saturating_sub(1) / 8
+}
+
+/// Divides a big endian number in place and returns the remainder
+fn divide(number: &mut [u8], divisor: u32) -> u32 {
+    number.iter_mut().fold(0, |remainder, byte| {
+        let current = (remainder << 8) | *byte as u32;
+
+        *byte = (current / divisor) as u8;
+        current % divisor
+    })
+}
+
+/// Computes `number * factor + addend` in place, returns false on overflow
+fn multiply_add(number: &mut [u8], factor: u32, addend: u32) -> bool {
+    let carry = number.iter_mut().rev().fold(addend, |carry, byte| {
+        let current = *byte as u32 * factor + carry;
+
+        *byte = current as u8;
+        current >> 8
+    });
+
+    carry == 0
+}
+
+/// Replaces the palette and rewrites every palette index with `mapping`,
+/// which maps the original indices to the indices of `palette`
+fn remap_palette(png: &Png, palette: &[[u8; 4]], mapping: &[u8]) -> Result<Png> {
+    let ihdr = Ihdr::from_png(png)?;
+    let image_data: Vec<u8> = png
+        .chunks()
+        .iter()
+        .filter(|c| &c.chunk_type().bytes() == b"IDAT")
+        .flat_map(|c| c.data().iter().copied())
+        .collect();
+    let mut raster = Raster::decode(&ihdr, ihdr.width, ihdr.height, &image_data)?;
+    let bit_depth = ihdr.bit_depth as usize;
+    let row_length = Raster::row_length(&ihdr, ihdr.width)?;
+
+    for row in raster.pixels.chunks_mut(row_length) {
+        for x in 0..ihdr.width as usize {
+            let byte = x * bit_depth / 8;
+            let shift = 8 - bit_depth - x * bit_depth % 8;
+            let mask = ((1u16 << bit_depth) - 1) as u8;
+            let index = (row[byte] >> shift) & mask;
+            let remapped = *mapping.get(index as usize).ok_or_else(|| {
+                PngMeError::InvalidImageData(format!("palette index {} out of range", index))
+            })?;
+
+            row[byte] = (row[byte] & !(mask << shift)) | (remapped << shift);
+        }
+    }
+
+    let plte = Plte {
+        entries: palette.iter().map(|c| [c[0], c[1], c[2]]).collect(),
+    };
+    let mut alpha: Vec<u8> = palette.iter().map(|c| c[3]).collect();
+
+    while alpha.last() == Some(&255) {
+        alpha.pop();
+    }
+
+    let mut chunks = Vec::with_capacity(png.chunks().len());
+
+    for chunk in png.chunks() {
+        match &chunk.chunk_type().bytes() {
+            b"PLTE" => chunks.push(plte.to_chunk()),
+            b"tRNS" if !alpha.is_empty() => chunks.push(Trns::Indexed(alpha.clone()).to_chunk()),
+            b"tRNS" => {}
+            b"IDAT"
+                if chunks
+                    .iter()
+                    .any(|c: &Chunk| &c.chunk_type().bytes() == b"IDAT") => {}
+            b"IDAT" => chunks.push(Chunk::new(chunk.chunk_type().clone(), raster.encode()?)),
+            b"bKGD" => {
+                let index = chunk.data().first().copied().unwrap_or(0) as usize;
+                let remapped = mapping.get(index).copied().unwrap_or(0);
+
+                chunks.push(Chunk::new(chunk.chunk_type().clone(), vec![remapped]));
+            }
+            b"hIST" => {
+                let mut histogram = vec![0u16; palette.len()];
+
+                for (index, frequency) in chunk.data().chunks(2).enumerate() {
+                    if let (Some(remapped), [high, low]) = (mapping.get(index), frequency) {
+                        let entry = &mut histogram[*remapped as usize];
+
+                        *entry = entry.saturating_add(u16::from_be_bytes([*high, *low]));
+                    }
+                }
+
+                let data = histogram.iter().flat_map(|f| f.to_be_bytes()).collect();
+
+                chunks.push(Chunk::new(chunk.chunk_type().clone(), data));
+            }
+            _ => chunks.push(chunk.clone()),
+        }
+    }
+
+    Ok(Png::from_chunks_and_trailing_data(
+        chunks,
+        png.trailing_data().to_vec(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apng::{BlendOp, DisposeOp};
+    use std::str::FromStr;
+
+    fn frame_control(sequence_number: u32) -> FrameControl {
+        FrameControl {
+            sequence_number,
+            width: 4,
+            height: 4,
+            x_offset: 0,
+            y_offset: 0,
+            delay_num: 1,
+            delay_den: 10,
+            dispose_op: DisposeOp::None,
+            blend_op: BlendOp::Source,
+        }
+    }
+
+    fn image_data(seed: u8) -> Vec<u8> {
+        let raster = Raster {
+            width: 4,
+            height: 4,
+            bytes_per_pixel: 4,
+            filter_types: vec![0, 1, 2, 4],
+            pixels: (0..64u8).map(|b| b.wrapping_mul(seed)).collect(),
+        };
+
+        raster.encode().unwrap()
+    }
+
+    fn testing_png() -> Png {
+        let ihdr = Ihdr {
+            width: 4,
+            height: 4,
+            bit_depth: 8,
+            color_type: 6,
+            compression_method: 0,
+            filter_method: 0,
+            interlace_method: 0,
+        };
+        let second = image_data(7);
+        let (head, tail) = second.split_at(10);
+
+        Png::from_chunks(vec![
+            ihdr.to_chunk(),
+            AnimationControl {
+                num_frames: 3,
+                num_plays: 0,
+            }
+            .to_chunk(),
+            frame_control(0).to_chunk(),
+            Chunk::new(ChunkType::from_str("IDAT").unwrap(), image_data(3)),
+            frame_control(1).to_chunk(),
+            FrameData {
+                sequence_number: 2,
+                data: head.to_vec(),
+            }
+            .to_chunk(),
+            FrameData {
+                sequence_number: 3,
+                data: tail.to_vec(),
+            }
+            .to_chunk(),
+            frame_control(4).to_chunk(),
+            FrameData {
+                sequence_number: 5,
+                data: image_data(11),
+            }
+            .to_chunk(),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()),
+        ])
+    }
+
+    #[test]
+    fn test_frames_capacity() {
+        // 3 frames of 64 samples
+        assert_eq!(frames_capacity(&testing_png()).unwrap(), 24 - HEADER_LENGTH);
+    }
+
+    #[test]
+    fn test_hide_and_reveal() {
+        let png = testing_png();
+        let tag = ChunkType::from_str("ruSt").unwrap();
+        let payload = b"across frames";
+        let hidden = hide_in_frames(&png, &tag, payload).unwrap();
+
+        assert_eq!(reveal_from_frames(&hidden, &tag).unwrap(), payload);
+        assert_eq!(
+            AnimationControl::from_png(&hidden).unwrap(),
+            AnimationControl::from_png(&png).unwrap()
+        );
+        assert!(apng::validate(&hidden).is_ok());
+
+        let original = frame_rasters(&png).unwrap().1;
+        let modified = frame_rasters(&hidden).unwrap().1;
+
+        for (a, b) in original.iter().zip(modified.iter()) {
+            assert!(a
+                .pixels
+                .iter()
+                .zip(b.pixels.iter())
+                .all(|(a, b)| (a ^ b) <= 1));
+        }
+    }
+
+    #[test]
+    fn test_hide_too_large_payload() {
+        let tag = ChunkType::from_str("ruSt").unwrap();
+
+        assert!(hide_in_frames(&testing_png(), &tag, &[0; 17]).is_err());
+    }
+
+    #[test]
+    fn test_reveal_with_another_tag() {
+        let png = testing_png();
+        let hidden =
+            hide_in_frames(&png, &ChunkType::from_str("ruSt").unwrap(), b"message").unwrap();
+
+        assert!(reveal_from_frames(&hidden, &ChunkType::from_str("abCd").unwrap()).is_err());
+    }
+
+    fn indexed_png() -> Png {
+        let ihdr = Ihdr {
+            width: 6,
+            height: 7,
+            bit_depth: 8,
+            color_type: 3,
+            compression_method: 0,
+            filter_method: 0,
+            interlace_method: 0,
+        };
+        // The last two entries duplicate the entries 4 and 5
+        let mut entries: Vec<[u8; 3]> = (0..40u8).map(|i| [i * 6, 255 - i, i % 7]).collect();
+
+        entries.extend_from_within(4..6);
+
+        let raster = Raster {
+            width: 6,
+            height: 7,
+            bytes_per_pixel: 1,
+            filter_types: vec![0; 7],
+            pixels: (0..42u8).collect(),
+        };
+
+        Png::from_chunks(vec![
+            ihdr.to_chunk(),
+            Plte { entries }.to_chunk(),
+            Trns::Indexed(vec![0, 128, 255, 64]).to_chunk(),
+            Chunk::new(ChunkType::from_str("bKGD").unwrap(), vec![41]),
+            Chunk::new(
+                ChunkType::from_str("IDAT").unwrap(),
+                raster.encode().unwrap(),
+            ),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()),
+        ])
+    }
+
+    fn rendered(png: &Png) -> Vec<[u8; 4]> {
+        let ihdr = Ihdr::from_png(png).unwrap();
+        let colors = palette_colors(png).unwrap();
+        let data = png.chunk_by_type("IDAT").unwrap().data();
+        let raster = Raster::decode(&ihdr, ihdr.width, ihdr.height, data).unwrap();
+
+        raster.pixels.iter().map(|i| colors[*i as usize]).collect()
+    }
+
+    #[test]
+    fn test_palette_capacity() {
+        // 40 distinct colors, 40! is about 2^159
+        assert_eq!(permutation_bytes(40), 19);
+        assert_eq!(permutation_bytes(1), 0);
+        assert_eq!(
+            palette_capacity(&indexed_png()).unwrap(),
+            19 - PALETTE_HEADER_LENGTH
+        );
+    }
+
+    #[test]
+    fn test_hide_and_reveal_in_palette() {
+        let png = indexed_png();
+        let tag = ChunkType::from_str("ruSt").unwrap();
+        let hidden = hide_in_palette(&png, &tag, b"palette").unwrap();
+
+        assert_eq!(reveal_from_palette(&hidden, &tag).unwrap(), b"palette");
+        assert_eq!(rendered(&hidden), rendered(&png));
+        assert_eq!(Plte::from_png(&hidden).unwrap().unwrap().entries.len(), 40);
+
+        let background = hidden.chunk_by_type("bKGD").unwrap().data()[0];
+
+        assert_eq!(
+            palette_colors(&hidden).unwrap()[background as usize][0..3],
+            [30, 250, 5]
+        );
+    }
+
+    #[test]
+    fn test_hide_in_palette_of_truecolor_image() {
+        let tag = ChunkType::from_str("ruSt").unwrap();
+
+        assert!(hide_in_palette(&testing_png(), &tag, b"palette").is_err());
+        assert!(hide_in_palette(&indexed_png(), &tag, &[0; 14]).is_err());
+    }
+}
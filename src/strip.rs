@@ -0,0 +1,169 @@
+use std::collections::BTreeSet;
+
+use crate::chunk::Chunk;
+use crate::png::Png;
+use crate::Result;
+
+/// Ancillary chunks which are always kept given that they change the
+/// decoded pixels: `tRNS` defines transparency and the APNG chunks define
+/// the animation frames
+const PIXEL_CHUNK_TYPES: [&str; 4] = ["tRNS", "acTL", "fcTL", "fdAT"];
+
+/// Ancillary chunks kept by default
+pub const DEFAULT_KEEP_CHUNK_TYPES: [&str; 3] = ["sRGB", "gAMA", "iCCP"];
+
+/// Describes which ancillary chunks are removed when stripping a PNG file.
+///
+/// Every ancillary chunk is removed, `eXIf`, `tIME` and the textual chunks
+/// included, except the ones in `keep` and the chunks required to decode
+/// the image pixels.
+#[derive(Debug)]
+pub struct StripOptions {
+    pub keep: Vec<String>,
+}
+
+/// Outcome of stripping a PNG file
+#[derive(Debug, Default, PartialEq)]
+pub struct StripReport {
+    /// Type and length of every removed chunk
+    pub removed: Vec<(String, u32)>,
+    /// Number of bytes removed after the `IEND` chunk
+    pub bytes_after_iend: usize,
+}
+
+impl Default for StripOptions {
+    fn default() -> Self {
+        StripOptions {
+            keep: DEFAULT_KEEP_CHUNK_TYPES
+                .iter()
+                .map(|t| t.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl StripOptions {
+    fn is_kept(&self, chunk: &Chunk) -> bool {
+        let chunk_type = chunk.chunk_type().to_string();
+        let chunk_type = chunk_type.as_str();
+
+        chunk.chunk_type().is_critical()
+            || PIXEL_CHUNK_TYPES.contains(&chunk_type)
+            || self.keep.iter().any(|t| t == chunk_type)
+    }
+}
+
+/// Removes the ancillary chunks not allowed by `options` along with any
+/// data after the `IEND` chunk.
+///
+/// Critical chunks and the ancillary chunks defining the image pixels are
+/// never removed, so the stripped file decodes to the same pixels.
+pub fn strip(png: &mut Png, options: &StripOptions) -> Result<StripReport> {
+    let bytes_after_iend = png.remove_data_after_iend();
+    let chunk_types: BTreeSet<String> = png
+        .chunks()
+        .iter()
+        .filter(|c| !options.is_kept(c))
+        .map(|c| c.chunk_type().to_string())
+        .collect();
+    let mut removed = Vec::new();
+
+    for chunk_type in chunk_types {
+        while let Ok(chunk) = png.remove_chunk(chunk_type.as_str()) {
+            removed.push((chunk_type.clone(), chunk.length()));
+        }
+    }
+
+    Ok(StripReport {
+        removed,
+        bytes_after_iend,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::chunk_type::ChunkType;
+
+    fn chunk_from_strings(chunk_type: &str, data: &str) -> Chunk {
+        Chunk::new(
+            ChunkType::from_str(chunk_type).unwrap(),
+            data.as_bytes().to_vec(),
+        )
+    }
+
+    fn testing_png() -> Png {
+        Png::from_chunks(vec![
+            chunk_from_strings("IHDR", "header"),
+            chunk_from_strings("gAMA", "gamma"),
+            chunk_from_strings("pHYs", "physical"),
+            chunk_from_strings("eXIf", "exif"),
+            chunk_from_strings("tIME", "time"),
+            chunk_from_strings("PLTE", "palette"),
+            chunk_from_strings("tRNS", "alpha"),
+            chunk_from_strings("IDAT", "data"),
+            chunk_from_strings("tEXt", "Comment\0Hello"),
+            chunk_from_strings("ruSt", "secret"),
+            chunk_from_strings("IEND", ""),
+            chunk_from_strings("ruSt", "after end"),
+        ])
+    }
+
+    fn types(png: &Png) -> Vec<String> {
+        png.chunks()
+            .iter()
+            .map(|c| c.chunk_type().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_strip_default() {
+        let mut png = testing_png();
+        let report = strip(&mut png, &StripOptions::default()).unwrap();
+
+        assert_eq!(
+            types(&png),
+            vec!["IHDR", "gAMA", "PLTE", "tRNS", "IDAT", "IEND"]
+        );
+        assert_eq!(
+            report,
+            StripReport {
+                removed: vec![
+                    (String::from("eXIf"), 4),
+                    (String::from("pHYs"), 8),
+                    (String::from("ruSt"), 6),
+                    (String::from("tEXt"), 13),
+                    (String::from("tIME"), 4),
+                ],
+                bytes_after_iend: 21,
+            }
+        );
+    }
+
+    #[test]
+    fn test_strip_keeping_metadata() {
+        let mut png = testing_png();
+        let options = StripOptions {
+            keep: vec![String::from("eXIf"), String::from("tEXt")],
+        };
+
+        strip(&mut png, &options).unwrap();
+
+        assert_eq!(
+            types(&png),
+            vec!["IHDR", "eXIf", "PLTE", "tRNS", "IDAT", "tEXt", "IEND"]
+        );
+    }
+
+    #[test]
+    fn test_strip_everything() {
+        let mut png = testing_png();
+        let options = StripOptions { keep: vec![] };
+
+        strip(&mut png, &options).unwrap();
+
+        assert_eq!(types(&png), vec!["IHDR", "PLTE", "tRNS", "IDAT", "IEND"]);
+    }
+}
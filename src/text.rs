@@ -0,0 +1,372 @@
+use std::convert::TryFrom;
+use std::fmt::{self, Display};
+use std::io::{Read, Write};
+use std::str::FromStr;
+
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::error::PngMeError;
+use crate::Result;
+
+/// Chunk types holding textual data
+pub const TEXT_CHUNK_TYPES: [&str; 3] = ["tEXt", "zTXt", "iTXt"];
+
+/// Textual data stored in a `tEXt` chunk, both the keyword and the text
+/// are Latin-1 encoded.
+///
+/// Reference: http://www.libpng.org/pub/png/spec/1.2/PNG-Chunks.html#C.tEXt
+#[derive(Debug, Clone, PartialEq)]
+pub struct Text {
+    pub keyword: String,
+    pub text: String,
+}
+
+/// Compressed textual data stored in a `zTXt` chunk
+///
+/// Reference: http://www.libpng.org/pub/png/spec/1.2/PNG-Chunks.html#C.zTXt
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompressedText {
+    pub keyword: String,
+    pub text: String,
+}
+
+/// International textual data stored in an `iTXt` chunk, the text is UTF-8
+/// encoded and can be optionally compressed
+///
+/// Reference: http://www.libpng.org/pub/png/spec/1.2/PNG-Chunks.html#C.iTXt
+#[derive(Debug, Clone, PartialEq)]
+pub struct InternationalText {
+    pub keyword: String,
+    pub compressed: bool,
+    pub language_tag: String,
+    pub translated_keyword: String,
+    pub text: String,
+}
+
+impl Text {
+    pub fn new(keyword: &str, text: &str) -> Self {
+        Text {
+            keyword: keyword.to_string(),
+            text: text.to_string(),
+        }
+    }
+
+    pub fn to_chunk(&self) -> Result<Chunk> {
+        let mut data = encode_keyword(&self.keyword)?;
+
+        data.extend(encode_latin1(&self.text)?);
+
+        Ok(Chunk::new(ChunkType::from_str("tEXt")?, data))
+    }
+}
+
+impl CompressedText {
+    pub fn new(keyword: &str, text: &str) -> Self {
+        CompressedText {
+            keyword: keyword.to_string(),
+            text: text.to_string(),
+        }
+    }
+
+    pub fn to_chunk(&self) -> Result<Chunk> {
+        let mut data = encode_keyword(&self.keyword)?;
+
+        // Compression method 0 is the only one defined, zlib deflate/inflate
+        data.push(0);
+        data.extend(compress(&encode_latin1(&self.text)?)?);
+
+        Ok(Chunk::new(ChunkType::from_str("zTXt")?, data))
+    }
+}
+
+impl InternationalText {
+    pub fn new(keyword: &str, text: &str) -> Self {
+        InternationalText {
+            keyword: keyword.to_string(),
+            compressed: false,
+            language_tag: String::new(),
+            translated_keyword: String::new(),
+            text: text.to_string(),
+        }
+    }
+
+    pub fn to_chunk(&self) -> Result<Chunk> {
+        let mut data = encode_keyword(&self.keyword)?;
+
+        data.push(self.compressed as u8);
+        data.push(0);
+        data.extend(self.language_tag.as_bytes());
+        data.push(0);
+        data.extend(self.translated_keyword.as_bytes());
+        data.push(0);
+
+        if self.compressed {
+            data.extend(compress(self.text.as_bytes())?);
+        } else {
+            data.extend(self.text.as_bytes());
+        }
+
+        Ok(Chunk::new(ChunkType::from_str("iTXt")?, data))
+    }
+}
+
+impl TryFrom<&Chunk> for Text {
+    type Error = crate::Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        expect_type(chunk, "tEXt")?;
+
+        let (keyword, rest) = split_keyword(chunk.data())?;
+
+        Ok(Text {
+            keyword,
+            text: decode_latin1(rest),
+        })
+    }
+}
+
+impl TryFrom<&Chunk> for CompressedText {
+    type Error = crate::Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        expect_type(chunk, "zTXt")?;
+
+        let (keyword, rest) = split_keyword(chunk.data())?;
+
+        match rest.split_first() {
+            Some((0, compressed)) => Ok(CompressedText {
+                keyword,
+                text: decode_latin1(&decompress(compressed)?),
+            }),
+            _ => Err(invalid("zTXt has an unknown compression method")),
+        }
+    }
+}
+
+impl TryFrom<&Chunk> for InternationalText {
+    type Error = crate::Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        expect_type(chunk, "iTXt")?;
+
+        let (keyword, rest) = split_keyword(chunk.data())?;
+
+        if rest.len() < 2 {
+            return Err(invalid("iTXt is missing the compression fields"));
+        }
+
+        let compressed = match (rest[0], rest[1]) {
+            (0, _) => false,
+            (1, 0) => true,
+            _ => return Err(invalid("iTXt has an unknown compression method")),
+        };
+        let (language_tag, rest) = split_null(&rest[2..])?;
+        let (translated_keyword, text) = split_null(rest)?;
+        let text = if compressed {
+            decompress(text)?
+        } else {
+            text.to_vec()
+        };
+
+        Ok(InternationalText {
+            keyword,
+            compressed,
+            language_tag: String::from_utf8(language_tag.to_vec())?,
+            translated_keyword: String::from_utf8(translated_keyword.to_vec())?,
+            text: String::from_utf8(text)?,
+        })
+    }
+}
+
+impl Display for Text {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.keyword, self.text)
+    }
+}
+
+impl Display for CompressedText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.keyword, self.text)
+    }
+}
+
+impl Display for InternationalText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.language_tag.is_empty() {
+            write!(f, "{}: {}", self.keyword, self.text)
+        } else {
+            write!(f, "{} [{}]: {}", self.keyword, self.language_tag, self.text)
+        }
+    }
+}
+
+/// Checks wether the chunk holds textual data
+pub fn is_text_chunk(chunk: &Chunk) -> bool {
+    TEXT_CHUNK_TYPES
+        .iter()
+        .any(|t| chunk.chunk_type().bytes() == t.as_bytes())
+}
+
+/// Retrieves the keyword and the text of any of the textual chunks,
+/// `None` is returned for any other chunk type
+pub fn keyword_and_text(chunk: &Chunk) -> Option<Result<(String, String)>> {
+    match &chunk.chunk_type().bytes() {
+        b"tEXt" => Some(Text::try_from(chunk).map(|t| (t.keyword, t.text))),
+        b"zTXt" => Some(CompressedText::try_from(chunk).map(|t| (t.keyword, t.text))),
+        b"iTXt" => Some(InternationalText::try_from(chunk).map(|t| (t.keyword, t.text))),
+        _ => None,
+    }
+}
+
+/// Compresses `bytes` as a zlib stream
+pub fn compress(bytes: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+
+    encoder.write_all(bytes)?;
+
+    Ok(encoder.finish()?)
+}
+
+/// Decompresses a zlib stream
+pub fn decompress(bytes: &[u8]) -> Result<Vec<u8>> {
+    let mut decompressed = Vec::new();
+
+    ZlibDecoder::new(bytes).read_to_end(&mut decompressed)?;
+
+    Ok(decompressed)
+}
+
+/// Decompresses at most `limit` bytes of a zlib stream, the rest of the
+/// stream is ignored
+pub fn decompress_limited(bytes: &[u8], limit: usize) -> Result<Vec<u8>> {
+    let mut decompressed = Vec::new();
+
+    ZlibDecoder::new(bytes)
+        .take(limit as u64)
+        .read_to_end(&mut decompressed)?;
+
+    Ok(decompressed)
+}
+
+fn expect_type(chunk: &Chunk, chunk_type: &str) -> Result<()> {
+    if chunk.chunk_type().bytes() != chunk_type.as_bytes() {
+        return Err(Box::new(PngMeError::InvalidChunkData(format!(
+            "expected a {} chunk, found {}",
+            chunk_type,
+            chunk.chunk_type()
+        ))));
+    }
+
+    Ok(())
+}
+
+/// Splits the null terminated keyword from the rest of the data, keywords
+/// must be between 1 and 79 bytes long
+pub(crate) fn split_keyword(data: &[u8]) -> Result<(String, &[u8])> {
+    let (keyword, rest) = split_null(data)?;
+
+    if keyword.is_empty() || keyword.len() > 79 {
+        return Err(invalid("keywords must be 1-79 bytes long"));
+    }
+
+    Ok((decode_latin1(keyword), rest))
+}
+
+fn split_null(data: &[u8]) -> Result<(&[u8], &[u8])> {
+    match data.iter().position(|b| *b == 0) {
+        Some(index) => Ok((&data[..index], &data[index + 1..])),
+        None => Err(invalid("missing null separator")),
+    }
+}
+
+pub(crate) fn encode_keyword(keyword: &str) -> Result<Vec<u8>> {
+    let mut bytes = encode_latin1(keyword)?;
+
+    if bytes.is_empty() || bytes.len() > 79 || bytes.contains(&0) {
+        return Err(invalid("keywords must be 1-79 bytes long"));
+    }
+
+    bytes.push(0);
+
+    Ok(bytes)
+}
+
+fn decode_latin1(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| *b as char).collect()
+}
+
+fn encode_latin1(text: &str) -> Result<Vec<u8>> {
+    text.chars()
+        .map(|c| {
+            u8::try_from(c as u32)
+                .map_err(|_| invalid(&format!("{:?} can't be encoded as Latin-1", c)))
+        })
+        .collect()
+}
+
+fn invalid(reason: &str) -> crate::Error {
+    Box::new(PngMeError::InvalidChunkData(reason.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_round_trip() {
+        let chunk = Text::new("Comment", "Caf\u{e9}").to_chunk().unwrap();
+        let text = Text::try_from(&chunk).unwrap();
+
+        assert_eq!(chunk.data(), b"Comment\0Caf\xe9");
+        assert_eq!(text, Text::new("Comment", "Caf\u{e9}"));
+    }
+
+    #[test]
+    fn test_compressed_text_round_trip() {
+        let expected = CompressedText::new("Description", "A long description");
+        let chunk = expected.to_chunk().unwrap();
+
+        assert_eq!(CompressedText::try_from(&chunk).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_international_text_round_trip() {
+        let mut expected =
+            InternationalText::new("Title", "\u{3053}\u{3093}\u{306b}\u{3061}\u{306f}");
+        expected.language_tag = String::from("ja");
+        expected.compressed = true;
+
+        let chunk = expected.to_chunk().unwrap();
+
+        assert_eq!(InternationalText::try_from(&chunk).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_keyword_and_text() {
+        let chunk = InternationalText::new("Author", "Esteban")
+            .to_chunk()
+            .unwrap();
+        let (keyword, text) = keyword_and_text(&chunk).unwrap().unwrap();
+
+        assert!(is_text_chunk(&chunk));
+        assert_eq!(keyword, "Author");
+        assert_eq!(text, "Esteban");
+    }
+
+    #[test]
+    fn test_invalid_keyword() {
+        assert!(Text::new("", "text").to_chunk().is_err());
+        assert!(Text::new(&"k".repeat(80), "text").to_chunk().is_err());
+    }
+
+    #[test]
+    fn test_text_missing_separator() {
+        let chunk = Chunk::new(ChunkType::from_str("tEXt").unwrap(), b"Comment".to_vec());
+
+        assert!(Text::try_from(&chunk).is_err());
+    }
+}
@@ -0,0 +1,707 @@
+use std::env;
+use std::fs::{self, DirBuilder, OpenOptions};
+use std::io::{self, ErrorKind, Write};
+#[cfg(unix)]
+use std::os::unix::fs::DirBuilderExt;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::crossterm::execute;
+use ratatui::crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+
+use pngme::chunk::{Chunk, ChunkRef};
+use pngme::chunk_type::ChunkType;
+use pngme::parse::ParseOptions;
+use pngme::png::Png;
+
+use crate::Result;
+
+/// Bytes shown on each line of the hex pane
+const HEX_LINE_LENGTH: usize = 16;
+
+/// Lines scrolled by `PageUp` and `PageDown` in the data pane
+const PAGE_LENGTH: usize = 16;
+
+const HELP: &str =
+    "↑↓ select  J/K move  d delete  x export  e edit  t hex/text  PgUp/PgDn scroll  s save  q quit";
+
+/// How the data of the selected chunk is shown
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DataView {
+    Hex,
+    Text,
+}
+
+/// What the event loop does after a key is handled
+#[derive(Debug, PartialEq)]
+enum Action {
+    Continue,
+    /// Opens the data of the selected chunk in `$EDITOR`
+    Edit,
+    Quit,
+}
+
+/// State of the chunk browser, the file is only written when saved
+struct App {
+    path: PathBuf,
+    backup: Option<String>,
+    png: Png,
+    /// Whether the stored CRC of each chunk is valid, computed once as
+    /// image data chunks can be large
+    valid_crcs: Vec<bool>,
+    selected: usize,
+    data_scroll: usize,
+    data_view: DataView,
+    modified: bool,
+    /// Set once `q` is pressed with unsaved changes, a second `q` quits
+    quit_pending: bool,
+    status: String,
+}
+
+impl App {
+    fn new(path: PathBuf, png: Png, backup: Option<String>) -> Self {
+        let status = match png.diagnostics().len() {
+            0 => String::new(),
+            n => format!(
+                "Problems found while reading the file: {}, see `pngme print`",
+                n
+            ),
+        };
+
+        App {
+            path,
+            backup,
+            valid_crcs: png.chunks().iter().map(crc_is_valid).collect(),
+            png,
+            selected: 0,
+            data_scroll: 0,
+            data_view: DataView::Hex,
+            modified: false,
+            quit_pending: false,
+            status,
+        }
+    }
+
+    fn selected_chunk(&self) -> Option<&Chunk> {
+        self.png.chunks().get(self.selected)
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Action {
+        let quit_pending = std::mem::replace(&mut self.quit_pending, false);
+
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return Action::Quit;
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc if self.modified && !quit_pending => {
+                self.quit_pending = true;
+                self.status = String::from("Unsaved changes, press q again to quit or s to save");
+            }
+            KeyCode::Char('q') | KeyCode::Esc => return Action::Quit,
+            KeyCode::Down | KeyCode::Char('j') => self.select(self.selected + 1),
+            KeyCode::Up | KeyCode::Char('k') => self.select(self.selected.saturating_sub(1)),
+            KeyCode::Home | KeyCode::Char('g') => self.select(0),
+            KeyCode::End | KeyCode::Char('G') => self.select(usize::MAX),
+            KeyCode::Char('J') => self.move_selected(true),
+            KeyCode::Char('K') => self.move_selected(false),
+            KeyCode::PageDown => self.scroll_data(PAGE_LENGTH as isize),
+            KeyCode::PageUp => self.scroll_data(-(PAGE_LENGTH as isize)),
+            KeyCode::Char('t') => {
+                self.data_view = match self.data_view {
+                    DataView::Hex => DataView::Text,
+                    DataView::Text => DataView::Hex,
+                };
+                self.data_scroll = 0;
+            }
+            KeyCode::Char('d') | KeyCode::Delete => self.delete_selected(),
+            KeyCode::Char('x') => {
+                let result = self.export_selected();
+
+                self.report(result);
+            }
+            KeyCode::Char('e') if self.selected_chunk().is_some() => return Action::Edit,
+            KeyCode::Char('s') => {
+                let result = self.save();
+
+                self.report(result);
+            }
+            _ => {}
+        }
+
+        Action::Continue
+    }
+
+    fn select(&mut self, index: usize) {
+        let last = self.png.chunks().len().saturating_sub(1);
+
+        self.selected = index.min(last);
+        self.data_scroll = 0;
+    }
+
+    fn scroll_data(&mut self, lines: isize) {
+        let last = self.data_lines().saturating_sub(1);
+        let scroll = self.data_scroll as isize + lines;
+
+        self.data_scroll = (scroll.max(0) as usize).min(last);
+    }
+
+    /// Number of lines the data of the selected chunk takes in the data pane
+    fn data_lines(&self) -> usize {
+        let data = self.selected_chunk().map(Chunk::data).unwrap_or(&[]);
+
+        match self.data_view {
+            DataView::Hex => data.len().div_ceil(HEX_LINE_LENGTH),
+            DataView::Text => String::from_utf8_lossy(data).lines().count(),
+        }
+    }
+
+    /// Swaps the selected chunk with the next one when `down` is set or the
+    /// previous one otherwise, the chunk stays selected
+    fn move_selected(&mut self, down: bool) {
+        let target = if down {
+            self.selected + 1
+        } else {
+            match self.selected.checked_sub(1) {
+                Some(target) => target,
+                None => return,
+            }
+        };
+
+        if target >= self.png.chunks().len() {
+            return;
+        }
+
+        // Both positions exist, the chunk can't fail to be moved
+        let chunk = self.png.remove_chunk_at(self.selected).unwrap();
+
+        self.png.insert_chunk(target, chunk).unwrap();
+        self.valid_crcs.swap(self.selected, target);
+        self.selected = target;
+        self.modified = true;
+        self.status = format!("Moved chunk to position {}", target);
+    }
+
+    fn delete_selected(&mut self) {
+        if let Ok(chunk) = self.png.remove_chunk_at(self.selected) {
+            self.valid_crcs.remove(self.selected);
+            self.status = format!("Deleted {} ({} bytes)", chunk.chunk_type(), chunk.length());
+            self.modified = true;
+            self.select(self.selected);
+        }
+    }
+
+    /// Writes the data of the selected chunk next to the file, as
+    /// `<file stem>.<index>.<chunk type>.bin`
+    fn export_selected(&self) -> Result<String> {
+        let chunk = self.selected_chunk().ok_or("There is no chunk to export")?;
+        let stem = self
+            .path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let path = self.path.with_file_name(format!(
+            "{}.{}.{}.bin",
+            stem,
+            self.selected,
+            chunk.chunk_type()
+        ));
+
+        fs::write(&path, chunk.data())?;
+
+        Ok(format!(
+            "Exported {} bytes to {}",
+            chunk.length(),
+            path.display()
+        ))
+    }
+
+    /// Replaces the data of the selected chunk, its CRC is computed again
+    fn replace_selected_data(&mut self, data: Vec<u8>) -> Result<String> {
+        let chunk = self.selected_chunk().ok_or("There is no chunk to edit")?;
+
+        if chunk.data() == data.as_slice() {
+            return Ok(String::from("The chunk data is unchanged"));
+        }
+
+        let chunk = Chunk::new(chunk.chunk_type().clone(), data);
+        let status = format!("Updated {} ({} bytes)", chunk.chunk_type(), chunk.length());
+
+        self.png.replace_chunk_at(self.selected, chunk)?;
+        self.valid_crcs[self.selected] = true;
+        self.modified = true;
+        self.scroll_data(0);
+
+        Ok(status)
+    }
+
+    fn save(&mut self) -> Result<String> {
+        match &self.backup {
+            Some(suffix) => self
+                .png
+                .write_file_with_backup(self.path.clone(), suffix.as_str())?,
+            None => self.png.write_file(self.path.clone())?,
+        }
+
+        self.modified = false;
+
+        Ok(format!("Saved {}", self.path.display()))
+    }
+
+    fn report(&mut self, result: Result<String>) {
+        self.status = match result {
+            Ok(status) => status,
+            Err(e) => format!("Error: {}", e),
+        };
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        let [main, status] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+        let [list, data] =
+            Layout::horizontal([Constraint::Length(52), Constraint::Min(1)]).areas(main);
+
+        self.render_list(frame, list);
+        self.render_data(frame, data);
+
+        let status_line = if self.status.is_empty() {
+            HELP
+        } else {
+            self.status.as_str()
+        };
+
+        frame.render_widget(Paragraph::new(status_line).reversed(), status);
+    }
+
+    fn render_list(&self, frame: &mut Frame, area: Rect) {
+        let items: Vec<ListItem> = self
+            .png
+            .chunks()
+            .iter()
+            .enumerate()
+            .map(|(index, chunk)| {
+                let chunk_type = chunk.chunk_type();
+                let crc = if self.valid_crcs[index] {
+                    Span::raw("CRC ok").green()
+                } else {
+                    Span::raw("CRC BAD").red().bold()
+                };
+
+                ListItem::new(Line::from(vec![
+                    Span::raw(format!(
+                        "{:>3} {} {:>9}  {:<4} {:<4} {:<6} ",
+                        index,
+                        chunk_type,
+                        chunk.length(),
+                        if chunk_type.is_critical() {
+                            "crit"
+                        } else {
+                            "anc"
+                        },
+                        if chunk_type.is_public() {
+                            "pub"
+                        } else {
+                            "priv"
+                        },
+                        if chunk_type.is_safe_to_copy() {
+                            "safe"
+                        } else {
+                            "unsafe"
+                        },
+                    )),
+                    crc,
+                ]))
+            })
+            .collect();
+        let title = format!(
+            " {}{} ",
+            self.path.display(),
+            if self.modified { " [modified]" } else { "" }
+        );
+        let list = List::new(items)
+            .block(Block::bordered().title(title))
+            .highlight_style(Style::new().reversed());
+        let mut state = ListState::default().with_selected(Some(self.selected));
+
+        frame.render_stateful_widget(list, area, &mut state);
+    }
+
+    fn render_data(&self, frame: &mut Frame, area: Rect) {
+        let chunk = match self.selected_chunk() {
+            Some(chunk) => chunk,
+            None => {
+                frame.render_widget(Block::bordered().title(" No chunks "), area);
+                return;
+            }
+        };
+        let title = format!(
+            " {} · {} bytes · {} · {} ",
+            chunk.chunk_type(),
+            chunk.length(),
+            type_properties(chunk.chunk_type()),
+            match self.data_view {
+                DataView::Hex => "hex",
+                DataView::Text => "text",
+            }
+        );
+        let block = Block::bordered().title(title);
+
+        match self.data_view {
+            DataView::Hex => {
+                // Only the visible lines are formatted, image data can take
+                // thousands of lines
+                let height = block.inner(area).height as usize;
+                let lines: Vec<Line> = chunk
+                    .data()
+                    .chunks(HEX_LINE_LENGTH)
+                    .enumerate()
+                    .skip(self.data_scroll)
+                    .take(height)
+                    .map(|(line, bytes)| Line::raw(hex_line(line * HEX_LINE_LENGTH, bytes)))
+                    .collect();
+
+                frame.render_widget(Paragraph::new(lines).block(block), area);
+            }
+            DataView::Text => {
+                let text = String::from_utf8_lossy(chunk.data()).to_string();
+                let paragraph = Paragraph::new(text)
+                    .block(block)
+                    .wrap(Wrap { trim: false })
+                    .scroll((self.data_scroll.min(u16::MAX as usize) as u16, 0));
+
+                frame.render_widget(paragraph, area);
+            }
+        }
+    }
+}
+
+/// Opens the PNG file in the chunk browser until it is closed with `q`
+pub fn run(path: &Path, backup: Option<String>) -> Result<()> {
+    let png = Png::from_file_with_options(path.to_path_buf(), &ParseOptions::lenient())?;
+    let mut app = App::new(path.to_path_buf(), png, backup);
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, &mut app);
+
+    ratatui::restore();
+
+    result
+}
+
+fn event_loop(terminal: &mut DefaultTerminal, app: &mut App) -> Result<()> {
+    loop {
+        terminal.draw(|frame| app.render(frame))?;
+
+        let key = match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => key,
+            _ => continue,
+        };
+
+        match app.handle_key(key) {
+            Action::Continue => {}
+            Action::Edit => {
+                let data = app.selected_chunk().map(Chunk::data).unwrap_or(&[]);
+                let result =
+                    edit_in_editor(terminal, data).and_then(|data| app.replace_selected_data(data));
+
+                app.report(result);
+            }
+            Action::Quit => return Ok(()),
+        }
+    }
+}
+
+/// Writes the data into a temporary file, opens it in `$EDITOR` and reads
+/// it back once the editor exits. The terminal is handed over to the editor
+/// while it runs.
+fn edit_in_editor(terminal: &mut DefaultTerminal, data: &[u8]) -> Result<Vec<u8>> {
+    let dir = create_private_dir()?;
+    let path = dir.join("chunk.bin");
+    let editor = env::var("EDITOR").unwrap_or_else(|_| String::from("vi"));
+    // `$EDITOR` may hold arguments, such as `code --wait`
+    let mut command = editor.split_whitespace();
+    let program = command.next().unwrap_or("vi");
+    let written = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(data));
+
+    if let Err(e) = written {
+        let _ = fs::remove_dir_all(&dir);
+        return Err(e.into());
+    }
+
+    disable_raw_mode()?;
+    execute!(io::stdout(), LeaveAlternateScreen)?;
+
+    let status = Command::new(program).args(command).arg(&path).status();
+
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen)?;
+    terminal.clear()?;
+
+    let edited = match status {
+        Ok(status) if status.success() => fs::read(&path).map_err(Into::into),
+        Ok(status) => Err(format!("{} exited with {}", program, status).into()),
+        Err(e) => Err(format!("Unable to run {}: {}", program, e).into()),
+    };
+
+    // The edited data was already read, the editor may have removed the file
+    let _ = fs::remove_dir_all(&dir);
+
+    edited
+}
+
+/// Creates a directory in the temporary directory only the current user can
+/// access, so the file handed to the editor can't be replaced or read by
+/// other users
+fn create_private_dir() -> Result<PathBuf> {
+    let mut builder = DirBuilder::new();
+
+    #[cfg(unix)]
+    builder.mode(0o700);
+
+    for attempt in 0..100 {
+        let dir = env::temp_dir().join(format!("pngme-{}-{}", process::id(), attempt));
+
+        match builder.create(&dir) {
+            Ok(()) => return Ok(dir),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    Err("Unable to create a temporary directory for the editor".into())
+}
+
+fn crc_is_valid(chunk: &Chunk) -> bool {
+    ChunkRef::from(chunk).verify_crc().is_ok()
+}
+
+/// Describes the properties encoded in the case of each chunk type letter
+fn type_properties(chunk_type: &ChunkType) -> String {
+    let mut properties = vec![
+        if chunk_type.is_critical() {
+            "critical"
+        } else {
+            "ancillary"
+        },
+        if chunk_type.is_public() {
+            "public"
+        } else {
+            "private"
+        },
+        if chunk_type.is_safe_to_copy() {
+            "safe to copy"
+        } else {
+            "unsafe to copy"
+        },
+    ];
+
+    if !chunk_type.is_reserved_bit_valid() {
+        properties.push("reserved bit set");
+    }
+
+    properties.join(", ")
+}
+
+/// Formats a line of the hex pane as its offset, the bytes in hex and the
+/// printable bytes as ASCII
+fn hex_line(offset: usize, bytes: &[u8]) -> String {
+    let hex: Vec<String> = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    let ascii: String = bytes
+        .iter()
+        .map(|&byte| {
+            if byte.is_ascii_graphic() || byte == b' ' {
+                byte as char
+            } else {
+                '.'
+            }
+        })
+        .collect();
+
+    format!(
+        "{:08x}  {:<width$}  {}",
+        offset,
+        hex.join(" "),
+        ascii,
+        width = HEX_LINE_LENGTH * 3 - 1
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+    use std::str::FromStr;
+
+    fn testing_png() -> Png {
+        let chunks = ["IHDR", "tEXt", "IDAT", "IEND"]
+            .iter()
+            .map(|chunk_type| {
+                Chunk::new(
+                    ChunkType::from_str(chunk_type).unwrap(),
+                    chunk_type.as_bytes().to_vec(),
+                )
+            })
+            .collect();
+
+        Png::from_chunks(chunks)
+    }
+
+    fn testing_app(name: &str) -> App {
+        let path = env::temp_dir().join(format!("pngme-tui-{}-{}.png", process::id(), name));
+
+        App::new(path, testing_png(), None)
+    }
+
+    fn press(app: &mut App, code: KeyCode) -> Action {
+        app.handle_key(KeyEvent::from(code))
+    }
+
+    fn chunk_types(app: &App) -> Vec<String> {
+        app.png
+            .chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_move_and_delete() {
+        let mut app = testing_app("move");
+
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Char('J'));
+
+        assert_eq!(chunk_types(&app), ["IHDR", "IDAT", "tEXt", "IEND"]);
+        assert_eq!(app.selected, 2);
+
+        press(&mut app, KeyCode::Char('d'));
+
+        assert_eq!(chunk_types(&app), ["IHDR", "IDAT", "IEND"]);
+        assert_eq!(app.selected, 2);
+        assert!(app.modified);
+
+        press(&mut app, KeyCode::Char('G'));
+        press(&mut app, KeyCode::Char('J'));
+
+        assert_eq!(app.selected, 2);
+    }
+
+    #[test]
+    fn test_quit_asks_to_save_changes() {
+        let mut app = testing_app("quit");
+
+        press(&mut app, KeyCode::Char('d'));
+
+        assert_eq!(press(&mut app, KeyCode::Char('q')), Action::Continue);
+        assert_eq!(press(&mut app, KeyCode::Char('q')), Action::Quit);
+    }
+
+    #[test]
+    fn test_edit_export_and_save() {
+        let mut app = testing_app("save");
+
+        press(&mut app, KeyCode::Down);
+
+        assert_eq!(press(&mut app, KeyCode::Char('e')), Action::Edit);
+
+        app.replace_selected_data(b"Title\0Dice".to_vec()).unwrap();
+        press(&mut app, KeyCode::Char('x'));
+
+        let exported = app
+            .path
+            .with_file_name(format!("pngme-tui-{}-save.1.tEXt.bin", process::id()));
+
+        assert_eq!(fs::read(&exported).unwrap(), b"Title\0Dice");
+
+        press(&mut app, KeyCode::Char('s'));
+
+        let saved = Png::from_file(app.path.clone()).unwrap();
+
+        assert!(!app.modified);
+        assert_eq!(saved.chunks()[1].data(), b"Title\0Dice");
+        assert!(crc_is_valid(&saved.chunks()[1]));
+
+        fs::remove_file(exported).unwrap();
+        fs::remove_file(&app.path).unwrap();
+    }
+
+    #[test]
+    fn test_render_shows_type_bits_and_crc_status() {
+        let mut bytes = testing_png().as_bytes();
+        // Last CRC byte of the `tEXt` chunk
+        bytes[8 + 16 + 15] ^= 0xff;
+
+        let png = Png::parse(&bytes, &ParseOptions::lenient()).unwrap();
+        let mut app = App::new(PathBuf::from("damaged.png"), png, None);
+        let mut terminal = Terminal::new(TestBackend::new(120, 10)).unwrap();
+
+        press(&mut app, KeyCode::Down);
+        terminal.draw(|frame| app.render(frame)).unwrap();
+
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+
+        assert!(screen.contains("IHDR         4  crit pub  unsafe CRC ok"));
+        assert!(screen.contains("tEXt         4  anc  pub  safe   CRC BAD"));
+        assert!(screen.contains("ancillary, public, safe to copy"));
+        assert!(screen.contains("00000000  74 45 58 74"));
+
+        app.replace_selected_data(b"Title\0Dice".to_vec()).unwrap();
+        terminal.draw(|frame| app.render(frame)).unwrap();
+
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+
+        assert!(screen.contains("tEXt        10  anc  pub  safe   CRC ok"));
+    }
+
+    #[test]
+    fn test_create_private_dir() {
+        let first = create_private_dir().unwrap();
+        let second = create_private_dir().unwrap();
+
+        assert_ne!(first, second);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let mode = fs::metadata(&first).unwrap().permissions().mode();
+
+            assert_eq!(mode & 0o777, 0o700);
+        }
+
+        fs::remove_dir(first).unwrap();
+        fs::remove_dir(second).unwrap();
+    }
+
+    #[test]
+    fn test_hex_line() {
+        assert_eq!(
+            hex_line(16, b"IHDR\x00\x01"),
+            format!("00000010  {:<47}  IHDR..", "49 48 44 52 00 01")
+        );
+    }
+}
@@ -0,0 +1,277 @@
+use alloc::{boxed::Box, vec::Vec};
+use core::convert::{TryFrom, TryInto};
+#[cfg(feature = "std")]
+use std::fs::File;
+#[cfg(feature = "std")]
+use std::path::Path;
+
+#[cfg(feature = "std")]
+use memmap2::Mmap;
+
+use crate::chunk::ChunkRef;
+use crate::error::PngMeError;
+use crate::parse::{self, Diagnostic, ParseOptions};
+use crate::png::{Png, PNG_FILE_SIGNATURE};
+use crate::Error;
+use crate::Result;
+
+/// Which chunks have their CRC verified when a file is scanned, see
+/// `PngView::scan`
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum VerifyCrc {
+    /// Every chunk is verified while the file is scanned
+    #[default]
+    All,
+    /// Only the chunks read through `PngView::chunk_by_type` or copied by
+    /// `PngView::to_png` are verified, large chunks such as `IDAT` which are
+    /// never read are skipped
+    Accessed,
+    /// No chunk is verified
+    None,
+}
+
+/// A PNG file borrowed from its bytes, such as a memory-mapped file. Chunks
+/// are `ChunkRef` slices of those bytes and are only copied when the file
+/// is converted into a `Png` to be edited.
+#[derive(Debug, Clone)]
+pub struct PngView<'a> {
+    chunks: Vec<ChunkRef<'a>>,
+    /// Bytes found after the `IEND` chunk which are not chunks
+    trailing_data: &'a [u8],
+    /// Problems found while parsing the file, see `PngView::parse`
+    diagnostics: Vec<Diagnostic>,
+    verify_crc: VerifyCrc,
+}
+
+impl<'a> PngView<'a> {
+    /// Parses a PNG file with the provided `ParseOptions`, problems found
+    /// in lenient and forensic modes are available in `PngView::diagnostics`
+    pub fn parse(bytes: &'a [u8], options: &ParseOptions) -> Result<Self> {
+        let parsed = parse::parse(bytes, options)?;
+
+        Ok(Self {
+            chunks: parsed.chunks,
+            trailing_data: parsed.trailing_data,
+            diagnostics: parsed.diagnostics,
+            verify_crc: VerifyCrc::All,
+        })
+    }
+
+    /// Scans a PNG file reading only the length and type of each chunk and
+    /// skipping over its data, CRCs are verified as `verify_crc` requires
+    pub fn scan(bytes: &'a [u8], verify_crc: VerifyCrc) -> Result<Self> {
+        // First 8 bytes from PNG file must be exact
+        // the same as `PNG_FILE_SIGNATURE`
+        if !bytes.starts_with(&PNG_FILE_SIGNATURE) {
+            return Err(Box::new(PngMeError::InvalidPNGFileHeader));
+        }
+
+        let mut chunks: Vec<ChunkRef> = Vec::new();
+        let mut trailing_data: &[u8] = &[];
+        let mut cursor: usize = 8;
+        let mut seen_iend = false;
+
+        // We skip the first 8 bytes which represents
+        // the PNG file header and extract chunks
+        while cursor < bytes.len() {
+            // The first 4 bytes from a Chunk represents
+            // the length of the chunk
+            let chunk = match bytes.get(cursor..cursor + 4) {
+                Some(length) => {
+                    let length = u32::from_be_bytes(length.try_into()?) as usize;
+
+                    // The length can overflow `usize` on 32-bit targets
+                    length
+                        .checked_add(12)
+                        .and_then(|length| cursor.checked_add(length))
+                        .and_then(|end| bytes.get(cursor..end))
+                        .ok_or(PngMeError::TruncatedChunk)
+                        .and_then(|chunk| ChunkRef::read(chunk, verify_crc == VerifyCrc::All))
+                }
+                None => Err(PngMeError::TruncatedChunk),
+            };
+
+            match chunk {
+                Ok(chunk) => {
+                    // "Moves the cursor" the number of bytes ahead
+                    cursor += chunk.length() as usize + 12;
+                    seen_iend |= &chunk.chunk_type().bytes() == b"IEND";
+                    // Pushes the chunk to the `chunks` Vectors
+                    chunks.push(chunk);
+                }
+                Err(_) if seen_iend => {
+                    // Anything after `IEND` which is not a chunk is kept
+                    // as it is
+                    trailing_data = &bytes[cursor..];
+                    break;
+                }
+                Err(e) => return Err(Box::new(e)),
+            }
+        }
+
+        Ok(PngView {
+            chunks,
+            trailing_data,
+            diagnostics: Vec::new(),
+            verify_crc,
+        })
+    }
+
+    /// Retrieves every chunk, their CRC is not verified when the file was
+    /// scanned with `VerifyCrc::Accessed`, see `ChunkRef::verify_crc`
+    pub fn chunks(&self) -> &[ChunkRef<'a>] {
+        self.chunks.as_slice()
+    }
+
+    /// Retrieves the first chunk with the provided type, verifying its CRC
+    /// when the file was scanned with `VerifyCrc::Accessed`
+    pub fn chunk_by_type(&self, chunk_type: &str) -> Result<Option<&ChunkRef<'a>>> {
+        let chunk = self
+            .chunks
+            .iter()
+            .find(|c| c.chunk_type().bytes() == chunk_type.as_bytes());
+
+        if let Some(chunk) = chunk {
+            self.verify_accessed(chunk)?;
+        }
+
+        Ok(chunk)
+    }
+
+    /// Retrieves the bytes found after the `IEND` chunk which couldn't be
+    /// parsed as chunks
+    pub fn trailing_data(&self) -> &'a [u8] {
+        self.trailing_data
+    }
+
+    /// Retrieves the problems found while parsing the file
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        self.diagnostics.as_slice()
+    }
+
+    /// Copies every chunk into an owned `Png` which can be edited, chunks
+    /// are verified when the file was scanned with `VerifyCrc::Accessed`
+    pub fn to_png(&self) -> Result<Png> {
+        for chunk in self.chunks.iter() {
+            self.verify_accessed(chunk)?;
+        }
+
+        Ok(Png::from_parts(
+            self.chunks.iter().map(ChunkRef::to_chunk).collect(),
+            self.trailing_data.to_vec(),
+            self.diagnostics.clone(),
+        ))
+    }
+
+    fn verify_accessed(&self, chunk: &ChunkRef) -> Result<()> {
+        if self.verify_crc == VerifyCrc::Accessed {
+            chunk.verify_crc()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Scans the file verifying the CRC of every chunk
+impl<'a> TryFrom<&'a [u8]> for PngView<'a> {
+    type Error = Error;
+
+    fn try_from(value: &'a [u8]) -> Result<Self> {
+        PngView::scan(value, VerifyCrc::All)
+    }
+}
+
+/// Maps the file into memory so a `PngView` can read it without copying it.
+///
+/// The file must not be modified while it is mapped. Files written by this
+/// crate are replaced by renaming a new file over them (see `atomic::write`)
+/// so the mapped file is never changed in place.
+#[cfg(feature = "std")]
+pub fn map_file(path: &Path) -> Result<Mmap> {
+    let file = File::open(path)?;
+
+    // Safety: see the requirement above, a file truncated by another
+    // process while mapped would fault when read
+    Ok(unsafe { Mmap::map(&file)? })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::Chunk;
+    use crate::chunk_type::ChunkType;
+    use core::str::FromStr;
+
+    fn testing_bytes() -> Vec<u8> {
+        let chunks = ["IHDR", "tEXt", "IDAT", "IEND"]
+            .iter()
+            .map(|chunk_type| {
+                Chunk::new(
+                    ChunkType::from_str(chunk_type).unwrap(),
+                    chunk_type.as_bytes().to_vec(),
+                )
+            })
+            .collect();
+
+        Png::from_chunks(chunks).as_bytes()
+    }
+
+    #[test]
+    fn test_chunks_borrow_the_file_bytes() {
+        let bytes = testing_bytes();
+        let view = PngView::try_from(bytes.as_slice()).unwrap();
+        let text = view.chunk_by_type("tEXt").unwrap().unwrap();
+
+        assert_eq!(view.chunks().len(), 4);
+        assert_eq!(text.data(), b"tEXt");
+        assert!(bytes.as_ptr_range().contains(&text.data().as_ptr()));
+    }
+
+    #[test]
+    fn test_to_png() {
+        let mut bytes = testing_bytes();
+
+        bytes.extend(b"trailing");
+
+        let view = PngView::try_from(bytes.as_slice()).unwrap();
+        let png = view.to_png().unwrap();
+
+        assert_eq!(view.trailing_data(), b"trailing");
+        assert_eq!(png.as_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_parse_keeps_diagnostics() {
+        let mut bytes = testing_bytes();
+        // Last CRC byte of the `tEXt` chunk
+        bytes[8 + 16 + 15] ^= 0xff;
+
+        assert!(PngView::try_from(bytes.as_slice()).is_err());
+
+        let view = PngView::parse(&bytes, &ParseOptions::lenient()).unwrap();
+
+        assert_eq!(view.diagnostics().len(), 1);
+        assert_eq!(view.to_png().unwrap().diagnostics(), view.diagnostics());
+    }
+
+    #[test]
+    fn test_scan_verifies_accessed_chunks() {
+        let mut bytes = testing_bytes();
+        // Last CRC byte of the `IDAT` chunk
+        bytes[8 + 16 + 16 + 15] ^= 0xff;
+
+        assert!(PngView::scan(&bytes, VerifyCrc::All).is_err());
+
+        let view = PngView::scan(&bytes, VerifyCrc::Accessed).unwrap();
+
+        assert_eq!(view.chunks().len(), 4);
+        assert!(view.chunk_by_type("tEXt").unwrap().is_some());
+        assert!(view.chunk_by_type("IDAT").is_err());
+        assert!(view.to_png().is_err());
+
+        let view = PngView::scan(&bytes, VerifyCrc::None).unwrap();
+
+        assert!(view.chunk_by_type("IDAT").unwrap().is_some());
+        assert_eq!(view.to_png().unwrap().as_bytes(), bytes);
+    }
+}
@@ -0,0 +1,127 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::str::FromStr;
+
+use js_sys::{Array, Object, Reflect};
+use wasm_bindgen::prelude::*;
+
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::png::Png;
+use crate::view::PngView;
+
+/// Inserts the message into the PNG file in a chunk of the provided type
+/// and returns the new file
+#[wasm_bindgen]
+pub fn encode(bytes: &[u8], chunk_type: &str, message: &str) -> Result<Vec<u8>, JsError> {
+    let mut png = Png::try_from(bytes).map_err(js_error)?;
+    let chunk_type = ChunkType::from_str(chunk_type).map_err(js_error)?;
+
+    png.insert_message(Chunk::new(chunk_type, message.as_bytes().to_vec()))
+        .map_err(js_error)?;
+
+    Ok(png.as_bytes())
+}
+
+/// Retrieves the message stored in the first chunk of the provided type
+#[wasm_bindgen]
+pub fn decode(bytes: &[u8], chunk_type: &str) -> Result<String, JsError> {
+    let png = PngView::try_from(bytes).map_err(js_error)?;
+
+    match png.chunk_by_type(chunk_type).map_err(js_error)? {
+        Some(chunk) => Ok(String::from_utf8_lossy(chunk.data()).to_string()),
+        None => Err(JsError::new(&alloc::format!(
+            "Chunk type: {} not found",
+            chunk_type
+        ))),
+    }
+}
+
+/// Lists the chunks of the PNG file as `{ type, length, crc }` objects
+#[wasm_bindgen]
+pub fn list_chunks(bytes: &[u8]) -> Result<Array, JsError> {
+    let png = PngView::try_from(bytes).map_err(js_error)?;
+    let chunks = Array::new();
+
+    for chunk in png.chunks() {
+        let object = Object::new();
+
+        set(&object, "type", &chunk.chunk_type().to_string().into())?;
+        set(&object, "length", &chunk.length().into())?;
+        set(&object, "crc", &chunk.crc().into())?;
+        chunks.push(&object);
+    }
+
+    Ok(chunks)
+}
+
+/// Removes the first chunk of the provided type and returns the new file
+#[wasm_bindgen]
+pub fn remove(bytes: &[u8], chunk_type: &str) -> Result<Vec<u8>, JsError> {
+    let mut png = Png::try_from(bytes).map_err(js_error)?;
+
+    png.remove_chunk(chunk_type).map_err(js_error)?;
+
+    Ok(png.as_bytes())
+}
+
+fn set(object: &Object, key: &str, value: &JsValue) -> Result<(), JsError> {
+    Reflect::set(object, &key.into(), value)
+        .map(|_| ())
+        .map_err(|_| JsError::new("Unable to build the chunk object"))
+}
+
+fn js_error(error: impl core::fmt::Display) -> JsError {
+    JsError::new(&error.to_string())
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    fn testing_bytes() -> Vec<u8> {
+        let chunks = ["IHDR", "IDAT", "IEND"]
+            .iter()
+            .map(|chunk_type| Chunk::new(ChunkType::from_str(chunk_type).unwrap(), Vec::new()))
+            .collect();
+
+        Png::from_chunks(chunks).as_bytes()
+    }
+
+    #[wasm_bindgen_test]
+    fn test_encode_and_decode() {
+        let bytes = encode(&testing_bytes(), "ruSt", "hidden").unwrap();
+
+        assert_eq!(decode(&bytes, "ruSt").unwrap(), "hidden");
+        assert!(decode(&testing_bytes(), "ruSt").is_err());
+        assert!(encode(&testing_bytes(), "IDAT", "hidden").is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn test_list_chunks() {
+        let bytes = encode(&testing_bytes(), "ruSt", "hidden").unwrap();
+        let chunks = list_chunks(&bytes).unwrap();
+        let message = chunks.get(2);
+
+        assert_eq!(chunks.length(), 4);
+        assert_eq!(
+            Reflect::get(&message, &"type".into()).unwrap(),
+            JsValue::from("ruSt")
+        );
+        assert_eq!(
+            Reflect::get(&message, &"length".into()).unwrap(),
+            JsValue::from(6)
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn test_remove() {
+        let bytes = encode(&testing_bytes(), "ruSt", "hidden").unwrap();
+
+        assert_eq!(remove(&bytes, "ruSt").unwrap(), testing_bytes());
+        assert!(remove(&bytes, "teSt").is_err());
+        assert!(list_chunks(b"not a png").is_err());
+    }
+}
@@ -0,0 +1,550 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt::{self, Display};
+
+use quick_xml::escape::{escape, resolve_predefined_entity};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+use crate::chunk::Chunk;
+use crate::error::PngMeError;
+use crate::png::Png;
+use crate::text::InternationalText;
+use crate::Result;
+
+/// Keyword of the `iTXt` chunk holding the XMP packet
+pub const XMP_KEYWORD: &str = "XML:com.adobe.xmp";
+
+const RDF_NAMESPACE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+
+/// Namespaces which don't need to be provided when setting a property
+const KNOWN_NAMESPACES: [(&str, &str); 7] = [
+    ("dc", "http://purl.org/dc/elements/1.1/"),
+    ("xmp", "http://ns.adobe.com/xap/1.0/"),
+    ("xmpRights", "http://ns.adobe.com/xap/1.0/rights/"),
+    ("photoshop", "http://ns.adobe.com/photoshop/1.0/"),
+    ("tiff", "http://ns.adobe.com/tiff/1.0/"),
+    ("exif", "http://ns.adobe.com/exif/1.0/"),
+    ("rdf", RDF_NAMESPACE),
+];
+
+/// Properties holding a language alternative, an ordered or an unordered
+/// array instead of a simple value
+const ARRAY_PROPERTIES: [(&str, &str); 5] = [
+    ("dc:title", "rdf:Alt"),
+    ("dc:description", "rdf:Alt"),
+    ("dc:rights", "rdf:Alt"),
+    ("dc:creator", "rdf:Seq"),
+    ("dc:subject", "rdf:Bag"),
+];
+
+/// XMP packet stored in an `iTXt` chunk with the `XML:com.adobe.xmp`
+/// keyword. Properties are edited in place so the rest of the packet is
+/// left untouched.
+///
+/// Reference: https://github.com/adobe/XMP-Toolkit-SDK/blob/main/docs/XMPSpecificationPart3.pdf
+#[derive(Debug, Clone, PartialEq)]
+pub struct Xmp {
+    packet: String,
+}
+
+/// A property of a top level `rdf:Description`, either an element or an
+/// attribute of the description
+#[derive(Debug, Clone, PartialEq)]
+struct Property {
+    name: String,
+    /// Range of the whole element, or of the attribute value
+    range: (usize, usize),
+    is_attribute: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Description {
+    /// Position of the `>` or `/>` closing the start tag
+    start_tag_end: usize,
+    /// Position of the end tag, `None` for empty elements
+    end_tag: Option<usize>,
+}
+
+/// Properties, descriptions and namespace declarations found in a packet
+#[derive(Debug, Default)]
+struct Index {
+    properties: Vec<Property>,
+    descriptions: Vec<Description>,
+    namespaces: HashMap<String, String>,
+}
+
+impl Xmp {
+    /// Parses a packet, invalid XML is rejected
+    pub fn new(packet: &str) -> Result<Self> {
+        let xmp = Xmp {
+            packet: packet.to_string(),
+        };
+
+        if xmp.index()?.descriptions.is_empty() {
+            return Err(invalid("the packet doesn't have an rdf:Description"));
+        }
+
+        Ok(xmp)
+    }
+
+    /// Creates a packet without properties
+    pub fn empty() -> Self {
+        Xmp {
+            packet: format!(
+                concat!(
+                    "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n",
+                    "<x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n",
+                    " <rdf:RDF xmlns:rdf=\"{}\">\n",
+                    "  <rdf:Description rdf:about=\"\"/>\n",
+                    " </rdf:RDF>\n",
+                    "</x:xmpmeta>\n",
+                    "<?xpacket end=\"w\"?>"
+                ),
+                RDF_NAMESPACE
+            ),
+        }
+    }
+
+    /// Retrieves the XMP packet of the provided PNG file, if any
+    pub fn from_png(png: &Png) -> Result<Option<Self>> {
+        for chunk in png.chunks() {
+            if &chunk.chunk_type().bytes() == b"iTXt" {
+                let text = InternationalText::try_from(chunk)?;
+
+                if text.keyword == XMP_KEYWORD {
+                    return Xmp::new(&text.text).map(Some);
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Builds an uncompressed `iTXt` chunk holding the packet
+    pub fn to_chunk(&self) -> Result<Chunk> {
+        InternationalText::new(XMP_KEYWORD, &self.packet).to_chunk()
+    }
+
+    /// Stores the packet in the provided PNG file, replacing its current XMP
+    /// `iTXt` chunk if there is one
+    pub fn write_to(&self, png: &mut Png) -> Result<()> {
+        let chunk = self.to_chunk()?;
+        let position = png.chunks().iter().position(|chunk| {
+            &chunk.chunk_type().bytes() == b"iTXt"
+                && InternationalText::try_from(chunk)
+                    .map(|text| text.keyword == XMP_KEYWORD)
+                    .unwrap_or(false)
+        });
+
+        match position {
+            Some(index) => {
+                png.replace_chunk_at(index, chunk)?;
+            }
+            None => {
+                png.insert_chunk_ordered(chunk);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn packet(&self) -> &str {
+        &self.packet
+    }
+
+    /// Retrieves the value of a property such as `dc:title`, the items of
+    /// arrays are separated by `; `
+    pub fn get(&self, name: &str) -> Result<Option<String>> {
+        let index = self.index()?;
+
+        match index.properties.iter().find(|p| p.name == name) {
+            Some(property) => {
+                let raw = &self.packet[property.range.0..property.range.1];
+
+                if property.is_attribute {
+                    Ok(Some(unescape(raw)?))
+                } else {
+                    element_value(raw).map(Some)
+                }
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Sets the value of a property, `namespace` is required for prefixes
+    /// which aren't declared in the packet nor known. Array properties such
+    /// as `dc:creator` take items separated by `;`, language alternatives
+    /// such as `dc:title` hold the whole value as their `x-default` item.
+    pub fn set(&mut self, name: &str, value: &str, namespace: Option<&str>) -> Result<()> {
+        let element = match ARRAY_PROPERTIES.iter().find(|(n, _)| *n == name) {
+            Some((_, container)) => {
+                let items: String = match *container {
+                    // A single default item, `;` may appear in the value
+                    "rdf:Alt" => {
+                        format!("<rdf:li xml:lang=\"x-default\">{}</rdf:li>", escape(value))
+                    }
+                    _ => value
+                        .split(';')
+                        .map(|item| format!("<rdf:li>{}</rdf:li>", escape(item.trim())))
+                        .collect(),
+                };
+
+                format!(
+                    "<{name}><{container}>{items}</{container}></{name}>",
+                    name = name,
+                    container = container,
+                    items = items
+                )
+            }
+            None => format!("<{name}>{}</{name}>", escape(value), name = name),
+        };
+
+        self.set_element(name, &element, namespace)
+    }
+
+    /// Copies every property of `other` into the packet, replacing the
+    /// properties both packets have
+    pub fn merge(&mut self, other: &Xmp) -> Result<()> {
+        let index = other.index()?;
+
+        for property in index.properties.iter() {
+            let raw = &other.packet[property.range.0..property.range.1];
+            let namespace = prefix(&property.name).and_then(|p| index.namespaces.get(p));
+            let element = if property.is_attribute {
+                format!("<{name}>{}</{name}>", raw, name = property.name)
+            } else {
+                raw.to_string()
+            };
+
+            self.set_element(&property.name, &element, namespace.map(|n| n.as_str()))?;
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the property with the provided element, or appends it to
+    /// the first description declaring its namespace if needed
+    fn set_element(&mut self, name: &str, element: &str, namespace: Option<&str>) -> Result<()> {
+        let index = self.index()?;
+        let mut packet = self.packet.clone();
+
+        if let Some(property) = index.properties.iter().find(|p| p.name == name) {
+            let (start, end) = property.range;
+
+            if property.is_attribute {
+                // Attributes hold simple values, drop the attribute and
+                // append the element instead
+                let attribute_start = self.packet[..start]
+                    .rfind(name)
+                    .ok_or_else(|| invalid("attribute not found"))?;
+
+                packet.replace_range(attribute_start..end + 1, "");
+                self.packet = packet;
+
+                return self.set_element(name, element, namespace);
+            }
+
+            packet.replace_range(start..end, element);
+        } else {
+            let description = &index.descriptions[0];
+            let prefix = prefix(name)
+                .ok_or_else(|| invalid(&format!("{} must have a namespace prefix", name)))?;
+            let declaration = if index.namespaces.contains_key(prefix) {
+                String::new()
+            } else {
+                let uri = namespace
+                    .or_else(|| {
+                        KNOWN_NAMESPACES
+                            .iter()
+                            .find(|(p, _)| *p == prefix)
+                            .map(|(_, uri)| *uri)
+                    })
+                    .ok_or_else(|| {
+                        invalid(&format!("the namespace of {} must be provided", prefix))
+                    })?;
+
+                format!(" xmlns:{}=\"{}\"", prefix, escape(uri))
+            };
+
+            match description.end_tag {
+                Some(end_tag) => {
+                    packet.insert_str(end_tag, &format!(" {}\n  ", element));
+                    packet.insert_str(description.start_tag_end, &declaration);
+                }
+                None => {
+                    packet.replace_range(
+                        description.start_tag_end..description.start_tag_end + 2,
+                        &format!(">\n   {}\n  </rdf:Description>", element),
+                    );
+                    packet.insert_str(description.start_tag_end, &declaration);
+                }
+            }
+        }
+
+        *self = Xmp::new(&packet)?;
+
+        Ok(())
+    }
+
+    /// Walks the packet collecting the properties of the top level
+    /// descriptions, which also checks the packet is well formed
+    fn index(&self) -> Result<Index> {
+        let mut reader = Reader::from_str(&self.packet);
+        let mut index = Index::default();
+        // Names and start positions of the open elements
+        let mut stack: Vec<(String, usize)> = Vec::new();
+
+        loop {
+            let start = reader.buffer_position() as usize;
+            let event = reader.read_event().map_err(|e| invalid(&e.to_string()))?;
+            let end = reader.buffer_position() as usize;
+
+            match event {
+                Event::Start(ref tag) | Event::Empty(ref tag) => {
+                    let name = tag.name().as_ref().to_string();
+                    let is_empty = matches!(event, Event::Empty(_));
+                    let parent = stack.last().map(|(n, _)| n.as_str());
+                    let grandparent = stack.iter().rev().nth(1).map(|(n, _)| n.as_str());
+
+                    collect_namespaces(tag, &mut index.namespaces)?;
+
+                    if name == "rdf:Description" && parent == Some("rdf:RDF") {
+                        let tag_end = if is_empty { end - 2 } else { end - 1 };
+
+                        index.descriptions.push(Description {
+                            start_tag_end: tag_end,
+                            end_tag: None,
+                        });
+                        collect_attribute_properties(
+                            &self.packet[start..end],
+                            start,
+                            &mut index.properties,
+                        )?;
+                    } else if parent == Some("rdf:Description") && grandparent == Some("rdf:RDF") {
+                        index.properties.push(Property {
+                            name: name.clone(),
+                            range: (start, end),
+                            is_attribute: false,
+                        });
+                    }
+
+                    if !is_empty {
+                        stack.push((name, start));
+                    }
+                }
+                Event::End(_) => {
+                    let (name, _) = stack.pop().ok_or_else(|| invalid("unexpected end tag"))?;
+                    let parent = stack.last().map(|(n, _)| n.as_str());
+                    let grandparent = stack.iter().rev().nth(1).map(|(n, _)| n.as_str());
+
+                    if name == "rdf:Description" && parent == Some("rdf:RDF") {
+                        if let Some(description) = index.descriptions.last_mut() {
+                            description.end_tag = Some(start);
+                        }
+                    } else if parent == Some("rdf:Description") && grandparent == Some("rdf:RDF") {
+                        if let Some(property) = index.properties.last_mut() {
+                            property.range.1 = end;
+                        }
+                    }
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+
+        if let Some((name, _)) = stack.last() {
+            return Err(invalid(&format!("{} is never closed", name)));
+        }
+
+        Ok(index)
+    }
+}
+
+impl Display for Xmp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.packet)
+    }
+}
+
+fn prefix(name: &str) -> Option<&str> {
+    name.split_once(':').map(|(prefix, _)| prefix)
+}
+
+fn collect_namespaces(tag: &BytesStart, namespaces: &mut HashMap<String, String>) -> Result<()> {
+    for attribute in tag.attributes() {
+        let attribute = attribute.map_err(|e| invalid(&e.to_string()))?;
+        let key = attribute.key.as_ref().to_string();
+
+        if let Some(prefix) = key.strip_prefix("xmlns:") {
+            namespaces.insert(prefix.to_string(), unescape(&attribute.value)?);
+        }
+    }
+
+    Ok(())
+}
+
+/// Collects the attributes of a description holding properties, every
+/// attribute except namespace declarations and `rdf:` attributes
+fn collect_attribute_properties(
+    tag: &str,
+    offset: usize,
+    properties: &mut Vec<Property>,
+) -> Result<()> {
+    let mut rest = tag.find(char::is_whitespace).unwrap_or(0);
+
+    while let Some(equals) = tag[rest..].find('=') {
+        let name = tag[rest..rest + equals].trim();
+        let quote_start = rest + equals + 1 + tag[rest + equals + 1..].len()
+            - tag[rest + equals + 1..].trim_start().len();
+        let quote = tag[quote_start..]
+            .chars()
+            .next()
+            .ok_or_else(|| invalid("missing attribute value"))?;
+        let value_end = tag[quote_start + 1..]
+            .find(quote)
+            .map(|index| quote_start + 1 + index)
+            .ok_or_else(|| invalid("unterminated attribute value"))?;
+
+        if !name.starts_with("xmlns") && !name.starts_with("rdf:") && name.contains(':') {
+            properties.push(Property {
+                name: name.to_string(),
+                range: (offset + quote_start + 1, offset + value_end),
+                is_attribute: true,
+            });
+        }
+
+        rest = value_end + 1;
+    }
+
+    Ok(())
+}
+
+/// Text of a property element, the items of arrays are joined with `; `
+fn element_value(raw: &str) -> Result<String> {
+    let mut reader = Reader::from_str(raw);
+    let mut items: Vec<String> = Vec::new();
+    let mut text = String::new();
+
+    loop {
+        match reader.read_event().map_err(|e| invalid(&e.to_string()))? {
+            Event::Start(tag) if tag.name().as_ref() == "rdf:li" => text.clear(),
+            Event::End(tag) if tag.name().as_ref() == "rdf:li" => items.push(text.clone()),
+            Event::Text(content) => text.push_str(&content.xml10_content()),
+            Event::CData(content) => text.push_str(&content.xml10_content()),
+            Event::GeneralRef(reference) => {
+                let name = reference.xml10_content();
+
+                match reference.resolve_char_ref() {
+                    Ok(Some(c)) => text.push(c),
+                    _ => text.push_str(resolve_predefined_entity(&name).unwrap_or_default()),
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    if items.is_empty() {
+        Ok(text.trim().to_string())
+    } else {
+        Ok(items.join("; "))
+    }
+}
+
+fn unescape(raw: &str) -> Result<String> {
+    quick_xml::escape::unescape(raw)
+        .map(|value| value.to_string())
+        .map_err(|e| invalid(&e.to_string()))
+}
+
+fn invalid(reason: &str) -> crate::Error {
+    Box::new(PngMeError::InvalidXmp(reason.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PACKET: &str = r#"<?xpacket begin="" id="W5M0MpCehiHzreSzNTczkc9d"?>
+<x:xmpmeta xmlns:x="adobe:ns:meta/">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about="" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:xmp="http://ns.adobe.com/xap/1.0/" xmp:Rating="4">
+   <dc:title><rdf:Alt><rdf:li xml:lang="x-default">Sunset &amp; sea</rdf:li></rdf:Alt></dc:title>
+   <dc:creator><rdf:Seq><rdf:li>Jane</rdf:li><rdf:li>John</rdf:li></rdf:Seq></dc:creator>
+   <!-- kept as is -->
+  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>
+<?xpacket end="w"?>"#;
+
+    #[test]
+    fn test_get() {
+        let xmp = Xmp::new(PACKET).unwrap();
+
+        assert_eq!(xmp.get("dc:title").unwrap().unwrap(), "Sunset & sea");
+        assert_eq!(xmp.get("dc:creator").unwrap().unwrap(), "Jane; John");
+        assert_eq!(xmp.get("xmp:Rating").unwrap().unwrap(), "4");
+        assert!(xmp.get("dc:subject").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_set_keeps_the_rest_of_the_packet() {
+        let mut xmp = Xmp::new(PACKET).unwrap();
+
+        xmp.set("dc:title", "Dawn", None).unwrap();
+        xmp.set("xmp:Rating", "5", None).unwrap();
+        xmp.set("acme:Project", "Launch", Some("http://acme.example/ns/"))
+            .unwrap();
+
+        assert_eq!(xmp.get("dc:title").unwrap().unwrap(), "Dawn");
+        assert_eq!(xmp.get("xmp:Rating").unwrap().unwrap(), "5");
+        assert_eq!(xmp.get("acme:Project").unwrap().unwrap(), "Launch");
+        assert_eq!(xmp.get("dc:creator").unwrap().unwrap(), "Jane; John");
+        assert!(xmp.packet().contains("<!-- kept as is -->"));
+        assert!(xmp
+            .packet()
+            .contains("xmlns:acme=\"http://acme.example/ns/\""));
+        assert!(xmp.set("unknown:Property", "value", None).is_err());
+    }
+
+    #[test]
+    fn test_set_language_alternative() {
+        let mut xmp = Xmp::new(PACKET).unwrap();
+
+        xmp.set("dc:title", "Sunset; sea", None).unwrap();
+
+        assert_eq!(xmp.get("dc:title").unwrap().unwrap(), "Sunset; sea");
+        assert_eq!(xmp.packet().matches("x-default").count(), 1);
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut xmp = Xmp::empty();
+
+        xmp.set("dc:subject", "sea; sunset", None).unwrap();
+        xmp.merge(&Xmp::new(PACKET).unwrap()).unwrap();
+
+        assert_eq!(xmp.get("dc:subject").unwrap().unwrap(), "sea; sunset");
+        assert_eq!(xmp.get("dc:title").unwrap().unwrap(), "Sunset & sea");
+        assert_eq!(xmp.get("xmp:Rating").unwrap().unwrap(), "4");
+    }
+
+    #[test]
+    fn test_invalid_xml() {
+        assert!(Xmp::new("<x:xmpmeta><rdf:RDF></x:xmpmeta>").is_err());
+        assert!(Xmp::new("<rdf:RDF><rdf:Description>").is_err());
+        assert!(Xmp::new("<x:xmpmeta/>").is_err());
+    }
+
+    #[test]
+    fn test_chunk_round_trip() {
+        let xmp = Xmp::new(PACKET).unwrap();
+        let mut png = Png::from_chunks(vec![Xmp::empty().to_chunk().unwrap()]);
+
+        xmp.write_to(&mut png).unwrap();
+
+        assert_eq!(png.chunks().len(), 1);
+        assert_eq!(Xmp::from_png(&png).unwrap().unwrap(), xmp);
+    }
+}
@@ -0,0 +1,171 @@
+//! Starts `pngme serve` and sends requests to it over localhost
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::process::{Child, Command, Stdio};
+
+use serde_json::Value;
+
+const DICE: &[u8] = include_bytes!("../docs/dice.png");
+
+/// A running server, stopped when dropped
+struct TestServer {
+    child: Child,
+    address: String,
+}
+
+impl TestServer {
+    fn start(args: &[&str]) -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_pngme"))
+            .args(["serve", "--bind", "127.0.0.1:0"])
+            .args(args)
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut line = String::new();
+
+        BufReader::new(child.stdout.as_mut().unwrap())
+            .read_line(&mut line)
+            .unwrap();
+
+        let address = line
+            .trim()
+            .strip_prefix("Listening on http://")
+            .unwrap()
+            .to_string();
+
+        TestServer { child, address }
+    }
+
+    /// Sends a request and returns the response status and body
+    fn request(&self, method: &str, path: &str, content_type: &str, body: &[u8]) -> (u16, Vec<u8>) {
+        let mut stream = TcpStream::connect(&self.address).unwrap();
+
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            method,
+            path,
+            self.address,
+            content_type,
+            body.len()
+        )
+        .unwrap();
+        stream.write_all(body).unwrap();
+
+        let mut response = Vec::new();
+
+        stream.read_to_end(&mut response).unwrap();
+
+        let headers_end = response
+            .windows(4)
+            .position(|window| window == b"\r\n\r\n")
+            .unwrap();
+        let status = String::from_utf8_lossy(&response[..headers_end])
+            .split(' ')
+            .nth(1)
+            .unwrap()
+            .parse()
+            .unwrap();
+
+        (status, response[headers_end + 4..].to_vec())
+    }
+
+    fn post(&self, path: &str, body: &[u8]) -> (u16, Vec<u8>) {
+        self.request("POST", path, "image/png", body)
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn json(body: &[u8]) -> Value {
+    serde_json::from_slice(body).unwrap()
+}
+
+#[test]
+fn test_encode_and_decode_raw_bodies() {
+    let server = TestServer::start(&[]);
+    let (status, encoded) = server.post("/encode?chunk_type=ruSt&message=Hello%20world", DICE);
+
+    assert_eq!(status, 200);
+    assert!(encoded.len() > DICE.len());
+
+    let (status, decoded) = server.post("/decode?chunk_type=ruSt", &encoded);
+
+    assert_eq!(status, 200);
+    assert_eq!(json(&decoded)["message"], "Hello world");
+
+    let (status, missing) = server.post("/decode?chunk_type=ruSt", DICE);
+
+    assert_eq!(status, 404);
+    assert_eq!(json(&missing)["error"], "Chunk type: ruSt not found");
+}
+
+#[test]
+fn test_encode_multipart_body() {
+    let server = TestServer::start(&[]);
+    let mut body = b"--XyZ\r\n\
+        Content-Disposition: form-data; name=\"chunk_type\"\r\n\r\n\
+        ruSt\r\n\
+        --XyZ\r\n\
+        Content-Disposition: form-data; name=\"message\"\r\n\r\n\
+        From a form\r\n\
+        --XyZ\r\n\
+        Content-Disposition: form-data; name=\"file\"; filename=\"dice.png\"\r\n\
+        Content-Type: image/png\r\n\r\n"
+        .to_vec();
+
+    body.extend_from_slice(DICE);
+    body.extend_from_slice(b"\r\n--XyZ--\r\n");
+
+    let (status, encoded) = server.request(
+        "POST",
+        "/encode",
+        "multipart/form-data; boundary=XyZ",
+        &body,
+    );
+
+    assert_eq!(status, 200);
+
+    let (_, decoded) = server.post("/decode?chunk_type=ruSt", &encoded);
+
+    assert_eq!(json(&decoded)["message"], "From a form");
+}
+
+#[test]
+fn test_inspect_and_strip() {
+    let server = TestServer::start(&[]);
+    let (_, encoded) = server.post("/encode?chunk_type=ruSt&message=secret", DICE);
+    let (status, listing) = server.post("/inspect", &encoded);
+    let listing = json(&listing);
+    let chunks = listing["chunks"].as_array().unwrap();
+
+    assert_eq!(status, 200);
+    assert_eq!(chunks[0]["type"], "IHDR");
+    assert_eq!(chunks[0]["length"], 13);
+    assert_eq!(chunks[chunks.len() - 2]["type"], "ruSt");
+
+    let (status, stripped) = server.post("/strip", &encoded);
+    let (_, listing) = server.post("/inspect", &stripped);
+
+    assert_eq!(status, 200);
+    assert!(json(&listing)["chunks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|chunk| chunk["type"] != "ruSt"));
+}
+
+#[test]
+fn test_rejected_requests() {
+    let server = TestServer::start(&["--max-body-size", "1024", "--max-concurrency", "1"]);
+
+    assert_eq!(server.post("/inspect", DICE).0, 413);
+    assert_eq!(server.post("/inspect", b"not a png").0, 400);
+    assert_eq!(server.post("/unknown", b"").0, 404);
+    assert_eq!(server.request("GET", "/inspect", "image/png", b"").0, 405);
+}